@@ -0,0 +1,264 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::models::KLineData;
+
+/// 复权方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Adjust {
+    /// 不复权
+    #[default]
+    None,
+    /// 前复权：以最新价格为基准向前调整历史价格
+    Forward,
+    /// 后复权：以上市首日为基准向后调整价格
+    Backward,
+}
+
+impl Adjust {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Adjust::None => "不复权",
+            Adjust::Forward => "前复权",
+            Adjust::Backward => "后复权",
+        }
+    }
+
+    /// 命令行 / 接口使用的代码: none, qfq, hfq
+    pub fn code(&self) -> &'static str {
+        match self {
+            Adjust::None => "none",
+            Adjust::Forward => "qfq",
+            Adjust::Backward => "hfq",
+        }
+    }
+
+    /// 从代码解析
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "none" => Some(Adjust::None),
+            "qfq" | "forward" => Some(Adjust::Forward),
+            "hfq" | "backward" => Some(Adjust::Backward),
+            _ => None,
+        }
+    }
+
+    /// 依次切换: 不复权 → 前复权 → 后复权
+    pub fn next(&self) -> Self {
+        match self {
+            Adjust::None => Adjust::Forward,
+            Adjust::Forward => Adjust::Backward,
+            Adjust::Backward => Adjust::None,
+        }
+    }
+}
+
+/// 是否支持复权（仅 A股）
+pub fn supports(symbol: &str) -> bool {
+    ["sh", "sz", "bj"].iter().any(|p| symbol.starts_with(p))
+}
+
+/// 后复权因子序列：每个除权日起的累计因子，后复权价 = 原始价 × 因子
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdjustFactors {
+    /// (除权日 YYYY-MM-DD, 因子)，按日期升序
+    points: Vec<(String, f64)>,
+}
+
+impl AdjustFactors {
+    /// 由 (日期, 因子) 列表创建（顺序不限）
+    pub fn new(mut points: Vec<(String, f64)>) -> Self {
+        points.sort_by(|a, b| a.0.cmp(&b.0));
+        Self { points }
+    }
+
+    /// 是否没有任何因子
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// 某日适用的因子（当日及之前最近一次除权的因子，首次除权前为最早的因子）
+    pub fn factor_at(&self, day: &str) -> f64 {
+        // 分钟K线的 day 带时间，只取日期部分
+        let date = day.get(..10).unwrap_or(day);
+        let idx = self.points.partition_point(|(d, _)| d.as_str() <= date);
+        match idx {
+            0 => self.points.first().map_or(1.0, |p| p.1),
+            i => self.points[i - 1].1,
+        }
+    }
+
+    /// 最新因子
+    pub fn latest(&self) -> f64 {
+        self.points.last().map_or(1.0, |p| p.1)
+    }
+
+    /// 对K线应用复权（只调整价格，成交量不变）
+    pub fn apply(&self, data: &[KLineData], mode: Adjust) -> Vec<KLineData> {
+        if mode == Adjust::None || self.is_empty() {
+            return data.to_vec();
+        }
+        let base = match mode {
+            Adjust::Forward => self.latest(),
+            _ => 1.0,
+        };
+        self.scale(data, base)
+    }
+
+    /// 以某日为基准的前复权：该日及之后的价格不变，只调整之前的价格（回放时不引入之后的除权）
+    pub fn forward_as_of(&self, data: &[KLineData], day: &str) -> Vec<KLineData> {
+        if self.is_empty() {
+            return data.to_vec();
+        }
+        self.scale(data, self.factor_at(day))
+    }
+
+    /// 按 因子 / base 调整价格
+    fn scale(&self, data: &[KLineData], base: f64) -> Vec<KLineData> {
+        data.iter()
+            .map(|k| {
+                let ratio = self.factor_at(&k.day) / base;
+                let adj = |v: f64| format!("{:.3}", v * ratio);
+                KLineData {
+                    day: k.day.clone(),
+                    open: adj(k.open_f64()),
+                    high: adj(k.high_f64()),
+                    low: adj(k.low_f64()),
+                    close: adj(k.close_f64()),
+                    volume: k.volume.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// 解析新浪后复权因子 (hfq.js)
+    ///
+    /// 格式: `var sh600519hfq={"total":2,"data":[{"d":"2024-06-19","f":"8.62"},...]}` (可能带注释)
+    pub fn parse_sina(text: &str) -> Result<Self> {
+        let start = text.find('{').context("复权因子格式错误: 未找到 {")?;
+        let end = text.rfind('}').context("复权因子格式错误: 未找到 }")?;
+        let json: Value =
+            serde_json::from_str(&text[start..=end]).context("解析复权因子 JSON 失败")?;
+        let points = json
+            .get("data")
+            .and_then(Value::as_array)
+            .context("复权因子缺少 data")?
+            .iter()
+            .filter_map(|item| {
+                let day = item.get("d")?.as_str()?;
+                let f = item.get("f")?;
+                let f = f.as_str().and_then(|s| s.parse().ok()).or(f.as_f64())?;
+                Some((day.get(..10).unwrap_or(day).to_string(), f))
+            })
+            .collect();
+        Ok(Self::new(points))
+    }
+
+    /// 读取导入的除权文件：每行 `日期,后复权因子`（可有表头，# 开头为注释）
+    pub fn load_csv(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("读取除权文件失败: {}", path.display()))?;
+        let mut points = Vec::new();
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (day, factor) = line
+                .split_once(',')
+                .with_context(|| format!("{} 第 {} 行格式错误", path.display(), i + 1))?;
+            match factor.trim().parse() {
+                Ok(f) => points.push((day.trim().to_string(), f)),
+                // 表头
+                Err(_) if i == 0 => continue,
+                Err(_) => anyhow::bail!("{} 第 {} 行因子无效: {}", path.display(), i + 1, factor),
+            }
+        }
+        Ok(Self::new(points))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(day: &str, close: f64) -> KLineData {
+        KLineData {
+            day: day.to_string(),
+            open: close.to_string(),
+            high: close.to_string(),
+            low: close.to_string(),
+            close: close.to_string(),
+            volume: "100".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_sina_factors() {
+        let text = r#"var sh600519hfq={"total":2,"data":[{"d":"2024-06-19","f":"2.0000"},{"d":"1990-01-01","f":"1.0000"}]}
+/* 2024-06-19 */"#;
+        let factors = AdjustFactors::parse_sina(text).unwrap();
+        assert_eq!(factors.factor_at("2024-06-18"), 1.0);
+        assert_eq!(factors.factor_at("2024-06-19"), 2.0);
+        assert_eq!(factors.factor_at("2024-06-20 10:30:00"), 2.0);
+        assert_eq!(factors.latest(), 2.0);
+    }
+
+    #[test]
+    fn test_apply_removes_ex_rights_gap() {
+        // 6-19 每 10 股送 10 股，价格从 20 跌到 10
+        let factors = AdjustFactors::new(vec![
+            ("1990-01-01".to_string(), 1.0),
+            ("2024-06-19".to_string(), 2.0),
+        ]);
+        let data = vec![kline("2024-06-18", 20.0), kline("2024-06-19", 10.0)];
+
+        let forward = factors.apply(&data, Adjust::Forward);
+        assert_eq!(
+            (forward[0].close_f64(), forward[1].close_f64()),
+            (10.0, 10.0)
+        );
+        let backward = factors.apply(&data, Adjust::Backward);
+        assert_eq!(
+            (backward[0].close_f64(), backward[1].close_f64()),
+            (20.0, 20.0)
+        );
+        let none = factors.apply(&data, Adjust::None);
+        assert_eq!(
+            (none[0].close.as_str(), none[1].close.as_str()),
+            ("20", "10")
+        );
+        assert_eq!(forward[0].volume, "100");
+    }
+
+    #[test]
+    fn test_forward_as_of_ignores_later_ex_rights() {
+        let factors = AdjustFactors::new(vec![
+            ("1990-01-01".to_string(), 1.0),
+            ("2024-06-19".to_string(), 2.0),
+        ]);
+        let data = vec![kline("2024-06-18", 20.0), kline("2024-06-19", 10.0)];
+        // 除权日之前为基准时价格不变
+        let before = factors.forward_as_of(&data[..1], "2024-06-18");
+        assert_eq!(before[0].close_f64(), 20.0);
+        let after = factors.forward_as_of(&data, "2024-06-19");
+        assert_eq!((after[0].close_f64(), after[1].close_f64()), (10.0, 10.0));
+    }
+
+    #[test]
+    fn test_load_csv() {
+        let path =
+            std::env::temp_dir().join(format!("stock-tui-adjust-{}.csv", std::process::id()));
+        fs::write(&path, "date,factor\n# 导入\n2024-06-19,2.0\n1990-01-01,1\n").unwrap();
+        let factors = AdjustFactors::load_csv(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(factors.factor_at("2000-01-01"), 1.0);
+        assert_eq!(factors.latest(), 2.0);
+    }
+}
@@ -1,37 +1,327 @@
+use std::sync::OnceLock;
+
 use anyhow::{Context, Result};
 use encoding_rs::GBK;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-use crate::models::{KLineData, StockQuote};
+use crate::adjust::AdjustFactors;
+use crate::board::{self, BoardKind, Sector};
+use crate::http::{Endpoint, EndpointStats, HttpClient};
+use crate::models::{IndexQuote, KLineData, PriceLevel, QuoteExt, StockQuote};
+use crate::profile::CompanyProfile;
 
 const REALTIME_URL: &str = "http://hq.sinajs.cn/list=";
 const KLINE_URL_CN: &str =
     "http://money.finance.sina.com.cn/quotes_service/api/json_v2.php/CN_MarketData.getKLineData";
 const KLINE_URL_US: &str =
     "http://stock.finance.sina.com.cn/usstock/api/jsonp.php/IO/US_MinKService.getDailyK";
+const HFQ_URL: &str = "http://finance.sina.com.cn/realstock/company/{symbol}/hfq.js";
+const PROFILE_URL: &str = "http://finance.sina.com.cn/realstock/company/{symbol}/jsvar.js";
+const CORP_INFO_URL: &str =
+    "http://vip.stock.finance.sina.com.cn/corp/go.php/vCI_CorpOtherInfo/stockid/{code}/menu_num/2.phtml";
+const BONUS_URL: &str =
+    "http://vip.stock.finance.sina.com.cn/corp/go.php/vISSUE_ShareBonus/stockid/{code}.phtml";
+const INDUSTRY_URL: &str = "http://vip.stock.finance.sina.com.cn/q/view/newSinaHy.php";
+const CONCEPT_URL: &str = "http://money.finance.sina.com.cn/q/view/newFLJK.php?param=class";
+const NODE_URL: &str =
+    "http://vip.stock.finance.sina.com.cn/quotes_service/api/json_v2.php/Market_Center.getHQNodeData";
+
+/// 新浪接口设置（config.json 中的 `sina` 项），可将接口地址指向镜像或测试服务器
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SinaConfig {
+    /// 实时行情地址前缀，请求时直接拼接股票代码
+    pub realtime_url: String,
+    /// A股K线接口地址
+    pub kline_cn_url: String,
+    /// 美股日K线 JSONP 接口地址
+    pub kline_us_url: String,
+    /// A股后复权因子地址，`{symbol}` 替换为股票代码
+    pub hfq_url: String,
+    /// A股公司资料（股本、每股收益等）地址，`{symbol}` 替换为股票代码
+    pub profile_url: String,
+    /// A股所属行业与概念板块页面地址，`{code}` 替换为不带市场前缀的代码
+    pub corp_info_url: String,
+    /// A股分红送配页面地址，`{code}` 替换为不带市场前缀的代码
+    pub bonus_url: String,
+    /// 行业板块列表地址
+    pub industry_url: String,
+    /// 概念板块列表地址
+    pub concept_url: String,
+    /// 行情中心分页列表地址（板块成分股、沪深A股等）
+    pub node_url: String,
+    /// 连接超时（毫秒）
+    pub connect_timeout_ms: u64,
+    /// 请求超时（毫秒，含读取响应）
+    pub timeout_ms: u64,
+    /// 连接失败、超时或 429/5xx 时的最大重试次数
+    pub retries: u32,
+    /// 首次重试前的等待（毫秒），之后每次翻倍并加随机抖动
+    pub backoff_ms: u64,
+    /// HTTP(S) 代理地址，如 `http://127.0.0.1:7890`；未设置时使用环境变量 HTTP_PROXY / HTTPS_PROXY
+    pub proxy: Option<String>,
+}
 
-/// 从新浪财经获取实时行情
-pub fn fetch_realtime_quote(symbol: &str) -> Result<StockQuote> {
-    let url = format!("{}{}", REALTIME_URL, symbol);
-    let client = reqwest::blocking::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "http://finance.sina.com.cn")
-        .send()
-        .context("请求实时行情失败")?;
+impl Default for SinaConfig {
+    fn default() -> Self {
+        Self {
+            realtime_url: REALTIME_URL.to_string(),
+            kline_cn_url: KLINE_URL_CN.to_string(),
+            kline_us_url: KLINE_URL_US.to_string(),
+            hfq_url: HFQ_URL.to_string(),
+            profile_url: PROFILE_URL.to_string(),
+            corp_info_url: CORP_INFO_URL.to_string(),
+            bonus_url: BONUS_URL.to_string(),
+            industry_url: INDUSTRY_URL.to_string(),
+            concept_url: CONCEPT_URL.to_string(),
+            node_url: NODE_URL.to_string(),
+            connect_timeout_ms: 3_000,
+            timeout_ms: 10_000,
+            retries: 2,
+            backoff_ms: 200,
+            proxy: None,
+        }
+    }
+}
 
-    let bytes = resp.bytes().context("读取响应数据失败")?;
+impl SinaConfig {
+    /// 实时行情请求地址
+    pub fn quote_url(&self, symbol: &str) -> String {
+        format!("{}{}", self.realtime_url, symbol)
+    }
 
-    // 新浪实时行情接口返回 GBK 编码
-    let (decoded, _, _) = GBK.decode(&bytes);
-    let text = decoded.to_string();
+    /// K线请求地址，暂不支持的市场（港股）返回 None
+    pub fn kline_url(&self, symbol: &str, scale: u32, datalen: u32) -> Option<String> {
+        if let Some(raw_symbol) = symbol.strip_prefix("gb_") {
+            // gb_aapl -> aapl
+            Some(format!("{}?symbol={}", self.kline_us_url, raw_symbol))
+        } else if symbol.starts_with("hk") {
+            None
+        } else {
+            Some(format!(
+                "{}?symbol={}&scale={}&ma=no&datalen={}",
+                self.kline_cn_url, symbol, scale, datalen
+            ))
+        }
+    }
+}
+
+/// 一次请求的地址和原始响应（已完成 GBK 解码），录制会话时保存
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    /// 请求地址
+    pub url: String,
+    /// 响应正文
+    pub body: String,
+}
+
+/// A股公司资料的各个页面
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfilePages {
+    /// 个股变量脚本（股本、每股收益等）
+    pub jsvar: Page,
+    /// 所属行业与概念板块页面
+    pub corp_info: Option<Page>,
+    /// 分红送配页面
+    pub bonus: Option<Page>,
+}
+
+impl ProfilePages {
+    /// 解析为公司资料
+    pub fn parse(&self) -> Result<CompanyProfile> {
+        let mut profile = CompanyProfile::parse_sina_jsvar(&self.jsvar.body)?;
+        if let Some(page) = &self.corp_info {
+            profile.parse_sina_corp_info(&page.body);
+        }
+        if let Some(page) = &self.bonus {
+            profile.parse_sina_bonus(&page.body);
+        }
+        Ok(profile)
+    }
+}
 
-    parse_realtime_quote(symbol, &text)
+/// 新浪接口客户端：按 [`SinaConfig`] 请求，所有请求共用一个 [`HttpClient`]
+pub struct SinaClient {
+    config: SinaConfig,
+    http: HttpClient,
+}
+
+impl SinaClient {
+    /// 创建客户端（代理地址无效时返回错误）
+    pub fn new(config: SinaConfig) -> Result<Self> {
+        let http = HttpClient::new(&config)?;
+        Ok(Self { config, http })
+    }
+
+    /// 接口设置
+    pub fn config(&self) -> &SinaConfig {
+        &self.config
+    }
+
+    /// 各接口的请求统计
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.http.stats()
+    }
+
+    /// 获取实时行情原始响应（已完成 GBK 解码）
+    pub fn fetch_quote_body(&self, symbol: &str) -> Result<String> {
+        let url = self.config.quote_url(symbol);
+        self.get_text(Endpoint::Realtime, &url)
+            .context("请求实时行情失败")
+    }
+
+    /// 获取K线原始响应，暂不支持的市场返回 None
+    pub fn fetch_kline_body(
+        &self,
+        symbol: &str,
+        scale: u32,
+        datalen: u32,
+    ) -> Result<Option<String>> {
+        let endpoint = if symbol.starts_with("gb_") {
+            Endpoint::KlineUs
+        } else {
+            Endpoint::KlineCn
+        };
+        self.config
+            .kline_url(symbol, scale, datalen)
+            .map(|url| self.get_text(endpoint, &url).context("请求K线数据失败"))
+            .transpose()
+    }
+
+    /// 获取A股后复权因子原始响应
+    pub fn fetch_factors_body(&self, symbol: &str) -> Result<Page> {
+        let url = self.config.hfq_url.replace("{symbol}", symbol);
+        let body = self
+            .get_text(Endpoint::Factors, &url)
+            .context("请求复权因子失败")?;
+        Ok(Page { url, body })
+    }
+
+    /// 获取A股后复权因子
+    pub fn fetch_adjust_factors(&self, symbol: &str) -> Result<AdjustFactors> {
+        AdjustFactors::parse_sina(&self.fetch_factors_body(symbol)?.body)
+    }
+
+    /// 获取A股公司资料的各个页面，行业和分红页面获取失败时为 None
+    pub fn fetch_profile_pages(&self, symbol: &str) -> Result<ProfilePages> {
+        let url = self.config.profile_url.replace("{symbol}", symbol);
+        let body = self
+            .get_text(Endpoint::Profile, &url)
+            .context("请求公司资料失败")?;
+        let jsvar = Page { url, body };
+
+        let code = symbol.get(2..).unwrap_or(symbol);
+        let page = |template: &str| {
+            let url = template.replace("{code}", code);
+            let body = self.get_text(Endpoint::Profile, &url).ok()?;
+            Some(Page { url, body })
+        };
+        Ok(ProfilePages {
+            jsvar,
+            corp_info: page(&self.config.corp_info_url),
+            bonus: page(&self.config.bonus_url),
+        })
+    }
+
+    /// 获取A股公司资料：股本和每股指标来自个股变量脚本，行业、概念板块和分红来自公司资料页
+    ///
+    /// 行业和分红页面获取失败时只缺少对应字段，不影响其他资料。
+    pub fn fetch_profile(&self, symbol: &str) -> Result<CompanyProfile> {
+        self.fetch_profile_pages(symbol)?.parse()
+    }
+
+    /// 获取并解析实时行情
+    pub fn fetch_realtime_quote(&self, symbol: &str) -> Result<StockQuote> {
+        parse_realtime_quote(symbol, &self.fetch_quote_body(symbol)?)
+    }
+
+    /// 获取行业或概念板块列表（按成交额从大到小）
+    pub fn fetch_boards(&self, kind: BoardKind) -> Result<Vec<Sector>> {
+        board::parse_sina_boards(&self.fetch_boards_body(kind)?.body)
+    }
+
+    /// 获取板块列表原始响应
+    pub fn fetch_boards_body(&self, kind: BoardKind) -> Result<Page> {
+        let url = match kind {
+            BoardKind::Industry => &self.config.industry_url,
+            BoardKind::Concept => &self.config.concept_url,
+        };
+        let body = self
+            .get_text(Endpoint::Market, url)
+            .context("请求板块列表失败")?;
+        Ok(Page {
+            url: url.clone(),
+            body,
+        })
+    }
+
+    /// 获取行情中心某节点（板块代码或 hs_a 等）第 page 页的行情，按成交额从大到小
+    pub fn fetch_node_quotes(
+        &self,
+        node: &str,
+        page: u32,
+        per_page: u32,
+    ) -> Result<Vec<StockQuote>> {
+        board::parse_node_quotes(&self.fetch_node_body(node, page, per_page)?.body)
+    }
+
+    /// 获取行情中心某节点第 page 页的原始响应
+    pub fn fetch_node_body(&self, node: &str, page: u32, per_page: u32) -> Result<Page> {
+        let url = format!(
+            "{}?page={}&num={}&sort=amount&asc=0&node={}",
+            self.config.node_url, page, per_page, node
+        );
+        let body = self
+            .get_text(Endpoint::Market, &url)
+            .context("请求行情列表失败")?;
+        Ok(Page { url, body })
+    }
+
+    /// 获取并解析指数行情（支持 `s_` 简版、`int_` 全球指数和普通行情格式）
+    pub fn fetch_index_quote(&self, symbol: &str) -> Result<IndexQuote> {
+        parse_index_quote(symbol, &self.fetch_quote_body(symbol)?)
+    }
+
+    /// 获取并解析K线数据
+    pub fn fetch_kline_data(
+        &self,
+        symbol: &str,
+        scale: u32,
+        datalen: u32,
+    ) -> Result<Vec<KLineData>> {
+        match self.fetch_kline_body(symbol, scale, datalen)? {
+            Some(text) => parse_kline_data(symbol, &text),
+            // 暂时不支持港股K线，返回空列表以免报错
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// 请求新浪接口并返回解码后的响应文本
+    fn get_text(&self, endpoint: Endpoint, url: &str) -> Result<String> {
+        let bytes = self.http.get(endpoint, url, "http://finance.sina.com.cn")?;
+        // 新浪实时行情接口返回 GBK 编码（K线接口为 ASCII JSON，按 GBK 解码不受影响）
+        let (decoded, _, _) = GBK.decode(&bytes);
+        Ok(decoded.into_owned())
+    }
+}
+
+/// 默认设置的共享客户端（供下方的便捷函数使用）
+fn default_client() -> &'static SinaClient {
+    static CLIENT: OnceLock<SinaClient> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        SinaClient::new(SinaConfig::default()).expect("默认设置不含代理，创建客户端不会失败")
+    })
+}
+
+/// 从新浪财经获取实时行情（默认接口地址）
+pub fn fetch_realtime_quote(symbol: &str) -> Result<StockQuote> {
+    default_client().fetch_realtime_quote(symbol)
 }
 
 /// 解析实时行情数据
 /// 格式: var hq_str_sh600519="贵州茅台,1731.50,...";
-fn parse_realtime_quote(symbol: &str, text: &str) -> Result<StockQuote> {
+pub fn parse_realtime_quote(symbol: &str, text: &str) -> Result<StockQuote> {
     // 提取引号内的数据
     let start = text.find('"').context("行情数据格式错误: 未找到引号")? + 1;
     let end = text
@@ -70,9 +360,30 @@ fn parse_cn_quote(symbol: &str, fields: &[&str]) -> Result<StockQuote> {
         turnover: fields[9].parse().unwrap_or(0.0),
         date: fields[30].to_string(),
         time: fields[31].to_string(),
+        // fields[10..20] 买一~买五 (量, 价)，fields[20..30] 卖一~卖五 (量, 价)
+        bids: parse_levels(&fields[10..20]),
+        asks: parse_levels(&fields[20..30]),
+        // A股行情不含股本、市盈率等数据，由 feed::QuoteEnricher 根据公司资料和日K补充
+        ext: QuoteExt::default(),
     })
 }
 
+/// 解析为正数，0 或无效值视为接口未提供
+fn positive(field: &str) -> Option<f64> {
+    field.parse().ok().filter(|v: &f64| *v > 0.0)
+}
+
+/// 解析 (量, 价) 交替排列的盘口字段
+fn parse_levels(fields: &[&str]) -> Vec<PriceLevel> {
+    fields
+        .chunks_exact(2)
+        .map(|pair| PriceLevel {
+            volume: pair[0].parse().unwrap_or(0.0),
+            price: pair[1].parse().unwrap_or(0.0),
+        })
+        .collect()
+}
+
 fn parse_hk_quote(symbol: &str, fields: &[&str]) -> Result<StockQuote> {
     if fields.len() < 19 {
         anyhow::bail!("港股行情数据字段不足: 期望19+，实际{}", fields.len());
@@ -91,6 +402,22 @@ fn parse_hk_quote(symbol: &str, fields: &[&str]) -> Result<StockQuote> {
         turnover: fields[11].parse().unwrap_or(0.0),
         date: fields[17].replace('/', "-"), // 2026/02/11 -> 2026-02-11
         time: fields[18].to_string(),
+        // fields[9] 买一价, fields[10] 卖一价（无挂单量）
+        bids: vec![PriceLevel {
+            price: fields[9].parse().unwrap_or(0.0),
+            volume: 0.0,
+        }],
+        asks: vec![PriceLevel {
+            price: fields[10].parse().unwrap_or(0.0),
+            volume: 0.0,
+        }],
+        // fields[13] 市盈率, fields[15] / fields[16] 52周最高 / 最低
+        ext: QuoteExt {
+            pe_ttm: positive(fields[13]),
+            week52_high: positive(fields[15]),
+            week52_low: positive(fields[16]),
+            ..QuoteExt::default()
+        },
     })
 }
 
@@ -106,6 +433,19 @@ fn parse_us_quote(symbol: &str, fields: &[&str]) -> Result<StockQuote> {
         (datetime.to_string(), "".to_string())
     };
 
+    // fields[8] / fields[9] 52周最高 / 最低, fields[11] 10日均量, fields[12] 总市值,
+    // fields[14] 市盈率, fields[19] 总股本
+    let volume: f64 = fields[10].parse().unwrap_or(0.0);
+    let ext = QuoteExt {
+        turnover_rate: positive(fields[19]).map(|shares| volume / shares * 100.0),
+        volume_ratio: positive(fields[11]).map(|avg| volume / avg),
+        week52_high: positive(fields[8]),
+        week52_low: positive(fields[9]),
+        pe_ttm: positive(fields[14]),
+        market_cap: positive(fields[12]),
+        float_market_cap: None,
+    };
+
     Ok(StockQuote {
         name: fields[0].to_string(),
         symbol: symbol.to_string(),
@@ -114,55 +454,61 @@ fn parse_us_quote(symbol: &str, fields: &[&str]) -> Result<StockQuote> {
         open: fields[5].parse().unwrap_or(0.0),
         high: fields[6].parse().unwrap_or(0.0),
         low: fields[7].parse().unwrap_or(0.0),
-        volume: fields[10].parse().unwrap_or(0.0),
+        volume,
         turnover: 0.0, // 美股接口通常不返回成交额
         pre_close: fields[26].parse().unwrap_or(0.0),
         date,
         time,
+        bids: Vec::new(),
+        asks: Vec::new(),
+        ext,
     })
 }
 
-/// 获取K线数据
-pub fn fetch_kline_data(symbol: &str, scale: u32, datalen: u32) -> Result<Vec<KLineData>> {
-    if symbol.starts_with("gb_") {
-        return fetch_us_kline(symbol, scale, datalen);
-    } else if symbol.starts_with("hk") {
-        // 暂时不支持港股K线，返回空列表以免报错
-        return Ok(Vec::new());
+/// 解析指数行情
+///
+/// - `s_sh000001="上证指数,3250.12,12.34,0.38,成交量(手),成交额(万元)"`
+/// - `int_dji="道琼斯,44000.12,120.50,0.27"`
+/// - 其他代码（如 hkHSI）按普通行情格式解析
+pub fn parse_index_quote(symbol: &str, text: &str) -> Result<IndexQuote> {
+    if !symbol.starts_with("s_") && !symbol.starts_with("int_") {
+        return parse_realtime_quote(symbol, text).map(IndexQuote::from);
     }
-
-    let url = format!(
-        "{}?symbol={}&scale={}&ma=no&datalen={}",
-        KLINE_URL_CN, symbol, scale, datalen
-    );
-
-    let client = reqwest::blocking::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "http://finance.sina.com.cn")
-        .send()
-        .context("请求K线数据失败")?;
-
-    let text = resp.text().context("读取K线数据失败")?;
-    let klines: Vec<KLineData> = serde_json::from_str(&text).context("解析K线 JSON 失败")?;
-
-    Ok(klines)
+    let start = text.find('"').context("指数数据格式错误: 未找到引号")? + 1;
+    let end = text
+        .rfind('"')
+        .context("指数数据格式错误: 未找到结束引号")?;
+    if start >= end {
+        anyhow::bail!("指数数据为空，可能是无效的代码: {}", symbol);
+    }
+    let fields: Vec<&str> = text[start..end].split(',').collect();
+    if fields.len() < 4 {
+        anyhow::bail!("指数数据字段不足: 期望4+，实际{}", fields.len());
+    }
+    Ok(IndexQuote {
+        symbol: symbol.to_string(),
+        name: fields[0].to_string(),
+        current: fields[1].parse().unwrap_or(0.0),
+        change: fields[2].parse().unwrap_or(0.0),
+        change_percent: fields[3].parse().unwrap_or(0.0),
+    })
 }
 
-fn fetch_us_kline(symbol: &str, _scale: u32, _datalen: u32) -> Result<Vec<KLineData>> {
-    // gb_aapl -> aapl
-    let raw_symbol = symbol.trim_start_matches("gb_");
-    let url = format!("{}?symbol={}", KLINE_URL_US, raw_symbol);
-
-    let client = reqwest::blocking::Client::new();
-    let resp = client
-        .get(&url)
-        .header("Referer", "http://finance.sina.com.cn")
-        .send()
-        .context("请求美股K线数据失败")?;
+/// 获取K线数据（默认接口地址）
+pub fn fetch_kline_data(symbol: &str, scale: u32, datalen: u32) -> Result<Vec<KLineData>> {
+    default_client().fetch_kline_data(symbol, scale, datalen)
+}
 
-    let text = resp.text().context("读取美股K线数据失败")?;
+/// 解析K线接口响应（A股为 JSON，美股为 JSONP）
+pub fn parse_kline_data(symbol: &str, text: &str) -> Result<Vec<KLineData>> {
+    if symbol.starts_with("gb_") {
+        parse_us_kline(text)
+    } else {
+        serde_json::from_str(text).context("解析K线 JSON 失败")
+    }
+}
 
+fn parse_us_kline(text: &str) -> Result<Vec<KLineData>> {
     // 解析 JSONP: IO({...}) 或 IO([...])
     let start_idx = text.find("IO(").context("解析美股K线失败: 未找到 IO(")? + 3;
     let json_str = text[start_idx..]
@@ -208,6 +554,154 @@ pub fn fetch_multiple_quotes(symbols: &[String]) -> Vec<Result<StockQuote>> {
     symbols.iter().map(|s| fetch_realtime_quote(s)).collect()
 }
 
+/// 测试用的新浪接口模拟服务器
+#[cfg(test)]
+pub(crate) mod mock {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use encoding_rs::GBK;
+
+    use super::SinaConfig;
+
+    /// 与新浪一致：缺少正确 Referer 的请求返回 403
+    pub const REFERER: &str = "http://finance.sina.com.cn";
+
+    /// 慢响应的延迟
+    pub const SLOW: Duration = Duration::from_millis(500);
+
+    /// 在本地随机端口启动模拟服务器，返回指向它的接口设置
+    ///
+    /// - `/list=sh600519` `hk00700` `gb_aapl`: GBK 编码的实时行情
+    /// - `/list=s_sh000001`: 简版指数行情
+    /// - `/list=sh000000`: 空行情；`/list=sh000009`: 延迟 [`SLOW`] 后返回
+    /// - `/cn?symbol=sh600519`: K线 JSON；`/cn?symbol=sz000001`: 截断的 JSON
+    /// - `/us?symbol=aapl`: JSONP `IO([...]);`
+    /// - `/hfq/sh600519`: 后复权因子；`/jsvar/sh600519`: 公司资料
+    /// - `/corp/600519`: 所属行业与概念板块；`/bonus/600519`: 分红送配
+    /// - `/industry`: 行业板块列表；`/node?...&node=new_blhy`: 板块成分股
+    /// - `/list=sh000503`: 始终 503；`/list=sz000503`: 首次 503，之后同 sh600519
+    pub fn start(referer: &'static str) -> SinaConfig {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let flaky_hits = Arc::new(AtomicUsize::new(0));
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let flaky_hits = Arc::clone(&flaky_hits);
+                thread::spawn(move || handle(stream, referer, &flaky_hits));
+            }
+        });
+        SinaConfig {
+            realtime_url: format!("{}/list=", base),
+            kline_cn_url: format!("{}/cn", base),
+            kline_us_url: format!("{}/us", base),
+            hfq_url: format!("{}/hfq/{{symbol}}", base),
+            profile_url: format!("{}/jsvar/{{symbol}}", base),
+            corp_info_url: format!("{}/corp/{{code}}", base),
+            bonus_url: format!("{}/bonus/{{code}}", base),
+            industry_url: format!("{}/industry", base),
+            concept_url: format!("{}/concept", base),
+            node_url: format!("{}/node", base),
+            timeout_ms: 2_000,
+            backoff_ms: 10,
+            ..SinaConfig::default()
+        }
+    }
+
+    fn handle(mut stream: TcpStream, referer: &str, flaky_hits: &AtomicUsize) {
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut has_referer = false;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).unwrap() == 0 || header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                has_referer |= name.eq_ignore_ascii_case("referer") && value.trim() == referer;
+            }
+        }
+
+        let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+        let (status, body) = if !has_referer {
+            ("403 Forbidden", b"Kinsoku jikou desu!".to_vec())
+        } else if path == "/list=sz000503" && flaky_hits.fetch_add(1, Ordering::SeqCst) == 0 {
+            ("503 Service Unavailable", Vec::new())
+        } else {
+            route(path.replace("sz000503", "sh600519").as_str())
+        };
+        let _ = write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            status,
+            body.len()
+        );
+        let _ = stream.write_all(&body);
+    }
+
+    fn route(path: &str) -> (&'static str, Vec<u8>) {
+        let (route, query) = path.split_once('?').unwrap_or((path, ""));
+        let symbol = query
+            .split('&')
+            .find_map(|kv| kv.strip_prefix("symbol="))
+            .unwrap_or_default();
+        let text = match (route, symbol) {
+            ("/list=sh600519", _) => {
+                r#"var hq_str_sh600519="贵州茅台,1731.500,1732.000,1755.000,1760.000,1728.000,1754.980,1755.000,25432100,44539876543.000,100,1754.980,200,1754.970,300,1754.960,400,1754.950,500,1754.940,100,1755.000,200,1755.010,300,1755.020,400,1755.030,500,1755.040,2025-02-11,15:00:00,00,";"#
+            }
+            ("/list=hk00700", _) => {
+                r#"var hq_str_hk00700="TENCENT,腾讯控股,543.000,551.000,550.500,543.000,548.000,-3.000,-0.544,547.50000,548.00000,12991880860,23759058,0.000,0.000,683.000,415.374,2026/02/11,16:08";"#
+            }
+            ("/list=gb_aapl", _) => {
+                r#"var hq_str_gb_aapl="苹果,276.0800,0.88,2026-02-12 04:17:52,2.4000,274.6950,280.1800,274.4500,288.6200,168.4300,37329226,61226827,4053169131200,7.93,34.810000,0.00,0.00,0.26,0.00,14681140000,63,0.0000,0.00,0.00,,Feb 11 03:17PM EST,273.6800,0,1,2026,10353387124.0000,0.0000,0.0000,0.0000,0.0000,273.6800";"#
+            }
+            ("/list=s_sh000001", _) => {
+                r#"var hq_str_s_sh000001="上证指数,3250.1234,12.3400,0.38,3456789,45678901";"#
+            }
+            ("/list=sh000000", _) => r#"var hq_str_sh000000="";"#,
+            ("/list=sh000503", _) => return ("503 Service Unavailable", Vec::new()),
+            ("/list=sh000009", _) => {
+                thread::sleep(SLOW);
+                r#"var hq_str_sh000009="";"#
+            }
+            ("/cn", "sh600519") => {
+                r#"[{"day":"2025-02-10","open":"1720.000","high":"1740.000","low":"1715.000","close":"1732.000","volume":"2100000"},{"day":"2025-02-11","open":"1731.500","high":"1760.000","low":"1728.000","close":"1755.000","volume":"2543210"}]"#
+            }
+            ("/cn", "sz000001") => r#"[{"day":"2025-02-10","open":"11.0"#,
+            ("/hfq/sh600519", _) => {
+                r#"var sh600519hfq={"total":2,"data":[{"d":"2024-06-19","f":"8.6207"},{"d":"2001-08-27","f":"1.0000"}]}
+/* 前复权因子 */"#
+            }
+            ("/industry", _) => {
+                r#"var S_Finance_bankuai_sinaindustry = {"new_blhy":"new_blhy,玻璃行业,19,19.30,0.12,0.62,110637283,1347396427,sh600586,3.46,6.89,0.22,金晶科技"}"#
+            }
+            ("/node", _) if query.contains("node=new_blhy") => {
+                r#"[{"symbol":"sh600586","name":"金晶科技","trade":"6.890","settlement":"6.670","volume":12345600,"amount":84512345}]"#
+            }
+            ("/jsvar/sh600519", _) => {
+                "var lastfive = 1720.05;\nvar totalcapital = 125619.78;\nvar currcapital = 125619.78;\nvar fourQ_mgsy = 68.64;\nvar mgjzc = 205.28;"
+            }
+            ("/corp/600519", _) => {
+                r#"<table><tr><th>所属行业板块</th></tr><tr><td>酿酒行业</td></tr><tr><th>所属概念板块</th></tr><tr><td>白酒</td></tr></table>"#
+            }
+            ("/bonus/600519", _) => {
+                r#"<table><tr><td>2025-06-19</td><td>0</td><td>0</td><td>276.24</td><td>实施</td><td>2025-06-26</td></tr></table>"#
+            }
+            ("/us", "aapl") => {
+                r#"IO([{"d":"2026-02-10 16:00:00","o":"274.00","h":"277.00","l":"273.00","c":"273.68","v":"41000000"},{"d":"2026-02-11","o":"274.70","h":"280.18","l":"274.45","c":"276.08","v":"37329226"}]);"#
+            }
+            _ => return ("404 Not Found", Vec::new()),
+        };
+        let (bytes, _, _) = GBK.encode(text);
+        ("200 OK", bytes.into_owned())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,6 +712,15 @@ mod tests {
         let q = parse_realtime_quote("sh600519", raw).unwrap();
         assert_eq!(q.name, "贵州茅台");
         assert_eq!(q.current, 1755.0);
+        assert_eq!(q.bids.len(), 5);
+        assert_eq!(
+            q.bids[0],
+            PriceLevel {
+                price: 1754.98,
+                volume: 100.0
+            }
+        );
+        assert_eq!(q.asks[4].price, 1755.04);
     }
 
     #[test]
@@ -229,6 +732,8 @@ mod tests {
         assert_eq!(q.pre_close, 551.0);
         assert_eq!(q.current, 548.0);
         assert_eq!(q.date, "2026-02-11");
+        assert_eq!(q.ext.week52_high, Some(683.0));
+        assert_eq!(q.ext.pe_ttm, None);
     }
 
     #[test]
@@ -241,5 +746,118 @@ mod tests {
         assert_eq!(q.pre_close, 273.68);
         assert_eq!(q.date, "2026-02-12");
         assert_eq!(q.time, "04:17:52");
+        assert_eq!(q.ext.pe_ttm, Some(34.81));
+        assert_eq!(q.ext.market_cap, Some(4053169131200.0));
+        assert_eq!(q.ext.week52_low, Some(168.43));
+        assert!((q.ext.turnover_rate.unwrap() - 0.2543).abs() < 1e-3);
+        assert!((q.ext.volume_ratio.unwrap() - 0.6097).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_parse_index_quotes() {
+        let q = parse_index_quote(
+            "int_dji",
+            r#"var hq_str_int_dji="道琼斯,44000.12,-120.50,-0.27";"#,
+        )
+        .unwrap();
+        assert_eq!(q.name, "道琼斯");
+        assert_eq!(q.current, 44000.12);
+        assert_eq!(q.change_percent, -0.27);
+
+        let raw = r#"var hq_str_hk00700="TENCENT,腾讯控股,543.000,551.000,550.500,543.000,548.000,-3.000,-0.544,547.50000,548.00000,12991880860,23759058,0.000,0.000,683.000,415.374,2026/02/11,16:08";"#;
+        let q = parse_index_quote("hk00700", raw).unwrap();
+        assert_eq!(q.change, -3.0);
+        assert!(parse_index_quote("s_sh000000", r#"var hq_str_s_sh000000="";"#).is_err());
+    }
+
+    #[test]
+    fn test_fetch_from_mock_server() {
+        let sina = SinaClient::new(mock::start(mock::REFERER)).unwrap();
+
+        let q = sina.fetch_realtime_quote("sh600519").unwrap();
+        assert_eq!(q.name, "贵州茅台");
+        assert_eq!(q.asks[0].price, 1755.0);
+        assert_eq!(
+            sina.fetch_realtime_quote("hk00700").unwrap().name,
+            "腾讯控股"
+        );
+        assert_eq!(sina.fetch_realtime_quote("gb_aapl").unwrap().name, "苹果");
+        let index = sina.fetch_index_quote("s_sh000001").unwrap();
+        assert_eq!(
+            (index.name.as_str(), index.change_percent),
+            ("上证指数", 0.38)
+        );
+
+        let klines = sina.fetch_kline_data("sh600519", 240, 2).unwrap();
+        assert_eq!(klines.len(), 2);
+        assert_eq!(klines[1].close, "1755.000");
+        // JSONP 去壳，日期去掉时间部分
+        let us = sina.fetch_kline_data("gb_aapl", 240, 2).unwrap();
+        assert_eq!(us[0].day, "2026-02-10");
+        assert_eq!(us[1].close, "276.08");
+        assert!(sina.fetch_kline_data("hk00700", 240, 2).unwrap().is_empty());
+        let factors = sina.fetch_adjust_factors("sh600519").unwrap();
+        assert_eq!(factors.latest(), 8.6207);
+        let profile = sina.fetch_profile("sh600519").unwrap();
+        assert_eq!(profile.eps, Some(68.64));
+        assert_eq!(profile.industry.as_deref(), Some("酿酒行业"));
+        assert_eq!(profile.sectors, vec!["白酒"]);
+        assert_eq!(profile.dividends[0].plan, "10派276.24元");
+        let boards = sina.fetch_boards(BoardKind::Industry).unwrap();
+        assert_eq!(boards[0].name, "玻璃行业");
+        let stocks = sina.fetch_node_quotes(&boards[0].code, 1, 40).unwrap();
+        assert_eq!(stocks[0].symbol, "sh600586");
+
+        let stats = sina.stats();
+        assert_eq!(stats[0].endpoint, Endpoint::Realtime);
+        assert_eq!((stats[0].requests, stats[0].errors), (4, 0));
+        assert_eq!((stats[1].requests, stats[2].requests), (1, 1));
+    }
+
+    #[test]
+    fn test_fetch_failures_from_mock_server() {
+        let config = mock::start(mock::REFERER);
+        let sina = SinaClient::new(config.clone()).unwrap();
+
+        let err = sina.fetch_realtime_quote("sh000000").unwrap_err();
+        assert!(format!("{err:#}").contains("行情数据为空"), "{err:#}");
+        let err = sina.fetch_kline_data("sz000001", 240, 2).unwrap_err();
+        assert!(format!("{err:#}").contains("解析K线 JSON 失败"), "{err:#}");
+        // 404 不重试
+        let err = sina.fetch_realtime_quote("sh999999").unwrap_err();
+        assert!(format!("{err:#}").contains("HTTP 404"), "{err:#}");
+        assert_eq!(sina.stats()[0].retries, 0);
+
+        // 超时
+        let impatient = SinaClient::new(SinaConfig {
+            timeout_ms: mock::SLOW.as_millis() as u64 / 5,
+            retries: 0,
+            ..config
+        })
+        .unwrap();
+        assert!(impatient.fetch_realtime_quote("sh000009").is_err());
+
+        // Referer 不符时服务器拒绝请求
+        let strict = SinaClient::new(mock::start("http://example.com")).unwrap();
+        let err = strict.fetch_realtime_quote("sh600519").unwrap_err();
+        assert!(format!("{err:#}").contains("HTTP 403"), "{err:#}");
+    }
+
+    #[test]
+    fn test_retries_transient_errors() {
+        let sina = SinaClient::new(mock::start(mock::REFERER)).unwrap();
+
+        // 首次 503，重试后成功
+        assert_eq!(
+            sina.fetch_realtime_quote("sz000503").unwrap().name,
+            "贵州茅台"
+        );
+        // 始终 503：重试 2 次后失败
+        let err = sina.fetch_realtime_quote("sh000503").unwrap_err();
+        assert!(format!("{err:#}").contains("HTTP 503"), "{err:#}");
+
+        let stats = &sina.stats()[0];
+        assert_eq!((stats.requests, stats.errors, stats.retries), (2, 1, 3));
+        assert!(stats.last_error.as_deref().unwrap().contains("503"));
     }
 }
@@ -1,7 +1,289 @@
-use crate::api;
-use crate::config::Config;
-use crate::models::*;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::{Duration, Instant};
+
 use ratatui::widgets::TableState;
+use stock_tui::adjust::{self, Adjust, AdjustFactors};
+use stock_tui::backtest::{self, BacktestResult, Strategy};
+use stock_tui::board::{BoardKind, Sector};
+use stock_tui::breadth::{self, Breadth, BreadthLog};
+use stock_tui::compare::Aligned;
+use stock_tui::config::Config;
+use stock_tui::export;
+use stock_tui::feed::{self, DailyExtra, KlineCache, ProfileCache, QuoteEnricher};
+use stock_tui::market::{
+    Market, MarketPhase, RefreshItem, RefreshTimer, Scheduler, TradingCalendar,
+};
+use stock_tui::models::*;
+use stock_tui::paper::{Account, Side};
+use stock_tui::profile::CompanyProfile;
+use stock_tui::provider::Provider;
+use stock_tui::replay::Replay;
+use stock_tui::screener::{self, Filter, Screen, ScreenResult};
+use stock_tui::tdx;
+use stock_tui::viewport;
+
+use crate::event::AppEvent;
+
+/// 输入模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputMode {
+    /// 正常浏览模式
+    Normal,
+    /// 输入股票代码模式
+    AddStock,
+    /// 快捷键帮助页面
+    HelpScreen,
+    /// 网络诊断页面（各接口延迟与错误）
+    Diagnostics,
+    /// 编辑选股条件
+    ScreenFilter,
+    /// 输入选股方案名称（保存）
+    ScreenName,
+    /// 输入模拟委托（数量和限价）
+    PaperOrder,
+    /// 输入历史回放的开始日期
+    ReplayDate,
+    /// 输入网格小图的股票代码
+    GridSymbol,
+}
+
+/// 视图模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewMode {
+    /// 正常布局（行情 + K线 + 自选股）
+    Normal,
+    /// 全屏K线图
+    FullscreenChart,
+    /// 板块热力图
+    Heatmap,
+    /// 市场宽度（涨跌家数、涨跌幅分布）
+    Breadth,
+    /// 条件选股结果
+    Screener,
+    /// 模拟交易（持仓、委托、成交）
+    Paper,
+    /// 多股票走势对比
+    Compare,
+    /// 多图网格（2x2 / 3x3）
+    Grid,
+}
+
+/// K线图缩放级别（决定每根K线占的列数）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChartZoom {
+    /// 收盘价折线，每根一列
+    Line,
+    /// 细K线（实体和影线都是细线），每根一列
+    Thin,
+    /// 标准K线，每根三列
+    Normal,
+    /// 宽K线，每根五列
+    Wide,
+}
+
+impl ChartZoom {
+    /// 每根K线占的列数
+    pub fn candle_width(&self) -> usize {
+        match self {
+            ChartZoom::Line | ChartZoom::Thin => 1,
+            ChartZoom::Normal => 3,
+            ChartZoom::Wide => 5,
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChartZoom::Line => "折线",
+            ChartZoom::Thin => "细K线",
+            ChartZoom::Normal => "标准",
+            ChartZoom::Wide => "宽K线",
+        }
+    }
+
+    /// 放大一级（K线更宽、数量更少）
+    fn zoom_in(self) -> Self {
+        match self {
+            ChartZoom::Line => ChartZoom::Thin,
+            ChartZoom::Thin => ChartZoom::Normal,
+            _ => ChartZoom::Wide,
+        }
+    }
+
+    /// 缩小一级（K线更窄、数量更多）
+    fn zoom_out(self) -> Self {
+        match self {
+            ChartZoom::Wide => ChartZoom::Normal,
+            ChartZoom::Normal => ChartZoom::Thin,
+            _ => ChartZoom::Line,
+        }
+    }
+}
+
+/// 一次回测：K线图上标注买卖点，下方显示权益曲线
+pub struct BacktestRun {
+    /// 股票代码
+    pub symbol: String,
+    /// K线周期
+    pub timeframe: TimeFrame,
+    /// 回测区间各K线的日期
+    pub days: Vec<String>,
+    /// 回测结果
+    pub result: BacktestResult,
+}
+
+/// 后台回测任务的结果
+pub struct BacktestLoad {
+    symbol: String,
+    timeframe: TimeFrame,
+    len: u32,
+    /// 本次获取的K线，写回缓存
+    fetched: Option<Vec<KLineData>>,
+    /// 本次获取的复权因子，写回缓存
+    factors: Option<AdjustFactors>,
+    /// 回测结果和状态栏提示，或错误信息
+    result: Result<(BacktestRun, String), String>,
+}
+
+/// 走势对比：当前股票和加入对比的股票按日期对齐的收盘价
+pub struct Comparison {
+    /// 各股票的 (代码, 名称)，与 aligned.closes 对应
+    pub series: Vec<(String, String)>,
+    /// 对齐后的收盘价
+    pub aligned: Aligned,
+}
+
+/// 交给后台对比任务的一只股票：已缓存的K线和复权因子（没有时在后台获取）
+struct CompareInput {
+    symbol: String,
+    name: String,
+    klines: Option<Vec<KLineData>>,
+    factors: Option<AdjustFactors>,
+}
+
+/// 后台对比任务的结果
+pub struct ComparisonLoad {
+    timeframe: TimeFrame,
+    adjust: Adjust,
+    comparison: Comparison,
+    /// 本次获取的K线，写回缓存
+    fetched: Vec<(String, Vec<KLineData>)>,
+    /// 本次获取的复权因子，写回缓存
+    factors: Vec<(String, AdjustFactors)>,
+    /// 获取失败或无数据的提示
+    errors: Vec<String>,
+}
+
+impl ComparisonLoad {
+    /// 获取缺少的K线和复权因子，按复权方式调整后按日期对齐
+    fn fetch(
+        provider: &dyn Provider,
+        adjust_dir: Option<&std::path::Path>,
+        inputs: Vec<CompareInput>,
+        timeframe: TimeFrame,
+        mode: Adjust,
+    ) -> Self {
+        let mut load = ComparisonLoad {
+            timeframe,
+            adjust: mode,
+            comparison: Comparison {
+                series: Vec::new(),
+                aligned: Aligned::default(),
+            },
+            fetched: Vec::new(),
+            factors: Vec::new(),
+            errors: Vec::new(),
+        };
+        let mut data = Vec::new();
+        for input in inputs {
+            let symbol = input.symbol;
+            let klines = match input.klines {
+                Some(klines) => klines,
+                None => match provider.klines(&symbol, timeframe, COMPARE_LEN) {
+                    Ok(klines) => {
+                        load.fetched.push((symbol.clone(), klines.clone()));
+                        klines
+                    }
+                    Err(e) => {
+                        load.errors
+                            .push(format!("获取 {} K线失败: {:#}", symbol, e));
+                        continue;
+                    }
+                },
+            };
+            if klines.is_empty() {
+                load.errors.push(format!("{} 无K线数据", symbol));
+                continue;
+            }
+            let klines = if mode != Adjust::None && adjust::supports(&symbol) {
+                let factors = input.factors.unwrap_or_else(|| {
+                    let factors = load_factors(provider, adjust_dir, &symbol).unwrap_or_default();
+                    load.factors.push((symbol.clone(), factors.clone()));
+                    factors
+                });
+                factors.apply(&klines, mode)
+            } else {
+                klines
+            };
+            data.push(klines);
+            load.comparison.series.push((symbol, input.name));
+        }
+        load.comparison.aligned = Aligned::new(&data);
+        load
+    }
+}
+
+/// 网格中的一个小图：各自的股票、周期、K线和滚动位置
+pub struct GridCell {
+    /// 股票代码
+    pub symbol: String,
+    /// K线周期
+    pub timeframe: TimeFrame,
+    /// K线数据
+    pub data: Vec<KLineData>,
+    /// 水平滚动偏移（从右往左）
+    pub offset: usize,
+    /// 最近获取的行情（股票不在自选股中时用于标题）
+    pub quote: Option<StockQuote>,
+    /// 选择的复权方式
+    pub adjust: Adjust,
+    /// 实际应用的复权方式
+    pub applied: Adjust,
+}
+
+impl GridCell {
+    fn new(symbol: String, timeframe: TimeFrame) -> Self {
+        Self {
+            symbol,
+            timeframe,
+            data: Vec::new(),
+            offset: 0,
+            quote: None,
+            adjust: Adjust::None,
+            applied: Adjust::None,
+        }
+    }
+}
+
+/// 历史回放：逐根显示K线，可用回放专用的模拟账户交易
+pub struct ReplaySession {
+    /// 股票代码
+    pub symbol: String,
+    /// 股票名称
+    pub name: String,
+    /// 回放进度
+    pub replay: Replay,
+    /// 是否自动播放
+    pub playing: bool,
+    /// 自动播放时每根K线的间隔（毫秒）
+    pub speed_ms: u64,
+    /// 最近一次前进的时间
+    last_step: Instant,
+    /// 回放专用的模拟账户（不保存）
+    pub account: Account,
+}
 
 /// 应用主状态
 pub struct App {
@@ -29,17 +311,182 @@ pub struct App {
     pub kline_offset: usize,
     /// K线游标位置（在可见K线中的索引，None表示未激活）
     pub kline_cursor: Option<usize>,
+    /// K线图缩放级别
+    pub chart_zoom: ChartZoom,
+    /// 上次绘制的主K线图宽度（含边框，不含详情面板），用于计算可见K线数量
+    pub chart_width: usize,
     /// 状态栏消息
     pub status_message: String,
+    /// 尚未显示的涨跌停提醒（同一次刷新触发多条时依次显示）
+    alerts: VecDeque<String>,
+    /// 正在显示的提醒及开始显示的时间
+    alert: Option<(String, Instant)>,
     /// 是否正在加载
     pub loading: bool,
+    /// 配置（保存自选股时保留其他配置项）
+    pub config: Config,
+    /// 行情数据源
+    pub provider: Arc<dyn Provider>,
+    /// K线缓存（切换股票/周期时复用最近获取的数据）
+    pub kline_cache: KlineCache,
+    /// 按交易时段决定自动刷新哪些股票
+    pub scheduler: Scheduler,
+    /// 主K线图选择的复权方式
+    pub adjust: Adjust,
+    /// 主K线图实际应用的复权方式（港美股或没有复权因子时为不复权）
+    pub kline_adjust: Adjust,
+    /// 已获取的复权因子（按股票代码）
+    factors: HashMap<String, AdjustFactors>,
+    /// 正在后台获取复权因子的股票
+    factors_pending: HashSet<String>,
+    /// 指数条行情（与 config.indices 对应，获取失败为 None）
+    pub indices: Vec<Option<IndexQuote>>,
+    /// 各指数最近的点位（用于迷你走势图）
+    pub index_history: HashMap<String, VecDeque<f64>>,
+    /// 热力图的板块分类
+    pub board_kind: BoardKind,
+    /// 板块列表（按成交额从大到小）
+    pub boards: Vec<Sector>,
+    /// 热力图中选中的板块
+    pub board_index: usize,
+    /// 下钻的板块成分股: (板块名称, 行情)
+    pub board_stocks: Option<(String, Vec<StockQuote>)>,
+    /// 成分股列表状态
+    pub board_stock_state: TableState,
+    /// 最近一次市场宽度统计
+    pub breadth: Option<Breadth>,
+    /// 市场宽度历史（昨日成交额、当日分时涨跌家数）
+    pub breadth_log: BreadthLog,
+    /// 最近一次统计所在的交易日 (YYYY-MM-DD)
+    pub breadth_date: String,
+    /// 是否正在后台获取市场宽度
+    pub breadth_pending: bool,
+    /// 当前选股方案（可能是未保存的条件）
+    pub screen: Screen,
+    /// 选股结果
+    pub screen_result: Option<ScreenResult>,
+    /// 是否正在后台执行选股
+    pub screen_pending: bool,
+    /// 选股结果列表状态
+    pub screen_state: TableState,
+    /// 选股结果的排序字段（screener::SORT_FIELDS 的索引）
+    pub screen_sort: usize,
+    /// 选股结果是否升序
+    pub screen_ascending: bool,
+    /// 当前股票的回测（按 T 开关）
+    pub backtest: Option<BacktestRun>,
+    /// 模拟交易账户
+    pub paper: Account,
+    /// 有模拟持仓或委托、但不在自选股中的股票行情
+    paper_quotes: HashMap<String, StockQuote>,
+    /// 正在输入的模拟委托方向
+    pub paper_side: Side,
+    /// 模拟交易委托列表状态（按时间倒序）
+    pub paper_state: TableState,
+    /// 历史回放（按 R 开始）
+    pub replay: Option<ReplaySession>,
+    /// 加入对比的自选股（当前股票总是第一条）
+    pub compare_symbols: Vec<String>,
+    /// 走势对比数据
+    pub comparison: Option<Comparison>,
+    /// 对比图游标（对齐日期的索引）
+    pub compare_cursor: Option<usize>,
+    /// 网格中的小图
+    pub grid: Vec<GridCell>,
+    /// 网格边长（2 或 3）
+    pub grid_size: usize,
+    /// 网格中选中的小图
+    pub grid_focus: usize,
+    /// 是否放大显示选中的小图
+    pub grid_expanded: bool,
+    /// 是否正在后台刷新网格小图
+    grid_pending: bool,
+    /// 是否正在后台回测
+    backtest_pending: bool,
+    /// 是否显示详情面板
+    pub show_detail: bool,
+    /// 详情面板的公司资料: (代码, 资料或错误信息)
+    pub detail: Option<(String, Result<CompanyProfile, String>)>,
+    /// 公司资料缓存（每天获取一次）
+    profiles: ProfileCache,
+    /// 正在后台获取公司资料的股票
+    profile_pending: HashSet<String>,
+    /// A股行情的换手率、量比、市值等补充数据（每天获取一次）
+    enricher: QuoteEnricher,
+    /// 正在后台获取补充数据的股票
+    enrich_pending: HashSet<String>,
+    /// 自动刷新计时（含暂停状态）
+    pub timer: RefreshTimer,
+    /// 当前K线是否来自通达信本地数据（网络无数据时的回退）
+    kline_local: bool,
+    /// 事件通道，后台任务完成后通过它送回结果
+    events: mpsc::Sender<AppEvent>,
+}
+
+/// 后台任务的结果（全市场扫描等耗时请求在工作线程执行，避免界面卡顿）
+pub enum JobResult {
+    /// 沪深A股全部行情（市场宽度采样）
+    Breadth(Result<Vec<StockQuote>, String>),
+    /// 选股结果及执行时的方案（方案已切换的结果丢弃）
+    Screen(Screen, Result<ScreenResult, String>),
+    /// 网格小图的K线和行情
+    Grid(Vec<GridUpdate>),
+    /// 不复权时预先获取的复权因子
+    Factors(String, Result<AdjustFactors, String>),
+    /// 板块列表及请求时的板块类型
+    Boards(BoardKind, Result<Vec<Sector>, String>),
+    /// 板块成分股行情
+    BoardStocks(Sector, Result<Vec<StockQuote>, String>),
+    /// 走势对比数据
+    Comparison(ComparisonLoad),
+    /// 回测结果
+    Backtest(Box<BacktestLoad>),
+    /// 详情面板的公司资料: (代码, 获取的日期, 资料或错误信息)
+    Profile(String, chrono::NaiveDate, Result<CompanyProfile, String>),
+    /// A股行情当天的补充数据（换手率、量比、市值等）
+    QuoteExtras(Vec<(String, DailyExtra)>),
+}
+
+/// 后台获取的一个网格小图的数据，按 (代码, 周期) 更新仍显示它的小图
+pub struct GridUpdate {
+    symbol: String,
+    timeframe: TimeFrame,
+    klines: Result<Vec<KLineData>, String>,
+    quote: Option<StockQuote>,
+    /// 小图需要复权且尚未缓存时获取的复权因子
+    factors: Option<Result<AdjustFactors, String>>,
 }
 
+/// 下钻板块时获取的成分股数量
+const BOARD_STOCKS_LEN: u32 = 80;
+
+/// 指数迷你走势图保留的点数
+const INDEX_HISTORY_LEN: usize = 60;
+
+/// 走势对比获取的K线数量
+const COMPARE_LEN: u32 = 120;
+
+/// 每条涨跌停提醒的最短显示时间（秒）
+const ALERT_SECS: u64 = 3;
+
+/// 回放速度的范围（每根K线的毫秒数）
+const MIN_REPLAY_SPEED_MS: u64 = 50;
+const MAX_REPLAY_SPEED_MS: u64 = 10_000;
+
 impl App {
-    pub fn new() -> Self {
+    pub fn new(provider: Arc<dyn Provider>, events: mpsc::Sender<AppEvent>) -> Self {
         // Load config from file
         let config = Config::load();
-        let watchlist = config.watchlist;
+        let watchlist = config.watchlist.clone();
+        let screen = config
+            .screens
+            .first()
+            .cloned()
+            .unwrap_or_else(|| Screen::new("", "pct > 5"));
+        let paper = Account::load(
+            &Config::paper_path().unwrap_or_default(),
+            config.paper.initial_cash,
+        );
 
         let quotes = vec![None; watchlist.len()];
         let mut watchlist_state = TableState::default();
@@ -60,8 +507,63 @@ impl App {
             input_buffer: String::new(),
             kline_offset: 0,
             kline_cursor: None,
+            chart_zoom: ChartZoom::Normal,
+            chart_width: 0,
             status_message: "正在加载数据...".to_string(),
+            alerts: VecDeque::new(),
+            alert: None,
             loading: true,
+            config,
+            provider,
+            kline_cache: KlineCache::new(Duration::from_secs(60)),
+            scheduler: Scheduler::new(TradingCalendar::load(Config::holidays_path().as_deref())),
+            adjust: Adjust::None,
+            kline_adjust: Adjust::None,
+            factors: HashMap::new(),
+            factors_pending: HashSet::new(),
+            indices: Vec::new(),
+            index_history: HashMap::new(),
+            board_kind: BoardKind::Industry,
+            boards: Vec::new(),
+            board_index: 0,
+            board_stocks: None,
+            board_stock_state: TableState::default(),
+            breadth: None,
+            breadth_log: Config::breadth_path()
+                .map(|path| BreadthLog::load(&path))
+                .unwrap_or_default(),
+            breadth_date: String::new(),
+            breadth_pending: false,
+            screen,
+            screen_result: None,
+            screen_pending: false,
+            screen_state: TableState::default(),
+            screen_sort: 0,
+            screen_ascending: false,
+            backtest: None,
+            paper,
+            paper_quotes: HashMap::new(),
+            paper_side: Side::Buy,
+            paper_state: TableState::default(),
+            replay: None,
+            compare_symbols: Vec::new(),
+            comparison: None,
+            compare_cursor: None,
+            grid: Vec::new(),
+            grid_size: 2,
+            grid_focus: 0,
+            grid_expanded: false,
+            grid_pending: false,
+            backtest_pending: false,
+            show_detail: false,
+            detail: None,
+            profiles: ProfileCache::default(),
+            profile_pending: HashSet::new(),
+            enricher: QuoteEnricher::default(),
+            enrich_pending: HashSet::new(),
+            timer: RefreshTimer::default(),
+            kline_local: false,
+            events,
         };
 
         app.refresh_all();
@@ -73,13 +575,40 @@ impl App {
         self.watchlist_state.selected().unwrap_or(0)
     }
 
-    /// 刷新所有数据
+    /// 刷新所有数据（忽略当前股票的K线缓存）
     pub fn refresh_all(&mut self) {
+        if let Some(symbol) = self.watchlist.get(self.active_index) {
+            self.kline_cache.invalidate(symbol);
+        }
         self.refresh_quotes();
+        self.refresh_indices();
         self.refresh_kline();
         self.loading = false;
     }
 
+    /// 刷新顶部指数条，并记录点位用于迷你走势图
+    pub fn refresh_indices(&mut self) {
+        self.timer.mark(RefreshItem::Indices, Instant::now());
+        let symbols = self.config.indices.clone();
+        self.indices = symbols
+            .iter()
+            .map(|s| self.provider.index_quote(s).ok())
+            .collect();
+        for q in self.indices.iter().flatten() {
+            let history = self.index_history.entry(q.symbol.clone()).or_default();
+            if history.len() >= INDEX_HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(q.current);
+        }
+    }
+
+    /// 指数所在市场是否开市（`int_` 全球指数分属多个时区，总是刷新）
+    fn index_live(&self, symbol: &str) -> bool {
+        let symbol = symbol.strip_prefix("s_").unwrap_or(symbol);
+        symbol.starts_with("int_") || self.is_live(symbol)
+    }
+
     /// 刷新所有股票的实时行情
     pub fn refresh_quotes(&mut self) {
         if self.watchlist.is_empty() {
@@ -87,33 +616,330 @@ impl App {
             return;
         }
 
-        let results = api::fetch_multiple_quotes(&self.watchlist);
-        self.quotes = results
-            .into_iter()
-            .map(|r| match r {
-                Ok(q) => Some(q),
-                Err(e) => {
-                    self.status_message = format!("获取行情失败: {}", e);
-                    None
-                }
-            })
-            .collect();
+        let mut poll = feed::poll_quotes(self.provider.as_ref(), &self.watchlist);
+        self.enricher.apply(&mut poll.quotes);
+        self.fetch_quote_extras(&poll.quotes);
+        let prev = std::mem::replace(&mut self.quotes, poll.quotes);
+        let now = Instant::now();
+        for symbol in &self.watchlist {
+            self.timer.mark(RefreshItem::Quote(symbol.clone()), now);
+        }
+        if let Some((symbol, e)) = poll.errors.last() {
+            self.status_message = format!("获取行情失败: {} {}", symbol, e);
+        }
 
         // 更新状态消息
         if let Some(Some(q)) = self.quotes.get(self.active_index) {
             self.status_message =
                 format!("{} {} 最后更新: {} {}", q.symbol, q.name, q.date, q.time);
         }
+        self.alert_limits(&prev);
+        self.refresh_paper_quotes(self.paper_only_symbols());
+    }
+
+    /// 定时刷新（每秒调用）：按配置的间隔刷新行情，开市期间刷新当前股票的K线
+    pub fn on_tick(&mut self) {
+        self.tick_replay();
+        self.show_alerts(false);
+        if self.timer.paused() {
+            return;
+        }
+        self.refresh_due_quotes();
+        let (now, refresh) = (Instant::now(), &self.config.refresh);
+        let indices_due = self
+            .timer
+            .due(&RefreshItem::Indices, refresh.indices_interval(), now);
+        let breadth_due = self
+            .timer
+            .due(&RefreshItem::Breadth, refresh.breadth_interval(), now);
+        let grid_due = self
+            .timer
+            .due(&RefreshItem::Grid, refresh.kline_interval(), now);
+        let kline_due = self
+            .timer
+            .due(&RefreshItem::Kline, refresh.kline_interval(), now);
+
+        if indices_due && self.config.indices.iter().any(|s| self.index_live(s)) {
+            self.refresh_indices();
+        }
+        // 开市期间无论是否打开视图都在后台采样，分时涨跌家数才完整
+        if breadth_due && self.is_live("sh000001") {
+            self.refresh_breadth();
+        }
+
+        if self.view_mode == ViewMode::Grid && grid_due {
+            self.refresh_grid_cells(true);
+        }
+
+        // 回放时K线不随行情更新
+        let live = self.replay.is_none()
+            && self
+                .watchlist
+                .get(self.active_index)
+                .is_some_and(|s| self.is_live(s));
+        if live && kline_due {
+            self.reload_live_kline();
+        }
+    }
+
+    /// 在后台获取缺少当天补充数据的A股（日K线和公司资料），完成后补充到自选股行情
+    fn fetch_quote_extras(&mut self, quotes: &[Option<StockQuote>]) {
+        let missing: Vec<(String, String)> = self
+            .enricher
+            .missing(quotes)
+            .into_iter()
+            .filter(|(symbol, _)| self.enrich_pending.insert(symbol.clone()))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        self.spawn_job(move |provider| {
+            JobResult::QuoteExtras(
+                missing
+                    .into_iter()
+                    .map(|(symbol, date)| {
+                        let extra = DailyExtra::fetch(provider, &symbol, &date);
+                        (symbol, extra)
+                    })
+                    .collect(),
+            )
+        });
+    }
+
+    /// 暂停 / 恢复自动刷新
+    pub fn toggle_pause(&mut self) {
+        self.status_message = if self.timer.toggle_pause() {
+            "已暂停自动刷新（p 恢复）".to_string()
+        } else {
+            "已恢复自动刷新".to_string()
+        };
+    }
+
+    /// 股票所在市场是否开市
+    fn is_live(&self, symbol: &str) -> bool {
+        self.scheduler
+            .calendar()
+            .phase(Market::of(symbol), chrono::Utc::now())
+            .is_live()
+    }
+
+    /// 获取开市中（或刚收盘）市场的股票行情，当前股票和其他股票按各自的间隔刷新，
+    /// 并用自选股和模拟持仓 / 委托股票的新行情撮合模拟委托
+    fn refresh_due_quotes(&mut self) {
+        let paper = self.paper_only_symbols();
+        // 两组一起判断，收盘后的最后一次刷新才会同时覆盖自选股和模拟股票
+        let due = self
+            .scheduler
+            .due_groups(&[&self.watchlist, &paper], chrono::Utc::now());
+        let watch_due = self.interval_due(&due[0]);
+        let paper_due = self.interval_due(&due[1]);
+
+        let mut polled = self.refresh_watchlist_quotes(watch_due);
+        polled.extend(self.refresh_paper_quotes(paper_due));
+        self.match_paper(&polled);
+    }
+
+    /// 去掉开市中但还没到刷新间隔的股票，收盘后的最后一次刷新不受间隔限制
+    fn interval_due(&self, symbols: &[String]) -> Vec<String> {
+        let active = self.watchlist.get(self.active_index);
+        let now = Instant::now();
+        symbols
+            .iter()
+            .filter(|s| {
+                let interval = self.config.refresh.symbol_interval(s, active == Some(s));
+                !self.is_live(s)
+                    || self
+                        .timer
+                        .due(&RefreshItem::Quote(s.to_string()), interval, now)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// 获取到期的自选股行情，返回本次获取的股票
+    fn refresh_watchlist_quotes(&mut self, due: Vec<String>) -> Vec<String> {
+        if due.is_empty() {
+            return due;
+        }
+
+        let now = Instant::now();
+        for symbol in &due {
+            self.timer.mark(RefreshItem::Quote(symbol.clone()), now);
+        }
+
+        let mut poll = feed::poll_quotes(self.provider.as_ref(), &due);
+        self.enricher.apply(&mut poll.quotes);
+        self.fetch_quote_extras(&poll.quotes);
+        let prev = self.quotes.clone();
+        poll.merge_into(&due, &self.watchlist, &mut self.quotes);
+        if let Some((symbol, e)) = poll.errors.last() {
+            self.status_message = format!("获取行情失败: {} {}", symbol, e);
+        } else if let (None, Some(Some(q))) = (&self.replay, self.quotes.get(self.active_index)) {
+            self.status_message =
+                format!("{} {} 最后更新: {} {}", q.symbol, q.name, q.date, q.time);
+        }
+        self.alert_limits(&prev);
+        due
+    }
+
+    /// 有模拟持仓或委托、但不在自选股中的股票
+    fn paper_only_symbols(&self) -> Vec<String> {
+        self.paper
+            .symbols()
+            .into_iter()
+            .filter(|s| !self.watchlist.contains(s))
+            .collect()
+    }
+
+    /// 获取到期的模拟持仓 / 委托股票（不在自选股中）行情，返回本次获取的股票
+    fn refresh_paper_quotes(&mut self, due: Vec<String>) -> Vec<String> {
+        let symbols = self.paper_only_symbols();
+        self.paper_quotes.retain(|s, _| symbols.contains(s));
+        if due.is_empty() {
+            return due;
+        }
+        let now = Instant::now();
+        for symbol in &due {
+            self.timer.mark(RefreshItem::Quote(symbol.clone()), now);
+        }
+        let poll = feed::poll_quotes(self.provider.as_ref(), &due);
+        for q in poll.quotes.into_iter().flatten() {
+            self.paper_quotes.insert(q.symbol.clone(), q);
+        }
+        due
+    }
+
+    /// 用刚获取的行情撮合模拟委托、更新持仓市值，并记录当日盈亏
+    fn match_paper(&mut self, symbols: &[String]) {
+        let before = self.paper.clone();
+        let mut date: Option<String> = None;
+        let quotes: Vec<StockQuote> = self
+            .quotes
+            .iter()
+            .flatten()
+            .chain(self.paper_quotes.values())
+            .filter(|q| symbols.contains(&q.symbol))
+            .cloned()
+            .collect();
+        let active = self.paper.symbols();
+        for q in quotes.iter().filter(|q| active.contains(&q.symbol)) {
+            for fill in self.paper.match_quote(q, &self.config.paper.fees) {
+                self.status_message = format!(
+                    "模拟成交: {} {} {} {} 股 @ {:.2}",
+                    fill.side.label(),
+                    fill.symbol,
+                    fill.name,
+                    fill.shares,
+                    fill.price
+                );
+            }
+            date = date.max(Some(q.date.clone()));
+        }
+        if let Some(date) = date {
+            self.paper.record_day(&date);
+        }
+        if self.paper != before {
+            self.save_paper();
+        }
+    }
+
+    /// 保存模拟账户
+    fn save_paper(&mut self) {
+        if let Some(path) = Config::paper_path() {
+            if let Err(e) = self.paper.save(&path) {
+                self.status_message = format!("{:#}", e);
+            }
+        }
+    }
+
+    /// 涨跌停提醒：自选股封板、炸板等状态变化按 `limit_alerts` 规则排队，依次显示在状态栏
+    fn alert_limits(&mut self, prev: &[Option<StockQuote>]) {
+        for (old, new) in prev.iter().zip(&self.quotes) {
+            if let (Some(old), Some(new)) = (old, new) {
+                if old.symbol != new.symbol {
+                    continue;
+                }
+                if let Some(state) = self.config.limit_alerts.check(old, new) {
+                    self.alerts.push_back(format!(
+                        "提醒: {} {} {} {:.2}",
+                        new.symbol,
+                        new.name,
+                        state.label(),
+                        new.current
+                    ));
+                }
+            }
+        }
+        // 刷新会覆盖状态栏，显示中的提醒重新显示
+        self.show_alerts(true);
+    }
+
+    /// 每条提醒至少显示 ALERT_SECS 秒后再显示下一条，还有排队的提醒时注明条数
+    fn show_alerts(&mut self, redisplay: bool) {
+        if self
+            .alert
+            .as_ref()
+            .is_some_and(|(_, since)| since.elapsed() >= Duration::from_secs(ALERT_SECS))
+        {
+            self.alert = None;
+        }
+        let mut changed = false;
+        if self.alert.is_none() {
+            if let Some(text) = self.alerts.pop_front() {
+                self.alert = Some((text, Instant::now()));
+                changed = true;
+            }
+        }
+        if let Some((text, _)) = &self.alert {
+            if changed || redisplay {
+                self.status_message = match self.alerts.len() {
+                    0 => text.clone(),
+                    n => format!("{}（还有 {} 条提醒）", text, n),
+                };
+            }
+        }
+    }
+
+    /// 自选股涉及的各市场当前状态
+    pub fn market_phases(&self) -> Vec<(Market, MarketPhase)> {
+        let now = chrono::Utc::now();
+        Market::all()
+            .into_iter()
+            .filter(|m| self.watchlist.iter().any(|s| Market::of(s) == *m))
+            .map(|m| (m, self.scheduler.calendar().phase(m, now)))
+            .collect()
     }
 
     /// 刷新当前选中股票的K线数据
     pub fn refresh_kline(&mut self) {
-        if let Some(symbol) = self.watchlist.get(self.active_index) {
-            match api::fetch_kline_data(symbol, self.timeframe.scale(), 120) {
+        if let Some(symbol) = self.watchlist.get(self.active_index).cloned() {
+            let result = self
+                .kline_cache
+                .get(self.provider.as_ref(), &symbol, self.timeframe, 120);
+            self.kline_local = false;
+            let data = match result {
+                Ok(data) if !data.is_empty() => Ok(data),
+                other => match self.load_tdx_kline(&symbol) {
+                    Some(local) => {
+                        self.status_message = format!(
+                            "{} {} 使用通达信本地数据 ({}根)",
+                            symbol,
+                            self.timeframe.short_label(),
+                            local.len()
+                        );
+                        self.kline_local = true;
+                        Ok(local)
+                    }
+                    None => other,
+                },
+            };
+            match data {
                 Ok(data) => {
-                    self.kline_data = data;
+                    (self.kline_data, self.kline_adjust) =
+                        self.adjusted(&symbol, data, self.adjust);
                     self.kline_offset = 0;
                     self.kline_cursor = None;
+                    self.timer.mark(RefreshItem::Kline, Instant::now());
                 }
                 Err(e) => {
                     self.status_message = format!("获取K线数据失败: {}", e);
@@ -125,6 +951,116 @@ impl App {
         }
     }
 
+    /// 重新获取当前股票的K线以更新正在形成的一根，保留滚动位置和游标
+    /// 显示通达信本地数据时不刷新（网络无数据才会回退到本地，手动刷新 r 可重试网络）
+    fn reload_live_kline(&mut self) {
+        self.timer.mark(RefreshItem::Kline, Instant::now());
+        let Some(symbol) = self.watchlist.get(self.active_index).cloned() else {
+            return;
+        };
+        if self.kline_local {
+            return;
+        }
+        // 只更新当前周期的缓存；获取失败或无数据时保留原数据，下次再试
+        if let Ok(data) = self.provider.klines(&symbol, self.timeframe, 120) {
+            if !data.is_empty() {
+                self.kline_cache
+                    .insert(&symbol, self.timeframe, 120, data.clone());
+                (self.kline_data, self.kline_adjust) = self.adjusted(&symbol, data, self.adjust);
+                if self
+                    .kline_cursor
+                    .is_some_and(|c| c >= self.kline_data.len())
+                {
+                    self.kline_cursor = None;
+                }
+            }
+        }
+    }
+
+    /// 切换主K线图的复权方式（不复权 → 前复权 → 后复权）
+    pub fn cycle_adjust(&mut self) {
+        self.adjust = self.adjust.next();
+        self.refresh_kline();
+        if self.adjust != Adjust::None && self.kline_adjust == Adjust::None {
+            self.status_message = format!("{}: 仅A股支持复权，或没有除权数据", self.adjust.label());
+        }
+    }
+
+    /// 按复权方式调整K线（仅 A股，在计算均线等指标之前），同时返回实际应用的复权方式
+    fn adjusted(
+        &mut self,
+        symbol: &str,
+        data: Vec<KLineData>,
+        mode: Adjust,
+    ) -> (Vec<KLineData>, Adjust) {
+        if !adjust::supports(symbol) {
+            return (data, Adjust::None);
+        }
+        if mode == Adjust::None {
+            // 不复权时在后台获取复权因子，只用于计算除权日的涨跌停价
+            self.prefetch_factors(symbol);
+            return (data, Adjust::None);
+        }
+        let factors = self.ensure_factors(symbol);
+        if factors.is_empty() {
+            return (data, Adjust::None);
+        }
+        (factors.apply(&data, mode), mode)
+    }
+
+    /// 已获取的复权因子（首次使用时获取）
+    fn ensure_factors(&mut self, symbol: &str) -> &AdjustFactors {
+        if !self.factors.contains_key(symbol) {
+            let factors = load_factors(
+                self.provider.as_ref(),
+                self.config.adjust_dir.as_deref(),
+                symbol,
+            )
+            .unwrap_or_else(|e| {
+                self.status_message = format!("获取复权因子失败: {}", e);
+                // 缓存空因子，避免每次刷新重复请求
+                AdjustFactors::default()
+            });
+            self.factors.insert(symbol.to_string(), factors);
+        }
+        &self.factors[symbol]
+    }
+
+    /// 在后台获取尚未缓存的复权因子（上一次尚未完成时忽略）
+    fn prefetch_factors(&mut self, symbol: &str) {
+        if self.factors.contains_key(symbol) || !self.factors_pending.insert(symbol.to_string()) {
+            return;
+        }
+        let symbol = symbol.to_string();
+        let adjust_dir = self.config.adjust_dir.clone();
+        self.spawn_job(move |provider| {
+            let factors =
+                load_factors(provider, adjust_dir.as_deref(), &symbol).map_err(|e| e.to_string());
+            JobResult::Factors(symbol, factors)
+        });
+    }
+
+    /// 不复权日K线用于识别除权日的复权因子（已复权的数据价格连续，返回 None）
+    pub fn ex_rights(&self, symbol: &str, applied: Adjust) -> Option<&AdjustFactors> {
+        (applied == Adjust::None)
+            .then(|| self.factors.get(symbol))
+            .flatten()
+            .filter(|f| !f.is_empty())
+    }
+
+    /// 从通达信本地目录读取K线（未配置或无数据时返回 None）
+    fn load_tdx_kline(&mut self, symbol: &str) -> Option<Vec<KLineData>> {
+        let vipdoc = self.config.tdx_dir.as_ref()?;
+        match tdx::load_local(vipdoc, symbol, self.timeframe) {
+            Ok(data) if !data.is_empty() => Some(data),
+            Ok(_) => None,
+            Err(e) => {
+                self.status_message = format!("读取通达信数据失败: {}", e);
+                None
+            }
+        }
+    }
+
     /// 获取当前激活股票的行情
     pub fn current_quote(&self) -> Option<&StockQuote> {
         self.quotes.get(self.active_index).and_then(|q| q.as_ref())
@@ -167,6 +1103,7 @@ impl App {
             self.active_index = highlighted;
             self.status_message = "正在加载...".to_string();
             self.refresh_kline();
+            self.load_detail();
             // Optional: refresh quotes too, or just wait for next tick
             // self.refresh_quotes();
         } else {
@@ -174,6 +1111,79 @@ impl App {
         }
     }
 
+    /// 打开 / 关闭详情面板（打开时激活列表中选中的股票）
+    pub fn toggle_detail(&mut self) {
+        if self.show_detail {
+            self.show_detail = false;
+            return;
+        }
+        self.show_detail = true;
+        let highlighted = self.highlighted_index();
+        if highlighted != self.active_index {
+            self.active_index = highlighted;
+            self.refresh_kline();
+        }
+        self.load_detail();
+    }
+
+    /// 获取当前股票的公司资料（详情面板打开时）
+    ///
+    /// 当天已获取过的直接显示，否则在后台获取，完成前面板显示"加载中"。
+    fn load_detail(&mut self) {
+        if !self.show_detail {
+            return;
+        }
+        let Some(symbol) = self.watchlist.get(self.active_index).cloned() else {
+            return;
+        };
+        let today = chrono::Local::now().date_naive();
+        if let Some(profile) = self.profiles.cached(&symbol, today) {
+            self.set_detail(symbol, Ok(profile));
+            return;
+        }
+        if !self.profile_pending.insert(symbol.clone()) {
+            return;
+        }
+        self.spawn_job(move |provider| {
+            let result = provider.profile(&symbol).map_err(|e| format!("{:#}", e));
+            JobResult::Profile(symbol, today, result)
+        });
+    }
+
+    /// 后台获取的公司资料：写入缓存，仍是当前股票时显示
+    fn apply_profile(
+        &mut self,
+        symbol: String,
+        today: chrono::NaiveDate,
+        result: Result<CompanyProfile, String>,
+    ) {
+        self.profile_pending.remove(&symbol);
+        if let Ok(profile) = &result {
+            self.profiles.insert(&symbol, today, profile.clone());
+        }
+        if self.show_detail && self.watchlist.get(self.active_index) == Some(&symbol) {
+            self.set_detail(symbol, result);
+        }
+    }
+
+    /// 显示详情面板的公司资料，profile_dir 中导入的资料覆盖网络数据
+    fn set_detail(&mut self, symbol: String, mut result: Result<CompanyProfile, String>) {
+        if let Some(dir) = &self.config.profile_dir {
+            let path = dir.join(format!("{}.json", symbol));
+            if path.exists() {
+                match CompanyProfile::load_json(&path) {
+                    Ok(imported) => {
+                        let mut profile = result.unwrap_or_default();
+                        profile.merge(imported);
+                        result = Ok(profile);
+                    }
+                    Err(e) => self.status_message = format!("{:#}", e),
+                }
+            }
+        }
+        self.detail = Some((symbol, result));
+    }
+
     /// 切换K线周期
     pub fn set_timeframe(&mut self, tf: TimeFrame) {
         if self.timeframe != tf {
@@ -205,108 +1215,1337 @@ impl App {
         self.view_mode = match self.view_mode {
             ViewMode::Normal => ViewMode::FullscreenChart,
             ViewMode::FullscreenChart => ViewMode::Normal,
+            other => other,
         };
     }
 
-    /// 获取当前可见K线数量（用于游标边界检查）
-    pub fn visible_kline_count(&self, chart_width: usize) -> usize {
-        let candle_width = 3;
-        // 去掉外框边框(2) + 左侧价格轴(10)
-        let inner_width = chart_width.saturating_sub(12);
-        (inner_width / candle_width).min(self.kline_data.len())
+    /// 打开板块热力图
+    pub fn open_heatmap(&mut self) {
+        self.view_mode = ViewMode::Heatmap;
+        self.board_stocks = None;
+        self.load_boards();
     }
 
-    /// K线游标左移
-    pub fn cursor_left(&mut self, max_visible: usize) {
-        match self.kline_cursor {
-            Some(pos) => {
-                if pos > 0 {
-                    self.kline_cursor = Some(pos - 1);
-                }
-            }
-            None => {
-                // 首次激活游标，放在最右边（最新K线）
-                if !self.kline_data.is_empty() {
-                    let last = max_visible.min(self.kline_data.len()).saturating_sub(1);
-                    self.kline_cursor = Some(last);
-                }
-            }
+    /// 热力图中返回上一级：成分股列表 → 热力图 → 正常布局
+    pub fn heatmap_back(&mut self) {
+        if self.board_stocks.take().is_none() {
+            self.view_mode = ViewMode::Normal;
         }
     }
 
-    /// K线游标右移
-    pub fn cursor_right(&mut self, max_visible: usize) {
-        match self.kline_cursor {
-            Some(pos) => {
-                let limit = max_visible.min(self.kline_data.len()).saturating_sub(1);
-                if pos < limit {
-                    self.kline_cursor = Some(pos + 1);
-                }
-            }
-            None => {
-                // 首次激活游标，放在最右边（最新K线）
-                if !self.kline_data.is_empty() {
-                    let last = max_visible.min(self.kline_data.len()).saturating_sub(1);
-                    self.kline_cursor = Some(last);
-                }
+    /// 切换行业 / 概念板块
+    pub fn cycle_board_kind(&mut self) {
+        self.board_kind = self.board_kind.next();
+        self.board_stocks = None;
+        self.load_boards();
+    }
+
+    /// 在后台获取板块列表
+    pub fn load_boards(&mut self) {
+        let kind = self.board_kind;
+        self.status_message = format!("正在获取{}板块...", kind.label());
+        self.spawn_job(move |provider| {
+            JobResult::Boards(kind, provider.boards(kind).map_err(|e| format!("{:#}", e)))
+        });
+    }
+
+    /// 显示获取到的板块列表（期间已切换板块类型的结果丢弃）
+    fn apply_boards(&mut self, kind: BoardKind, result: Result<Vec<Sector>, String>) {
+        if kind != self.board_kind {
+            return;
+        }
+        match result {
+            Ok(boards) => {
+                self.status_message = format!("{}板块 {} 个", kind.label(), boards.len());
+                self.boards = boards;
+                self.board_index = self.board_index.min(self.boards.len().saturating_sub(1));
             }
+            Err(e) => self.status_message = format!("获取板块失败: {}", e),
         }
     }
 
-    /// 获取游标指向的K线数据
-    pub fn cursor_kline(&self, chart_width: usize) -> Option<&KLineData> {
-        let cursor_pos = self.kline_cursor?;
-        let candle_width = 3;
-        let inner_width = chart_width.saturating_sub(2);
-        let visible_count = (inner_width / candle_width).min(self.kline_data.len());
-
-        let start_idx = if self.kline_data.len() > visible_count + self.kline_offset {
-            self.kline_data.len() - visible_count - self.kline_offset
-        } else {
-            0
+    /// 在热力图（或成分股列表）中移动选中项
+    pub fn board_move(&mut self, delta: isize) {
+        let (len, current) = match &self.board_stocks {
+            Some((_, stocks)) => (stocks.len(), self.board_stock_state.selected().unwrap_or(0)),
+            None => (self.boards.len(), self.board_index),
         };
+        if len == 0 {
+            return;
+        }
+        let next = (current as isize + delta).rem_euclid(len as isize) as usize;
+        if self.board_stocks.is_some() {
+            self.board_stock_state.select(Some(next));
+        } else {
+            self.board_index = next;
+        }
+    }
 
-        self.kline_data.get(start_idx + cursor_pos)
+    /// 进入选中板块，在后台获取成分股行情（按成交额排序）
+    pub fn drill_into_board(&mut self) {
+        let Some(board) = self.boards.get(self.board_index).cloned() else {
+            return;
+        };
+        self.status_message = format!("正在获取 {} 成分股...", board.name);
+        self.spawn_job(move |provider| {
+            let result = provider
+                .node_quotes(&board.code, 1, BOARD_STOCKS_LEN)
+                .map_err(|e| format!("{:#}", e));
+            JobResult::BoardStocks(board, result)
+        });
     }
 
-    /// 进入添加股票模式
-    pub fn start_add_stock(&mut self) {
-        self.input_mode = InputMode::AddStock;
+    /// 显示获取到的成分股（已离开热力图或换了选中板块时丢弃）
+    fn apply_board_stocks(&mut self, board: Sector, result: Result<Vec<StockQuote>, String>) {
+        let selected = self.boards.get(self.board_index).map(|b| &b.code);
+        if self.view_mode != ViewMode::Heatmap
+            || self.board_stocks.is_some()
+            || selected != Some(&board.code)
+        {
+            return;
+        }
+        match result {
+            Ok(stocks) => {
+                self.status_message = format!("{} 成分股 {} 只", board.name, stocks.len());
+                self.board_stock_state
+                    .select((!stocks.is_empty()).then_some(0));
+                self.board_stocks = Some((board.name, stocks));
+            }
+            Err(e) => self.status_message = format!("获取 {} 成分股失败: {}", board.name, e),
+        }
+    }
+
+    /// 将成分股列表中选中的股票加入自选股
+    pub fn add_board_stock(&mut self) {
+        let selected = self
+            .board_stocks
+            .as_ref()
+            .and_then(|(_, stocks)| stocks.get(self.board_stock_state.selected()?));
+        if let Some(symbol) = selected.map(|q| q.symbol.clone()) {
+            self.add_symbol(symbol);
+        }
+    }
+
+    /// 打开 / 关闭市场宽度视图
+    pub fn toggle_breadth(&mut self) {
+        if self.view_mode == ViewMode::Breadth {
+            self.view_mode = ViewMode::Normal;
+        } else {
+            self.view_mode = ViewMode::Breadth;
+            if self.breadth.is_none() {
+                self.refresh_breadth();
+            }
+        }
+    }
+
+    /// 在后台线程执行耗时的请求，完成后通过事件通道送回结果
+    fn spawn_job(&self, job: impl FnOnce(&dyn Provider) -> JobResult + Send + 'static) {
+        let provider = Arc::clone(&self.provider);
+        let events = self.events.clone();
+        thread::spawn(move || {
+            let _ = events.send(AppEvent::Job(job(provider.as_ref())));
+        });
+    }
+
+    /// 处理后台任务的结果
+    pub fn on_job(&mut self, result: JobResult) {
+        match result {
+            JobResult::Breadth(quotes) => {
+                self.breadth_pending = false;
+                self.apply_breadth(quotes);
+            }
+            JobResult::Screen(screen, result) => {
+                if screen == self.screen {
+                    self.screen_pending = false;
+                    self.apply_screen(result);
+                }
+            }
+            JobResult::Grid(updates) => {
+                self.grid_pending = false;
+                self.apply_grid(updates);
+            }
+            JobResult::Boards(kind, result) => self.apply_boards(kind, result),
+            JobResult::Comparison(load) => self.apply_comparison(load),
+            JobResult::Backtest(load) => self.apply_backtest(*load),
+            JobResult::BoardStocks(board, result) => self.apply_board_stocks(board, result),
+            JobResult::Profile(symbol, today, result) => self.apply_profile(symbol, today, result),
+            JobResult::QuoteExtras(extras) => {
+                for (symbol, extra) in extras {
+                    self.enrich_pending.remove(&symbol);
+                    self.enricher.insert(symbol, extra);
+                }
+                self.enricher.apply(&mut self.quotes);
+            }
+            JobResult::Factors(symbol, factors) => {
+                self.factors_pending.remove(&symbol);
+                // 获取失败时缓存空因子，避免每次刷新重复请求
+                self.factors
+                    .entry(symbol)
+                    .or_insert_with(|| factors.unwrap_or_default());
+            }
+        }
+    }
+
+    /// 在后台分页获取沪深A股全部行情（上一次尚未完成时忽略）
+    pub fn refresh_breadth(&mut self) {
+        self.timer.mark(RefreshItem::Breadth, Instant::now());
+        if self.breadth_pending {
+            return;
+        }
+        self.breadth_pending = true;
+        self.spawn_job(|provider| {
+            JobResult::Breadth(
+                breadth::fetch_all(provider, breadth::HS_A_NODE).map_err(|e| format!("{:#}", e)),
+            )
+        });
+    }
+
+    /// 统计市场宽度并记录分时采样；后台采样的结果只在打开视图时显示在状态栏
+    fn apply_breadth(&mut self, quotes: Result<Vec<StockQuote>, String>) {
+        let visible = self.view_mode == ViewMode::Breadth;
+        let quotes = match quotes {
+            Ok(quotes) if !quotes.is_empty() => quotes,
+            Ok(_) => {
+                if visible {
+                    self.status_message = "获取市场宽度失败: 无数据".to_string();
+                }
+                return;
+            }
+            Err(e) => {
+                if visible {
+                    self.status_message = format!("获取市场宽度失败: {}", e);
+                }
+                return;
+            }
+        };
+        let stats = Breadth::compute(&quotes);
+        let time = quotes
+            .iter()
+            .map(|q| q.time.as_str())
+            .max()
+            .unwrap_or_default()
+            .to_string();
+        if visible {
+            self.status_message = format!("市场宽度: {} 只股票 {}", quotes.len(), time);
+        }
+
+        // 只在交易日开盘后记录；休市或开盘前的行情属于上一交易日，沿用已有记录
+        let now = chrono::Utc::now().with_timezone(&Market::Cn.timezone());
+        let today = now.format("%Y-%m-%d").to_string();
+        let opened = self
+            .scheduler
+            .calendar()
+            .is_trading_day(Market::Cn, now.date_naive())
+            && now.format("%H:%M").to_string().as_str() >= "09:30";
+        if opened {
+            self.breadth_log.record(&today, &time, &stats);
+            if let Some(path) = Config::breadth_path() {
+                if let Err(e) = self.breadth_log.save(&path) {
+                    self.status_message = format!("{:#}", e);
+                }
+            }
+            self.breadth_date = today;
+        } else {
+            self.breadth_date = self
+                .breadth_log
+                .days
+                .keys()
+                .next_back()
+                .cloned()
+                .unwrap_or(today);
+        }
+        self.breadth = Some(stats);
+    }
+
+    /// 打开选股视图，首次打开时执行当前方案
+    pub fn open_screener(&mut self) {
+        self.view_mode = ViewMode::Screener;
+        if self.screen_result.is_none() {
+            self.run_screen();
+        }
+    }
+
+    /// 在后台执行当前选股方案（扫描全市场，可能需要较长时间）
+    pub fn run_screen(&mut self) {
+        let filter = match Filter::parse(&self.screen.filter) {
+            Ok(filter) => filter,
+            Err(e) => {
+                self.status_message = format!("选股条件错误: {:#}", e);
+                return;
+            }
+        };
+        self.screen_pending = true;
+        self.status_message = format!("正在扫描 {} ...", self.screen.universe);
+        let screen = self.screen.clone();
+        self.spawn_job(move |provider| {
+            let result =
+                screener::run(provider, &screen.universe, &filter).map_err(|e| format!("{:#}", e));
+            JobResult::Screen(screen, result)
+        });
+    }
+
+    fn apply_screen(&mut self, result: Result<ScreenResult, String>) {
+        match result {
+            Ok(result) => {
+                self.status_message = format!(
+                    "扫描 {} 只，符合 {} 只{}",
+                    result.scanned,
+                    result.matches.len(),
+                    if result.skipped > 0 {
+                        format!("，{} 只未获取K线", result.skipped)
+                    } else {
+                        String::new()
+                    }
+                );
+                self.screen_state
+                    .select((!result.matches.is_empty()).then_some(0));
+                self.screen_result = Some(result);
+                self.sort_screen_result();
+            }
+            Err(e) => self.status_message = format!("选股失败: {}", e),
+        }
+    }
+
+    /// 切换到下一个保存的选股方案并执行
+    pub fn next_screen(&mut self) {
+        let screens = &self.config.screens;
+        if screens.is_empty() {
+            self.status_message = "没有保存的选股方案，按 / 输入条件".to_string();
+            return;
+        }
+        let next = screens
+            .iter()
+            .position(|s| s.name == self.screen.name)
+            .map_or(0, |i| (i + 1) % screens.len());
+        self.screen = screens[next].clone();
+        self.run_screen();
+    }
+
+    /// 编辑选股条件
+    pub fn start_screen_filter(&mut self) {
+        self.input_mode = InputMode::ScreenFilter;
+        self.input_buffer = self.screen.filter.clone();
+        self.status_message = "输入选股条件，如 pct > 5 and vr > 2，Enter执行，Esc取消".to_string();
+    }
+
+    /// 执行输入的选股条件（成为未保存的方案）
+    pub fn confirm_screen_filter(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let filter = std::mem::take(&mut self.input_buffer);
+        if let Err(e) = Filter::parse(&filter) {
+            self.status_message = format!("选股条件错误: {:#}", e);
+            return;
+        }
+        self.screen = Screen {
+            name: String::new(),
+            filter,
+            universe: self.screen.universe.clone(),
+        };
+        self.run_screen();
+    }
+
+    /// 以名称保存当前选股方案
+    pub fn start_save_screen(&mut self) {
+        self.input_mode = InputMode::ScreenName;
+        self.input_buffer = self.screen.name.clone();
+        self.status_message = "输入方案名称，Enter保存（同名替换），Esc取消".to_string();
+    }
+
+    /// 确认保存选股方案到 config.json
+    pub fn confirm_save_screen(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let name = std::mem::take(&mut self.input_buffer).trim().to_string();
+        if name.is_empty() {
+            self.status_message = "方案名称不能为空".to_string();
+            return;
+        }
+        self.screen.name = name.clone();
+        self.config.set_screen(self.screen.clone());
+        self.save_config();
+        self.status_message = format!("已保存选股方案: {}", name);
+    }
+
+    /// 切换排序字段
+    pub fn cycle_screen_sort(&mut self) {
+        self.screen_sort = (self.screen_sort + 1) % screener::SORT_FIELDS.len();
+        self.sort_screen_result();
+    }
+
+    /// 切换升序 / 降序
+    pub fn reverse_screen_sort(&mut self) {
+        self.screen_ascending = !self.screen_ascending;
+        self.sort_screen_result();
+    }
+
+    fn sort_screen_result(&mut self) {
+        if let Some(result) = &mut self.screen_result {
+            let field = screener::SORT_FIELDS[self.screen_sort];
+            screener::sort(&mut result.matches, field, !self.screen_ascending);
+        }
+    }
+
+    /// 在选股结果中上下移动
+    pub fn screen_move(&mut self, delta: isize) {
+        let len = self.screen_result.as_ref().map_or(0, |r| r.matches.len());
+        if len == 0 {
+            return;
+        }
+        let current = self.screen_state.selected().unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(len as isize) as usize;
+        self.screen_state.select(Some(next));
+    }
+
+    /// 将选股结果中选中的股票加入自选股
+    pub fn add_screen_stock(&mut self) {
+        let selected = self
+            .screen_result
+            .as_ref()
+            .and_then(|r| r.matches.get(self.screen_state.selected()?));
+        if let Some(symbol) = selected.map(|q| q.symbol.clone()) {
+            self.add_symbol(symbol);
+        }
+    }
+
+    /// 打开 / 关闭模拟交易视图
+    pub fn toggle_paper(&mut self) {
+        if self.view_mode == ViewMode::Paper {
+            self.view_mode = ViewMode::Normal;
+        } else {
+            self.view_mode = ViewMode::Paper;
+        }
+    }
+
+    /// 当前使用的模拟账户（回放中为回放专用账户）
+    pub fn active_paper(&self) -> &Account {
+        self.replay.as_ref().map_or(&self.paper, |r| &r.account)
+    }
+
+    /// 下单使用的行情：回放中为当前K线，否则为当前股票的实时行情
+    pub fn order_quote(&self) -> Option<StockQuote> {
+        match &self.replay {
+            Some(r) => Some(r.replay.quote(&r.symbol, &r.name)),
+            None => self.current_quote().cloned(),
+        }
+    }
+
+    /// 开始输入当前股票的模拟委托
+    pub fn start_paper_order(&mut self, side: Side) {
+        let Some(q) = self.order_quote() else {
+            self.status_message = "当前股票无行情，无法下单".to_string();
+            return;
+        };
+        self.status_message = format!(
+            "{} {} {}: 输入数量，限价单在数量后加价格（如 200 10.50），Enter下单，Esc取消",
+            side.label(),
+            q.symbol,
+            q.name
+        );
+        self.paper_side = side;
+        self.input_mode = InputMode::PaperOrder;
         self.input_buffer.clear();
-        self.status_message = "输入代码 (sh600519/hk00700/gb_aapl)，Enter确认，Esc取消".to_string();
     }
 
-    /// 确认添加股票
-    pub fn confirm_add_stock(&mut self) {
-        let mut symbol = self.input_buffer.trim().to_lowercase();
-        if symbol.is_empty() {
-            self.status_message = "股票代码不能为空".to_string();
-            self.input_mode = InputMode::Normal;
+    /// 按输入的数量和限价下单
+    pub fn confirm_paper_order(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let input = std::mem::take(&mut self.input_buffer);
+        let mut parts = input.split_whitespace().map(str::parse::<f64>);
+        let (shares, limit) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(shares)), None, None) => (shares, None),
+            (Some(Ok(shares)), Some(Ok(price)), None) => (shares, Some(price)),
+            _ => {
+                self.status_message = format!("委托格式错误: {}（应为 数量 [限价]）", input);
+                return;
+            }
+        };
+        let Some(quote) = self.order_quote() else {
+            return;
+        };
+        let time = if self.replay.is_some() {
+            format!("{} {}", quote.date, quote.time)
+        } else {
+            let now = chrono::Utc::now().with_timezone(&Market::of(&quote.symbol).timezone());
+            now.format("%Y-%m-%d %H:%M:%S").to_string()
+        };
+        let side = self.paper_side;
+        let account = match &mut self.replay {
+            Some(r) => &mut r.account,
+            None => &mut self.paper,
+        };
+        match account.place(&quote, side, shares, limit, &quote.date, &time) {
+            Ok(id) => {
+                self.status_message = format!(
+                    "已委托 #{}: {} {} {} 股 {}",
+                    id,
+                    side.label(),
+                    quote.symbol,
+                    shares,
+                    limit.map_or("市价".to_string(), |p| format!("限价 {:.2}", p))
+                );
+                self.paper_state.select(Some(0));
+                if self.replay.is_some() {
+                    self.match_replay();
+                    return;
+                }
+                // 开市时立即用当前行情撮合
+                if self.is_live(&quote.symbol) {
+                    self.match_paper(std::slice::from_ref(&quote.symbol));
+                }
+                self.save_paper();
+            }
+            Err(e) => self.status_message = format!("下单失败: {:#}", e),
+        }
+    }
+
+    /// 撤销委托列表中选中的委托
+    pub fn cancel_paper_order(&mut self) {
+        let selected = self
+            .paper_state
+            .selected()
+            .and_then(|i| self.active_paper().orders.iter().rev().nth(i))
+            .map(|o| o.id);
+        let Some(id) = selected else {
+            return;
+        };
+        let cancelled = match &mut self.replay {
+            Some(r) => r.account.cancel(id),
+            None => self.paper.cancel(id),
+        };
+        if cancelled {
+            self.status_message = format!("已撤单 #{}", id);
+            if self.replay.is_none() {
+                self.save_paper();
+            }
+        } else {
+            self.status_message = format!("委托 #{} 不可撤销", id);
+        }
+    }
+
+    /// 在委托列表中移动选中
+    pub fn paper_move(&mut self, delta: isize) {
+        let len = self.active_paper().orders.len();
+        if len == 0 {
+            return;
+        }
+        let current = self.paper_state.selected().unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(len as isize) as usize;
+        self.paper_state.select(Some(next));
+    }
+
+    /// 将列表中高亮的股票加入 / 移出对比
+    pub fn toggle_compare_symbol(&mut self) {
+        let Some(symbol) = self.watchlist.get(self.highlighted_index()).cloned() else {
+            return;
+        };
+        if let Some(i) = self.compare_symbols.iter().position(|s| *s == symbol) {
+            self.compare_symbols.remove(i);
+            self.status_message = format!("已移出对比: {}", symbol);
+        } else {
+            self.status_message = format!("已加入对比: {}（C 查看对比图）", symbol);
+            self.compare_symbols.push(symbol);
+        }
+    }
+
+    /// 打开 / 关闭走势对比图
+    pub fn toggle_compare(&mut self) {
+        if self.view_mode == ViewMode::Compare {
+            self.view_mode = ViewMode::Normal;
+        } else {
+            self.view_mode = ViewMode::Compare;
+            self.load_comparison();
+        }
+    }
+
+    /// 在后台获取当前股票和对比股票在当前周期的K线并按日期对齐
+    ///
+    /// 已缓存的K线和复权因子直接交给后台任务，只有缺少的才在后台请求。
+    pub fn load_comparison(&mut self) {
+        let mut symbols: Vec<String> = self
+            .watchlist
+            .get(self.active_index)
+            .into_iter()
+            .cloned()
+            .collect();
+        for symbol in &self.compare_symbols {
+            if !symbols.contains(symbol) {
+                symbols.push(symbol.clone());
+            }
+        }
+
+        let (timeframe, mode) = (self.timeframe, self.adjust);
+        let inputs: Vec<CompareInput> = symbols
+            .into_iter()
+            .map(|symbol| CompareInput {
+                name: self
+                    .quote_of(&symbol)
+                    .map_or_else(|| symbol.clone(), |q| q.name.clone()),
+                klines: self.kline_cache.cached(&symbol, timeframe, COMPARE_LEN),
+                factors: self.factors.get(&symbol).cloned(),
+                symbol,
+            })
+            .collect();
+        self.status_message = "正在获取对比K线...".to_string();
+        let adjust_dir = self.config.adjust_dir.clone();
+        self.spawn_job(move |provider| {
+            JobResult::Comparison(ComparisonLoad::fetch(
+                provider,
+                adjust_dir.as_deref(),
+                inputs,
+                timeframe,
+                mode,
+            ))
+        });
+    }
+
+    /// 显示后台对齐好的对比数据（期间切换了股票、周期或复权方式的结果丢弃）
+    fn apply_comparison(&mut self, load: ComparisonLoad) {
+        for (symbol, klines) in load.fetched {
+            self.kline_cache
+                .insert(&symbol, load.timeframe, COMPARE_LEN, klines);
+        }
+        for (symbol, factors) in load.factors {
+            self.factors.entry(symbol).or_insert(factors);
+        }
+        let active = self.watchlist.get(self.active_index);
+        let first = load.comparison.series.first().map(|(s, _)| s);
+        if load.timeframe != self.timeframe
+            || load.adjust != self.adjust
+            || (active.is_some() && first != active)
+        {
             return;
         }
+        self.status_message = match load.errors.last() {
+            Some(e) => e.clone(),
+            None if load.comparison.series.len() < 2 => {
+                "对比需要至少两只股票：在自选股列表中按 x 加入对比".to_string()
+            }
+            None => format!("走势对比: {} 只股票", load.comparison.series.len()),
+        };
+        self.comparison = Some(load.comparison);
+        self.compare_cursor = None;
+    }
 
-        // 处理美股 us 前缀转 gb_
-        if symbol.starts_with("us") {
-            symbol = symbol.replacen("us", "gb_", 1);
+    /// 移动对比图游标（首次按下时出现在最后一天）
+    pub fn move_compare_cursor(&mut self, delta: isize) {
+        let len = self
+            .comparison
+            .as_ref()
+            .map_or(0, |c| c.aligned.dates.len());
+        if len == 0 {
+            return;
         }
+        let next = match self.compare_cursor {
+            Some(i) => (i as isize + delta).clamp(0, len as isize - 1) as usize,
+            None => len - 1,
+        };
+        self.compare_cursor = Some(next);
+    }
+
+    /// 自选股中某只股票的行情
+    pub fn quote_of(&self, symbol: &str) -> Option<&StockQuote> {
+        let i = self.watchlist.iter().position(|s| s == symbol)?;
+        self.quotes.get(i)?.as_ref()
+    }
+
+    /// 打开 / 关闭多图网格，首次打开时用自选股填充
+    pub fn toggle_grid(&mut self) {
+        if self.view_mode == ViewMode::Grid {
+            self.view_mode = ViewMode::Normal;
+            return;
+        }
+        self.view_mode = ViewMode::Grid;
+        self.fill_grid();
+        self.refresh_grid();
+        self.status_message =
+            "方向键选择, f 放大, 1-7 周期, [ ] 切换股票, e 输入代码, , . 滚动, + - 缩放, g 2x2/3x3, Esc 返回"
+                .to_string();
+    }
+
+    /// 网格格数不足时用未显示的自选股补齐，多余的格子移除
+    fn fill_grid(&mut self) {
+        let n = self.grid_size * self.grid_size;
+        self.grid.truncate(n);
+        let mut unused = self
+            .watchlist
+            .iter()
+            .filter(|s| !self.grid.iter().any(|c| c.symbol == **s))
+            .cloned()
+            .collect::<Vec<_>>()
+            .into_iter();
+        while self.grid.len() < n {
+            let Some(symbol) = unused.next() else {
+                break;
+            };
+            self.grid.push(GridCell::new(symbol, self.timeframe));
+        }
+        self.grid_focus = self.grid_focus.min(self.grid.len().saturating_sub(1));
+    }
 
-        // 检查格式: sh, sz, bj, hk, gb_
-        if !symbol.starts_with("sh")
-            && !symbol.starts_with("sz")
-            && !symbol.starts_with("bj")
-            && !symbol.starts_with("hk")
-            && !symbol.starts_with("gb_")
+    /// 切换 2x2 / 3x3 网格
+    pub fn cycle_grid_size(&mut self) {
+        self.grid_size = if self.grid_size == 2 { 3 } else { 2 };
+        self.fill_grid();
+        self.refresh_grid();
+        self.status_message = format!("网格 {}x{}", self.grid_size, self.grid_size);
+    }
+
+    /// 重新获取网格中所有小图的K线和行情
+    pub fn refresh_grid(&mut self) {
+        self.refresh_grid_cells(false);
+    }
+
+    /// 在后台重新获取网格小图的K线和行情，`live_only` 时只刷新开市中的股票（定时刷新）
+    ///
+    /// 上一次尚未完成时忽略。
+    fn refresh_grid_cells(&mut self, live_only: bool) {
+        self.timer.mark(RefreshItem::Grid, Instant::now());
+        if self.grid_pending {
+            return;
+        }
+        let mut cells: Vec<(String, TimeFrame, bool)> = Vec::new();
+        for cell in self
+            .grid
+            .iter()
+            .filter(|c| !live_only || self.is_live(&c.symbol))
         {
-            self.status_message =
-                "格式错误，支持前缀: sh/sz/bj(A股/北交), hk(港股), gb_/us(美股)".to_string();
-            self.input_mode = InputMode::Normal;
+            if !cells
+                .iter()
+                .any(|(s, tf, _)| *s == cell.symbol && *tf == cell.timeframe)
+            {
+                let need_factors = cell.adjust != Adjust::None
+                    && adjust::supports(&cell.symbol)
+                    && !self.factors.contains_key(&cell.symbol);
+                cells.push((cell.symbol.clone(), cell.timeframe, need_factors));
+            }
+        }
+        if cells.is_empty() {
             return;
         }
+        self.grid_pending = true;
+        let adjust_dir = self.config.adjust_dir.clone();
+        self.spawn_job(move |provider| {
+            let symbols: Vec<String> = cells.iter().map(|(s, _, _)| s.clone()).collect();
+            let quotes = provider.quotes(&symbols);
+            let updates = cells
+                .into_iter()
+                .zip(quotes)
+                .map(|((symbol, timeframe, need_factors), quote)| GridUpdate {
+                    klines: provider
+                        .klines(&symbol, timeframe, 120)
+                        .map_err(|e| format!("{:#}", e)),
+                    quote: quote.ok(),
+                    factors: need_factors.then(|| {
+                        load_factors(provider, adjust_dir.as_deref(), &symbol)
+                            .map_err(|e| e.to_string())
+                    }),
+                    symbol,
+                    timeframe,
+                })
+                .collect();
+            JobResult::Grid(updates)
+        });
+    }
+
+    /// 用后台获取的数据更新网格小图
+    ///
+    /// 只更新各小图所用周期的缓存，不影响主图等其他缓存；获取失败时保留原数据
+    fn apply_grid(&mut self, updates: Vec<GridUpdate>) {
+        for update in updates {
+            let GridUpdate {
+                symbol,
+                timeframe,
+                klines,
+                quote,
+                factors,
+            } = update;
+            if let Some(factors) = factors {
+                let factors = factors.unwrap_or_else(|e| {
+                    self.status_message = format!("获取复权因子失败: {}", e);
+                    AdjustFactors::default()
+                });
+                self.factors.entry(symbol.clone()).or_insert(factors);
+            }
+            let data = match klines {
+                Ok(data) if !data.is_empty() => {
+                    self.kline_cache
+                        .insert(&symbol, timeframe, 120, data.clone());
+                    Some(data)
+                }
+                Ok(_) => None,
+                Err(e) => {
+                    self.status_message = format!("获取 {} K线失败: {}", symbol, e);
+                    None
+                }
+            };
+            for i in 0..self.grid.len() {
+                let cell = &self.grid[i];
+                if cell.symbol != symbol || cell.timeframe != timeframe {
+                    continue;
+                }
+                if let Some(data) = &data {
+                    let (data, applied) = self.adjusted(&symbol, data.clone(), cell.adjust);
+                    (self.grid[i].data, self.grid[i].applied) = (data, applied);
+                }
+                if let Some(quote) = &quote {
+                    self.grid[i].quote = Some(quote.clone());
+                }
+            }
+        }
+    }
 
+    /// 重新获取网格中选中小图的K线
+    fn reload_grid_cell(&mut self) {
+        let Some(cell) = self.grid.get(self.grid_focus) else {
+            return;
+        };
+        let (symbol, timeframe, mode) = (cell.symbol.clone(), cell.timeframe, cell.adjust);
+        let data = self
+            .kline_cache
+            .get(self.provider.as_ref(), &symbol, timeframe, 120);
+        let quote = self.provider.quote(&symbol).ok();
+        let (data, applied) = match data {
+            Ok(data) => self.adjusted(&symbol, data, mode),
+            Err(e) => {
+                self.status_message = format!("获取 {} K线失败: {:#}", symbol, e);
+                (Vec::new(), Adjust::None)
+            }
+        };
+        let cell = &mut self.grid[self.grid_focus];
+        cell.data = data;
+        cell.applied = applied;
+        cell.quote = quote;
+        cell.offset = 0;
+    }
+
+    /// 在网格中移动选中（方向键）
+    pub fn move_grid_focus(&mut self, dx: isize, dy: isize) {
+        if self.grid.is_empty() {
+            return;
+        }
+        let size = self.grid_size as isize;
+        let (row, col) = (
+            self.grid_focus as isize / size,
+            self.grid_focus as isize % size,
+        );
+        let row = (row + dy).rem_euclid(size);
+        let col = (col + dx).rem_euclid(size);
+        self.grid_focus = ((row * size + col) as usize).min(self.grid.len() - 1);
+    }
+
+    /// 放大 / 还原选中的小图
+    pub fn toggle_grid_expand(&mut self) {
+        self.grid_expanded = !self.grid_expanded;
+    }
+
+    /// 设置选中小图的周期
+    pub fn set_grid_timeframe(&mut self, tf: TimeFrame) {
+        if let Some(cell) = self.grid.get_mut(self.grid_focus) {
+            cell.timeframe = tf;
+            self.reload_grid_cell();
+        }
+    }
+
+    /// 切换选中小图的复权方式
+    pub fn cycle_grid_adjust(&mut self) {
+        let Some(cell) = self.grid.get_mut(self.grid_focus) else {
+            return;
+        };
+        cell.adjust = cell.adjust.next();
+        let mode = cell.adjust;
+        self.reload_grid_cell();
+        if mode != Adjust::None && self.grid[self.grid_focus].applied == Adjust::None {
+            self.status_message = format!("{}: 仅A股支持复权，或没有除权数据", mode.label());
+        }
+    }
+
+    /// 选中小图切换为自选股中的上一只 / 下一只
+    pub fn cycle_grid_symbol(&mut self, delta: isize) {
+        let Some(cell) = self.grid.get_mut(self.grid_focus) else {
+            return;
+        };
+        if self.watchlist.is_empty() {
+            return;
+        }
+        let current = self.watchlist.iter().position(|s| *s == cell.symbol);
+        let next = match current {
+            Some(i) => (i as isize + delta).rem_euclid(self.watchlist.len() as isize) as usize,
+            None => 0,
+        };
+        cell.symbol = self.watchlist[next].clone();
+        self.reload_grid_cell();
+    }
+
+    /// 选中小图向左 / 向右滚动
+    pub fn scroll_grid_cell(&mut self, left: bool) {
+        if let Some(cell) = self.grid.get_mut(self.grid_focus) {
+            cell.offset = if left {
+                (cell.offset + 5).min(cell.data.len().saturating_sub(10))
+            } else {
+                cell.offset.saturating_sub(5)
+            };
+        }
+    }
+
+    /// 开始输入选中小图的股票代码
+    pub fn start_grid_symbol(&mut self) {
+        self.input_mode = InputMode::GridSymbol;
+        self.input_buffer.clear();
+        self.status_message = "输入股票代码（可以不在自选股中），Enter确认，Esc取消".to_string();
+    }
+
+    /// 将选中小图换成输入的股票
+    pub fn confirm_grid_symbol(&mut self) {
+        self.input_mode = InputMode::Normal;
+        match normalize_symbol(&std::mem::take(&mut self.input_buffer)) {
+            Ok(symbol) => {
+                match self.grid.get_mut(self.grid_focus) {
+                    Some(cell) => cell.symbol = symbol,
+                    None => self.grid.push(GridCell::new(symbol, self.timeframe)),
+                }
+                self.reload_grid_cell();
+            }
+            Err(msg) => self.status_message = msg.to_string(),
+        }
+    }
+
+    /// 开始输入历史回放的开始日期
+    pub fn start_replay_prompt(&mut self) {
+        if self.watchlist.get(self.active_index).is_none() {
+            return;
+        }
+        self.input_mode = InputMode::ReplayDate;
+        self.input_buffer.clear();
+        self.status_message = format!(
+            "输入回放开始日期 (YYYY-MM-DD)，当前周期 {}，Enter开始，Esc取消",
+            self.timeframe.label()
+        );
+    }
+
+    /// 获取当前股票和周期的K线，从输入的日期开始回放
+    pub fn confirm_replay(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let date = std::mem::take(&mut self.input_buffer).trim().to_string();
+        if chrono::NaiveDate::parse_from_str(&date, "%Y-%m-%d").is_err() {
+            self.status_message = format!("日期格式错误: {}（应为 YYYY-MM-DD）", date);
+            return;
+        }
+        let Some(symbol) = self.watchlist.get(self.active_index).cloned() else {
+            return;
+        };
+        let len = self.config.replay.len;
+        let data = match self
+            .kline_cache
+            .get(self.provider.as_ref(), &symbol, self.timeframe, len)
+        {
+            Ok(data) => data,
+            Err(e) => {
+                self.status_message = format!("获取回放K线失败: {:#}", e);
+                return;
+            }
+        };
+        // 前复权以当前回放的K线为基准逐根计算，不以最新的除权为基准，避免引入回放日之后的除权
+        let forward = (self.adjust == Adjust::Forward && adjust::supports(&symbol))
+            .then(|| self.ensure_factors(&symbol).clone())
+            .filter(|factors| !factors.is_empty());
+        let (data, applied) = match forward {
+            Some(_) => (data, Adjust::Forward),
+            None => self.adjusted(&symbol, data, self.adjust),
+        };
+        let replay = match Replay::new(data, &date) {
+            Ok(replay) => match forward {
+                Some(factors) => replay.with_forward(factors),
+                None => replay,
+            },
+            Err(e) => {
+                self.status_message = format!("无法回放: {:#}", e);
+                return;
+            }
+        };
+        let name = self
+            .current_quote()
+            .map_or(String::new(), |q| q.name.clone());
+        self.replay = Some(ReplaySession {
+            symbol,
+            name,
+            replay,
+            playing: false,
+            speed_ms: self.config.replay.speed_ms.max(MIN_REPLAY_SPEED_MS),
+            last_step: Instant::now(),
+            account: Account::new(self.config.paper.initial_cash),
+        });
+        self.kline_adjust = applied;
+        // 回测结果包含未来数据，回放时关闭
+        self.backtest = None;
+        self.kline_offset = 0;
+        self.kline_cursor = None;
+        self.paper_state = TableState::default();
+        self.apply_replay();
+    }
+
+    /// 退出回放，恢复最新K线
+    pub fn stop_replay(&mut self) {
+        if let Some(r) = self.replay.take() {
+            let profit = r.account.equity() - r.account.initial_cash;
+            self.paper_state = TableState::default();
+            self.refresh_kline();
+            self.status_message = format!("已退出回放，回放模拟盈亏 {:+.2}", profit);
+        }
+    }
+
+    /// 回放前进 n 根K线
+    pub fn replay_step(&mut self, n: usize) {
+        let Some(r) = &mut self.replay else {
+            return;
+        };
+        r.last_step = Instant::now();
+        if r.replay.step(n) == 0 {
+            r.playing = false;
+            return;
+        }
+        if r.replay.finished() {
+            r.playing = false;
+        }
+        self.apply_replay();
+        self.match_replay();
+    }
+
+    /// 播放 / 暂停回放
+    pub fn toggle_replay_play(&mut self) {
+        if let Some(r) = &mut self.replay {
+            r.playing = !r.playing && !r.replay.finished();
+            r.last_step = Instant::now();
+        }
+        self.apply_replay();
+    }
+
+    /// 调整回放速度（每根K线的间隔减半 / 加倍）
+    pub fn replay_speed(&mut self, faster: bool) {
+        if let Some(r) = &mut self.replay {
+            r.speed_ms = if faster {
+                (r.speed_ms / 2).max(MIN_REPLAY_SPEED_MS)
+            } else {
+                (r.speed_ms * 2).min(MAX_REPLAY_SPEED_MS)
+            };
+        }
+        self.apply_replay();
+    }
+
+    /// 自动播放：按经过的时间前进（刷新间隔大于速度时一次前进多根）
+    fn tick_replay(&mut self) {
+        let Some(r) = &self.replay else {
+            return;
+        };
+        if !r.playing {
+            return;
+        }
+        let steps = r.last_step.elapsed().as_millis() / r.speed_ms as u128;
+        if steps > 0 {
+            self.replay_step(steps as usize);
+        }
+    }
+
+    /// 显示回放到当前K线为止的数据（指标只用已显示的K线计算）
+    fn apply_replay(&mut self) {
+        let Some(r) = &self.replay else {
+            return;
+        };
+        self.kline_data = r.replay.visible();
+        let k = r.replay.current();
+        let (done, total) = r.replay.progress();
+        let state = if r.playing {
+            format!("播放中 {:.2}秒/根", r.speed_ms as f64 / 1000.0)
+        } else if r.replay.finished() {
+            "已结束".to_string()
+        } else {
+            "暂停".to_string()
+        };
+        self.status_message = format!(
+            "回放 {} {} 收 {} | {}/{} {} | 模拟盈亏 {:+.2} (空格 播放/暂停, n 下一根, +/- 速度, b/s 买卖, P 账户, Esc 退出)",
+            r.symbol,
+            k.day,
+            k.close,
+            done,
+            total,
+            state,
+            r.account.equity() - r.account.initial_cash
+        );
+    }
+
+    /// 用当前K线撮合回放账户的委托
+    fn match_replay(&mut self) {
+        let Some(r) = &mut self.replay else {
+            return;
+        };
+        let quote = r.replay.quote(&r.symbol, &r.name);
+        let fills = r.account.match_quote(&quote, &self.config.paper.fees);
+        r.account.record_day(&quote.date);
+        self.apply_replay();
+        if let Some(fill) = fills.last() {
+            self.status_message = format!(
+                "回放成交: {} {} {} 股 @ {:.2}",
+                fill.side.label(),
+                fill.symbol,
+                fill.shares,
+                fill.price
+            );
+        }
+    }
+
+    /// 对当前股票和周期运行回测（A股使用前复权K线），再按一次关闭
+    pub fn toggle_backtest(&mut self) {
+        if self.backtest_pending {
+            self.status_message = "回测中…".to_string();
+            return;
+        }
+        if self.backtest.take().is_some() {
+            self.status_message = "已关闭回测".to_string();
+            return;
+        }
+        let Some(symbol) = self.watchlist.get(self.active_index).cloned() else {
+            return;
+        };
+        let config = self.config.backtest.clone();
+        let strategy = match Strategy::parse(&config.buy, &config.sell) {
+            Ok(strategy) => strategy,
+            Err(e) => {
+                self.status_message = format!("回测条件错误: {:#}", e);
+                return;
+            }
+        };
+        let timeframe = self.timeframe;
+        let name = self
+            .current_quote()
+            .map_or(String::new(), |q| q.name.clone());
+        let cached = self.kline_cache.cached(&symbol, timeframe, config.len);
+        let factors = self.factors.get(&symbol).cloned();
+        let adjust_dir = self.config.adjust_dir.clone();
+        self.backtest_pending = true;
+        self.status_message = "回测中…".to_string();
+        self.spawn_job(move |provider| {
+            let mut load = BacktestLoad {
+                symbol,
+                timeframe,
+                len: config.len,
+                fetched: None,
+                factors: None,
+                result: Err(String::new()),
+            };
+            let data = match cached {
+                Some(data) => data,
+                None => match provider.klines(&load.symbol, timeframe, config.len) {
+                    Ok(data) => {
+                        load.fetched = Some(data.clone());
+                        data
+                    }
+                    Err(e) => {
+                        load.result = Err(format!("获取回测K线失败: {:#}", e));
+                        return JobResult::Backtest(Box::new(load));
+                    }
+                },
+            };
+            if data.is_empty() {
+                load.result = Err(format!("{} 无K线数据，无法回测", load.symbol));
+                return JobResult::Backtest(Box::new(load));
+            }
+            // 除权缺口会产生虚假信号，回测总是使用前复权数据
+            let data = if adjust::supports(&load.symbol) {
+                let factors = factors.unwrap_or_else(|| {
+                    let factors = load_factors(provider, adjust_dir.as_deref(), &load.symbol)
+                        .unwrap_or_default();
+                    load.factors = Some(factors.clone());
+                    factors
+                });
+                factors.apply(&data, Adjust::Forward)
+            } else {
+                data
+            };
+            let result = backtest::run(&load.symbol, &name, &data, &strategy, &config);
+            let message = format!(
+                "回测 {} {} 根{}: 买入 {} / 卖出 {}",
+                load.symbol,
+                data.len(),
+                timeframe.short_label(),
+                config.buy,
+                config.sell
+            );
+            load.result = Ok((
+                BacktestRun {
+                    symbol: load.symbol.clone(),
+                    timeframe,
+                    days: data.into_iter().map(|k| k.day).collect(),
+                    result,
+                },
+                message,
+            ));
+            JobResult::Backtest(Box::new(load))
+        });
+    }
+
+    /// 显示后台回测的结果，并把获取到的K线和复权因子写回缓存
+    fn apply_backtest(&mut self, load: BacktestLoad) {
+        self.backtest_pending = false;
+        if let Some(data) = load.fetched {
+            self.kline_cache
+                .insert(&load.symbol, load.timeframe, load.len, data);
+        }
+        if let Some(factors) = load.factors {
+            self.factors.entry(load.symbol).or_insert(factors);
+        }
+        match load.result {
+            // 回测结果包含未来数据，回放期间完成的丢弃
+            Ok(_) if self.replay.is_some() => {}
+            Ok((run, message)) => {
+                self.status_message = message;
+                self.backtest = Some(run);
+            }
+            Err(e) => self.status_message = e,
+        }
+    }
+
+    /// 与当前股票和周期对应的回测
+    pub fn active_backtest(&self) -> Option<&BacktestRun> {
+        let symbol = self.watchlist.get(self.active_index)?;
+        self.backtest
+            .as_ref()
+            .filter(|b| b.symbol == *symbol && b.timeframe == self.timeframe)
+    }
+
+    /// 获取当前可见K线数量（用于游标边界检查）
+    pub fn visible_kline_count(&self) -> usize {
+        // 去掉外框边框(2) + 左侧价格轴(10)
+        viewport::visible_count(
+            self.chart_width.saturating_sub(12),
+            self.chart_zoom.candle_width(),
+            self.kline_data.len(),
+        )
+    }
+
+    /// 缩放K线图：有游标时游标所在的K线保持在窗口中的相对位置，否则保持最右侧的K线不变
+    pub fn zoom_chart(&mut self, zoom_in: bool) {
+        let next = if zoom_in {
+            self.chart_zoom.zoom_in()
+        } else {
+            self.chart_zoom.zoom_out()
+        };
+        if next == self.chart_zoom {
+            return;
+        }
+        let old_count = self.visible_kline_count();
+        self.chart_zoom = next;
+        self.status_message = format!("K线缩放: {}", next.label());
+        (self.kline_offset, self.kline_cursor) = viewport::zoom(
+            self.kline_data.len(),
+            self.kline_offset,
+            self.kline_cursor,
+            old_count,
+            self.visible_kline_count(),
+        );
+    }
+
+    /// K线游标左移
+    pub fn cursor_left(&mut self, max_visible: usize) {
+        match self.kline_cursor {
+            Some(pos) => {
+                if pos > 0 {
+                    self.kline_cursor = Some(pos - 1);
+                }
+            }
+            None => {
+                // 首次激活游标，放在最右边（最新K线）
+                if !self.kline_data.is_empty() {
+                    let last = max_visible.min(self.kline_data.len()).saturating_sub(1);
+                    self.kline_cursor = Some(last);
+                }
+            }
+        }
+    }
+
+    /// K线游标右移
+    pub fn cursor_right(&mut self, max_visible: usize) {
+        match self.kline_cursor {
+            Some(pos) => {
+                let limit = max_visible.min(self.kline_data.len()).saturating_sub(1);
+                if pos < limit {
+                    self.kline_cursor = Some(pos + 1);
+                }
+            }
+            None => {
+                // 首次激活游标，放在最右边（最新K线）
+                if !self.kline_data.is_empty() {
+                    let last = max_visible.min(self.kline_data.len()).saturating_sub(1);
+                    self.kline_cursor = Some(last);
+                }
+            }
+        }
+    }
+
+    /// 导出当前K线数据（含均线列）
+    pub fn export_kline(&mut self) {
+        let Some(symbol) = self.watchlist.get(self.active_index) else {
+            return;
+        };
+        if self.kline_data.is_empty() {
+            self.status_message = "无K线数据可导出".to_string();
+            return;
+        }
+        let result = export::export_klines(
+            &self.config.export_dir(),
+            symbol,
+            self.timeframe,
+            &self.kline_data,
+            self.config.export_format,
+        );
+        self.status_message = match result {
+            Ok(path) => format!("已导出K线: {}", path.display()),
+            Err(e) => format!("导出失败: {}", e),
+        };
+    }
+
+    /// 导出自选股行情快照
+    pub fn export_watchlist(&mut self) {
+        let quotes: Vec<StockQuote> = self.quotes.iter().flatten().cloned().collect();
+        if quotes.is_empty() {
+            self.status_message = "无行情数据可导出".to_string();
+            return;
+        }
+        let result = export::export_quotes(
+            &self.config.export_dir(),
+            &quotes,
+            self.config.export_format,
+        );
+        self.status_message = match result {
+            Ok(path) => format!("已导出自选股: {}", path.display()),
+            Err(e) => format!("导出失败: {}", e),
+        };
+    }
+
+    /// 进入添加股票模式
+    pub fn start_add_stock(&mut self) {
+        self.input_mode = InputMode::AddStock;
+        self.input_buffer.clear();
+        self.status_message = "输入代码 (sh600519/hk00700/gb_aapl)，Enter确认，Esc取消".to_string();
+    }
+
+    /// 确认添加股票
+    pub fn confirm_add_stock(&mut self) {
+        let symbol = match normalize_symbol(&self.input_buffer) {
+            Ok(symbol) => symbol,
+            Err(msg) => {
+                self.status_message = msg.to_string();
+                self.input_mode = InputMode::Normal;
+                return;
+            }
+        };
+
+        self.input_mode = InputMode::Normal;
+        self.add_symbol(symbol);
+    }
+
+    /// 添加股票到自选股并获取行情
+    fn add_symbol(&mut self, symbol: String) {
         // 检查重复
         if self.watchlist.contains(&symbol) {
             self.status_message = format!("{} 已在自选股列表中", symbol);
-            self.input_mode = InputMode::Normal;
             return;
         }
 
@@ -314,7 +2553,7 @@ impl App {
         self.quotes.push(None);
 
         // 获取新股票行情
-        match api::fetch_realtime_quote(&symbol) {
+        match self.provider.quote(&symbol) {
             Ok(q) => {
                 self.status_message = format!("已添加: {} {}", q.symbol, q.name);
                 let idx = self.quotes.len() - 1;
@@ -327,8 +2566,6 @@ impl App {
                 self.save_config();
             }
         }
-
-        self.input_mode = InputMode::Normal;
     }
 
     /// 取消输入
@@ -362,13 +2599,27 @@ impl App {
 
         self.save_config();
         self.refresh_kline();
+        self.load_detail();
     }
     fn save_config(&mut self) {
-        let config = Config {
-            watchlist: self.watchlist.clone(),
-        };
-        if let Err(e) = config.save() {
+        self.config.watchlist = self.watchlist.clone();
+        if let Err(e) = self.config.save() {
             self.status_message = format!("配置保存失败: {}", e);
         }
     }
 }
+
+/// 复权因子：优先读取 adjust_dir 下导入的除权文件，否则从数据源获取
+fn load_factors(
+    provider: &dyn Provider,
+    adjust_dir: Option<&std::path::Path>,
+    symbol: &str,
+) -> anyhow::Result<AdjustFactors> {
+    if let Some(dir) = adjust_dir {
+        let path = dir.join(format!("{}.csv", symbol));
+        if path.exists() {
+            return AdjustFactors::load_csv(&path);
+        }
+    }
+    provider.adjust_factors(symbol)
+}
@@ -0,0 +1,536 @@
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::formula::Formula;
+use crate::limit::{Board, LimitPrices};
+use crate::market::Market;
+use crate::models::{KLineData, StockQuote};
+use crate::screener::{Field, Filter};
+
+/// 回测设置（config.json 的 backtest 项）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BacktestConfig {
+    /// 买入条件（选股条件语法或通达信公式），由不满足变为满足时发出买入信号
+    pub buy: String,
+    /// 卖出条件，由不满足变为满足时发出卖出信号
+    pub sell: String,
+    /// 初始资金（元）
+    pub initial_cash: f64,
+    /// 回测使用的K线根数
+    pub len: u32,
+    /// 交易费用
+    pub fees: Fees,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            buy: "ma5 > ma20".to_string(),
+            sell: "ma5 < ma20".to_string(),
+            initial_cash: 100_000.0,
+            len: 500,
+            fees: Fees::default(),
+        }
+    }
+}
+
+/// 交易费用（比例均为成交金额的比例）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Fees {
+    /// 佣金费率（买卖双向）
+    pub commission_rate: f64,
+    /// 每笔最低佣金（元）
+    pub min_commission: f64,
+    /// 印花税（仅卖出）
+    pub stamp_tax_rate: f64,
+    /// 过户费（买卖双向）
+    pub transfer_fee_rate: f64,
+}
+
+impl Default for Fees {
+    fn default() -> Self {
+        Self {
+            commission_rate: 0.00025,
+            min_commission: 5.0,
+            stamp_tax_rate: 0.0005,
+            transfer_fee_rate: 0.00001,
+        }
+    }
+}
+
+impl Fees {
+    /// 买入的费用
+    pub fn buy_cost(&self, amount: f64) -> f64 {
+        (amount * self.commission_rate).max(self.min_commission) + amount * self.transfer_fee_rate
+    }
+
+    /// 卖出的费用（含印花税）
+    pub fn sell_cost(&self, amount: f64) -> f64 {
+        self.buy_cost(amount) + amount * self.stamp_tax_rate
+    }
+}
+
+/// 买入或卖出条件
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// 选股条件表达式，如 `ma5 > ma20`
+    Filter(Filter),
+    /// 通达信公式，如 `CROSS(MA(C,5),MA(C,20))`
+    Formula(Formula),
+}
+
+impl Rule {
+    /// 解析条件：先按选股条件语法，不符合时按通达信公式
+    ///
+    /// 成交额、换手率、市盈率和市值只有实时行情才有，K线中没有，用在回测条件中时报错。
+    pub fn parse(text: &str) -> Result<Self> {
+        match Filter::parse(text) {
+            Ok(filter) => {
+                if let Some(field) = filter.fields().into_iter().find(|f| {
+                    matches!(
+                        f,
+                        Field::Turnover
+                            | Field::TurnoverRate
+                            | Field::Pe
+                            | Field::MarketCap
+                            | Field::FloatMarketCap
+                    )
+                }) {
+                    anyhow::bail!("回测不支持字段 {}：K线中没有该数据", field.label());
+                }
+                Ok(Rule::Filter(filter))
+            }
+            Err(filter_err) => Formula::parse(text)
+                .map(Rule::Formula)
+                .map_err(|formula_err| {
+                    anyhow::anyhow!(
+                        "既不是选股条件 ({:#}) 也不是通达信公式 ({:#})",
+                        filter_err,
+                        formula_err
+                    )
+                }),
+        }
+    }
+
+    /// 每根K线收盘时条件是否成立，指标未就绪为 None
+    fn states(&self, data: &[KLineData]) -> Vec<Option<bool>> {
+        match self {
+            Rule::Filter(filter) => {
+                let warmup = filter.warmup();
+                (0..data.len())
+                    .map(|i| {
+                        (i + 1 >= warmup).then(|| {
+                            filter.eval(&bar_quote(data, i), Some(&data[..=i])) == Some(true)
+                        })
+                    })
+                    .collect()
+            }
+            Rule::Formula(formula) => formula.states(data),
+        }
+    }
+}
+
+/// 交易规则：买入 / 卖出条件
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strategy {
+    /// 买入条件
+    pub buy: Rule,
+    /// 卖出条件
+    pub sell: Rule,
+}
+
+impl Strategy {
+    /// 解析买入 / 卖出条件（选股条件或通达信公式）
+    pub fn parse(buy: &str, sell: &str) -> Result<Self> {
+        Ok(Self {
+            buy: Rule::parse(buy).context("买入条件")?,
+            sell: Rule::parse(sell).context("卖出条件")?,
+        })
+    }
+
+    /// 每根K线收盘时的信号：买入为 Some(true)，卖出为 Some(false)
+    ///
+    /// 条件由不满足变为满足时才发出信号（如 `ma5 > ma20` 即金叉），
+    /// 前一根K线的指标未就绪时不发信号。
+    pub fn signals(&self, data: &[KLineData]) -> Vec<Option<bool>> {
+        let (buy, sell) = (self.buy.states(data), self.sell.states(data));
+        let turned_on = |states: &[Option<bool>], i: usize| {
+            states[i] == Some(true) && i > 0 && states[i - 1] == Some(false)
+        };
+        (0..data.len())
+            .map(|i| {
+                if turned_on(&buy, i) {
+                    Some(true)
+                } else if turned_on(&sell, i) {
+                    Some(false)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+/// 把第 i 根K线当作行情（收盘价为当前价，前一根的收盘价为昨收）
+pub fn bar_quote(data: &[KLineData], i: usize) -> StockQuote {
+    let k = &data[i];
+    StockQuote {
+        current: k.close_f64(),
+        open: k.open_f64(),
+        high: k.high_f64(),
+        low: k.low_f64(),
+        volume: k.volume_f64(),
+        pre_close: i
+            .checked_sub(1)
+            .map_or(k.open_f64(), |p| data[p].close_f64()),
+        date: k.day.clone(),
+        ..StockQuote::default()
+    }
+}
+
+/// 一笔交易（买入到卖出）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Trade {
+    /// 买入的K线索引
+    pub buy_index: usize,
+    /// 买入日期
+    pub buy_day: String,
+    /// 买入价
+    pub buy_price: f64,
+    /// 股数
+    pub shares: f64,
+    /// 卖出的K线索引，仍持有时为 None
+    pub sell_index: Option<usize>,
+    /// 卖出日期
+    pub sell_day: Option<String>,
+    /// 卖出价（仍持有时为最后收盘价）
+    pub sell_price: f64,
+    /// 扣除费用后的盈亏（元）
+    pub profit: f64,
+    /// 收益率（百分比，相对买入成本）
+    pub return_percent: f64,
+}
+
+/// 回测统计
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Stats {
+    /// 总收益率（百分比）
+    pub total_return: f64,
+    /// 年化收益率（百分比），区间不足一天时为 None
+    pub cagr: Option<f64>,
+    /// 最大回撤（百分比）
+    pub max_drawdown: f64,
+    /// 胜率（百分比，仅已卖出的交易），没有交易时为 None
+    pub win_rate: Option<f64>,
+    /// 夏普比率（无风险利率按 0），无法计算时为 None
+    pub sharpe: Option<f64>,
+    /// 已卖出的交易笔数
+    pub trades: usize,
+    /// 因开盘涨停无法买入的次数
+    pub blocked_buys: usize,
+}
+
+/// 回测结果
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BacktestResult {
+    /// 每根K线收盘时的账户权益
+    pub equity: Vec<f64>,
+    /// 交易列表（最后一笔可能仍持有）
+    pub trades: Vec<Trade>,
+    /// 统计
+    pub stats: Stats,
+}
+
+/// 运行回测
+///
+/// 收盘出现信号后以下一根K线的开盘价成交，全仓买入。A股按 100 股一手、T+1 交收，
+/// 开盘涨停无法买入（放弃该信号），开盘跌停无法卖出（顺延到下一根）。
+pub fn run(
+    symbol: &str,
+    name: &str,
+    data: &[KLineData],
+    strategy: &Strategy,
+    config: &BacktestConfig,
+) -> BacktestResult {
+    let board = Board::of(symbol, name);
+    let lot = if Market::of(symbol) == Market::Cn {
+        100.0
+    } else {
+        1.0
+    };
+    let signals = strategy.signals(data);
+    let fees = &config.fees;
+
+    let mut result = BacktestResult::default();
+    let mut cash = config.initial_cash;
+    let mut holding: Option<Trade> = None;
+    let mut pending: Option<bool> = None;
+    for (i, k) in data.iter().enumerate() {
+        if let (Some(order), Some(prev)) = (pending, i.checked_sub(1).map(|p| &data[p])) {
+            let open = k.open_f64();
+            let limits = board.map(|b| LimitPrices::new(prev.close_f64(), b.ratio()));
+            match (order, holding.take()) {
+                (true, None) => {
+                    pending = None;
+                    if limits.is_some_and(|l| l.is_up(open)) {
+                        result.stats.blocked_buys += 1;
+                    } else {
+                        let mut shares = (cash / open / lot).floor() * lot;
+                        while shares > 0.0 && shares * open + fees.buy_cost(shares * open) > cash {
+                            shares -= lot;
+                        }
+                        if shares > 0.0 {
+                            let cost = shares * open + fees.buy_cost(shares * open);
+                            cash -= cost;
+                            holding = Some(Trade {
+                                buy_index: i,
+                                buy_day: k.day.clone(),
+                                buy_price: open,
+                                shares,
+                                sell_index: None,
+                                sell_day: None,
+                                sell_price: open,
+                                profit: 0.0,
+                                return_percent: 0.0,
+                            });
+                        }
+                    }
+                }
+                (false, Some(mut trade)) => {
+                    let same_day = board.is_some() && day_of(&trade.buy_day) == day_of(&k.day);
+                    if same_day || limits.is_some_and(|l| l.is_down(open)) {
+                        holding = Some(trade);
+                    } else {
+                        pending = None;
+                        let amount = trade.shares * open;
+                        cash += amount - fees.sell_cost(amount);
+                        close_trade(&mut trade, fees, open);
+                        trade.sell_index = Some(i);
+                        trade.sell_day = Some(k.day.clone());
+                        result.trades.push(trade);
+                    }
+                }
+                (_, other) => {
+                    pending = None;
+                    holding = other;
+                }
+            }
+        }
+
+        let close = k.close_f64();
+        result
+            .equity
+            .push(cash + holding.as_ref().map_or(0.0, |t| t.shares * close));
+        match signals[i] {
+            Some(true) if holding.is_none() => pending = Some(true),
+            // 持仓时的买入信号取消尚未成交的卖出
+            Some(true) if pending == Some(false) => pending = None,
+            Some(false) if holding.is_some() => pending = Some(false),
+            _ => {}
+        }
+    }
+
+    // 仍持有的按最后收盘价计算浮动盈亏
+    if let (Some(mut trade), Some(last)) = (holding, data.last()) {
+        close_trade(&mut trade, fees, last.close_f64());
+        result.trades.push(trade);
+    }
+    result.stats = stats(data, &result, config.initial_cash);
+    result
+}
+
+/// 按卖出价计算一笔交易的盈亏（含买卖费用）
+fn close_trade(trade: &mut Trade, fees: &Fees, price: f64) {
+    let buy_amount = trade.shares * trade.buy_price;
+    let sell_amount = trade.shares * price;
+    let cost = buy_amount + fees.buy_cost(buy_amount);
+    trade.sell_price = price;
+    trade.profit = sell_amount - fees.sell_cost(sell_amount) - cost;
+    trade.return_percent = trade.profit / cost * 100.0;
+}
+
+/// K线时间中的日期部分（分钟K为 "YYYY-MM-DD HH:MM:SS"）
+fn day_of(day: &str) -> &str {
+    day.get(..10).unwrap_or(day)
+}
+
+fn stats(data: &[KLineData], result: &BacktestResult, initial_cash: f64) -> Stats {
+    let mut stats = result.stats.clone();
+    let Some(&last) = result.equity.last() else {
+        return stats;
+    };
+    stats.total_return = (last / initial_cash - 1.0) * 100.0;
+    stats.max_drawdown = max_drawdown(&result.equity);
+
+    let closed: Vec<&Trade> = result
+        .trades
+        .iter()
+        .filter(|t| t.sell_index.is_some())
+        .collect();
+    stats.trades = closed.len();
+    if !closed.is_empty() {
+        let wins = closed.iter().filter(|t| t.profit > 0.0).count();
+        stats.win_rate = Some(wins as f64 / closed.len() as f64 * 100.0);
+    }
+
+    let date = |k: Option<&KLineData>| NaiveDate::parse_from_str(day_of(&k?.day), "%Y-%m-%d").ok();
+    let years = match (date(data.first()), date(data.last())) {
+        (Some(first), Some(end)) => (end - first).num_days() as f64 / 365.25,
+        _ => 0.0,
+    };
+    if years > 0.0 {
+        stats.cagr = Some(((last / initial_cash).powf(1.0 / years) - 1.0) * 100.0);
+
+        // 按每年的K线根数把单根收益率的夏普比率年化
+        let returns: Vec<f64> = result
+            .equity
+            .windows(2)
+            .map(|w| w[1] / w[0] - 1.0)
+            .collect();
+        let n = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / n;
+        let std = (returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / n).sqrt();
+        if std > 0.0 {
+            stats.sharpe = Some(mean / std * (n / years).sqrt());
+        }
+    }
+    stats
+}
+
+/// 权益曲线的最大回撤（百分比）
+pub fn max_drawdown(equity: &[f64]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut worst: f64 = 0.0;
+    for &v in equity {
+        peak = peak.max(v);
+        if peak > 0.0 {
+            worst = worst.max((peak - v) / peak);
+        }
+    }
+    worst * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(day: &str, open: f64, close: f64) -> KLineData {
+        KLineData {
+            day: day.to_string(),
+            open: open.to_string(),
+            high: open.max(close).to_string(),
+            low: open.min(close).to_string(),
+            close: close.to_string(),
+            volume: "1000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_ma_cross_signals() {
+        // 先跌后涨再跌：金叉一次、死叉一次，均线就绪前不发信号
+        let closes: Vec<f64> = (0..10)
+            .map(|i| 20.0 - i as f64)
+            .chain((0..10).map(|i| 11.0 + 2.0 * i as f64))
+            .chain((0..10).map(|i| 28.0 - 3.0 * i as f64))
+            .collect();
+        let data: Vec<KLineData> = closes.iter().map(|&c| bar("2025-01-01", c, c)).collect();
+        let strategy = Strategy::parse("ma2 > ma5", "ma2 < ma5").unwrap();
+        let signals = strategy.signals(&data);
+        let marks: Vec<(usize, bool)> = signals
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| Some((i, (*s)?)))
+            .collect();
+        assert_eq!(marks, vec![(12, true), (21, false)]);
+        assert!(Strategy::parse("ma5 >", "ma5 < ma20").is_err());
+        // 量比由K线估算，换手率、市值等只有实时行情才有
+        assert!(Strategy::parse("vr > 2 and ma5 > ma20", "ma5 < ma20").is_ok());
+        for rule in [
+            "hs > 5",
+            "ma5 > ma20 or pe < 20",
+            "cap in 10亿..100亿",
+            "amount > 1亿",
+        ] {
+            let err = Strategy::parse(rule, "ma5 < ma20").unwrap_err();
+            assert!(format!("{:#}", err).contains("回测不支持字段"), "{}", rule);
+        }
+
+        // 等价的通达信公式发出相同的信号
+        let formula = Strategy::parse("CROSS(MA(C,2),MA(C,5))", "CROSS(MA(C,5),MA(C,2))").unwrap();
+        assert!(matches!(formula.buy, Rule::Formula(_)));
+        assert_eq!(formula.signals(&data), signals);
+    }
+
+    #[test]
+    fn test_run_with_a_share_rules() {
+        let data = vec![
+            bar("2025-01-27", 10.0, 10.0),
+            bar("2025-02-03", 10.0, 10.0),
+            bar("2025-02-04", 10.0, 10.6),   // 涨 6%：买入信号
+            bar("2025-02-05", 11.66, 11.66), // 开盘涨停，无法买入
+            bar("2025-02-06", 11.5, 11.0),
+            bar("2025-02-07", 11.0, 11.7), // 买入信号
+            bar("2025-02-10", 11.8, 12.0), // 开盘买入
+            bar("2025-02-11", 12.0, 11.9), // 下跌：卖出信号
+            bar("2025-02-12", 12.1, 12.2), // 开盘卖出
+        ];
+        let strategy = Strategy::parse("pct > 5", "pct < 0").unwrap();
+        let config = BacktestConfig::default();
+        let result = run("sh600000", "浦发银行", &data, &strategy, &config);
+
+        assert_eq!(result.stats.blocked_buys, 1);
+        assert_eq!(result.trades.len(), 1);
+        let t = &result.trades[0];
+        assert_eq!((t.buy_index, t.sell_index), (6, Some(8)));
+        // 100 股一手: 100000 / 11.8 = 8474 股 -> 8400 股
+        assert_eq!(t.shares, 8400.0);
+        let fees = Fees::default();
+        let expected = 8400.0 * 12.1
+            - fees.sell_cost(8400.0 * 12.1)
+            - (8400.0 * 11.8 + fees.buy_cost(8400.0 * 11.8));
+        assert!((t.profit - expected).abs() < 1e-6);
+        assert!((result.equity[8] - (100_000.0 + expected)).abs() < 1e-6);
+        assert_eq!(result.stats.win_rate, Some(100.0));
+        assert!(result.stats.cagr.is_some());
+    }
+
+    #[test]
+    fn test_t_plus_one_and_fees() {
+        // 分钟K：当天买入后的卖出信号顺延到下一个交易日
+        let data = vec![
+            bar("2025-02-11 09:55:00", 10.0, 10.0),
+            bar("2025-02-11 10:00:00", 10.0, 10.0),
+            bar("2025-02-11 10:05:00", 10.0, 10.6),
+            bar("2025-02-11 10:10:00", 10.6, 10.0),
+            bar("2025-02-11 10:15:00", 10.0, 10.1),
+            bar("2025-02-12 09:35:00", 10.2, 10.2),
+        ];
+        let strategy = Strategy::parse("pct > 5", "pct < 0").unwrap();
+        let result = run(
+            "sh600000",
+            "浦发银行",
+            &data,
+            &strategy,
+            &BacktestConfig::default(),
+        );
+        let t = &result.trades[0];
+        assert_eq!((t.buy_index, t.sell_index), (3, Some(5)));
+        // 美股没有 T+1 限制
+        let result = run(
+            "gb_aapl",
+            "苹果",
+            &data,
+            &strategy,
+            &BacktestConfig::default(),
+        );
+        assert_eq!(result.trades[0].sell_index, Some(4));
+
+        let fees = Fees::default();
+        assert!((fees.buy_cost(10_000.0) - 5.1).abs() < 1e-9);
+        assert!((fees.sell_cost(100_000.0) - 76.0).abs() < 1e-9);
+        assert_eq!(max_drawdown(&[100.0, 120.0, 90.0, 130.0]), 25.0);
+    }
+}
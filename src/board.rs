@@ -0,0 +1,179 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+
+use crate::models::{QuoteExt, StockQuote};
+
+/// 板块分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoardKind {
+    /// 新浪行业
+    Industry,
+    /// 概念板块
+    Concept,
+}
+
+impl BoardKind {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            BoardKind::Industry => "行业",
+            BoardKind::Concept => "概念",
+        }
+    }
+
+    /// 代码（录制会话中使用）
+    pub fn code(&self) -> &'static str {
+        match self {
+            BoardKind::Industry => "industry",
+            BoardKind::Concept => "concept",
+        }
+    }
+
+    /// 切换到另一种分类
+    pub fn next(&self) -> Self {
+        match self {
+            BoardKind::Industry => BoardKind::Concept,
+            BoardKind::Concept => BoardKind::Industry,
+        }
+    }
+}
+
+/// 板块行情
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sector {
+    /// 板块节点代码，如 new_blhy（用于获取成分股）
+    pub code: String,
+    /// 板块名称
+    pub name: String,
+    /// 成分股数量
+    pub count: u32,
+    /// 平均涨跌幅（百分比）
+    pub change_percent: f64,
+    /// 成交额（元）
+    pub turnover: f64,
+    /// 领涨股代码
+    pub leader_symbol: String,
+    /// 领涨股名称
+    pub leader_name: String,
+    /// 领涨股涨跌幅（百分比）
+    pub leader_change_percent: f64,
+}
+
+/// 解析新浪板块列表
+///
+/// 格式: `var S_Finance_bankuai_sinaindustry = {"new_blhy":"new_blhy,玻璃行业,19,19.30,0.12,0.62,成交量,成交额,sh600586,3.46,6.89,0.22,金晶科技",...}`
+pub fn parse_sina_boards(text: &str) -> Result<Vec<Sector>> {
+    let start = text.find('{').context("板块数据格式错误: 未找到 {")?;
+    let end = text.rfind('}').context("板块数据格式错误: 未找到 }")?;
+    let json: Value = serde_json::from_str(&text[start..=end]).context("解析板块数据 JSON 失败")?;
+    let map = json.as_object().context("板块数据不是对象")?;
+
+    let mut boards: Vec<Sector> = map
+        .values()
+        .filter_map(|v| {
+            let fields: Vec<&str> = v.as_str()?.split(',').collect();
+            if fields.len() < 13 {
+                return None;
+            }
+            Some(Sector {
+                code: fields[0].to_string(),
+                name: fields[1].to_string(),
+                count: fields[2].parse().unwrap_or(0),
+                change_percent: fields[5].parse().unwrap_or(0.0),
+                turnover: fields[7].parse().unwrap_or(0.0),
+                leader_symbol: fields[8].to_string(),
+                leader_change_percent: fields[9].parse().unwrap_or(0.0),
+                leader_name: fields[12].to_string(),
+            })
+        })
+        .collect();
+    // 按成交额从大到小（热力图中大块在前）
+    boards.sort_by(|a, b| b.turnover.total_cmp(&a.turnover));
+    Ok(boards)
+}
+
+/// 解析新浪行情中心的分页列表（板块成分股、沪深A股等）
+///
+/// 格式为 JSON 数组，元素如 `{"symbol":"sh600586","name":"金晶科技","trade":"6.890",
+/// "settlement":"6.670","open":"6.700","high":"6.950","low":"6.650","volume":1234,"amount":12345,
+/// "ticktime":"15:00:00","per":12.3,"mktcap":98765,"nmc":98765,"turnoverratio":1.23}`，
+/// 数值可能是字符串，市值单位为万元。
+pub fn parse_node_quotes(text: &str) -> Result<Vec<StockQuote>> {
+    let json: Value = serde_json::from_str(text.trim()).context("解析行情列表 JSON 失败")?;
+    let items = match json {
+        // 没有数据时接口返回 null
+        Value::Null => return Ok(Vec::new()),
+        Value::Array(items) => items,
+        _ => anyhow::bail!("行情列表不是数组"),
+    };
+
+    let num = |item: &Value, key: &str| -> Option<f64> {
+        match item.get(key)? {
+            Value::Number(n) => n.as_f64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        }
+    };
+    let text = |item: &Value, key: &str| {
+        item.get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    Ok(items
+        .iter()
+        .map(|item| StockQuote {
+            symbol: text(item, "symbol"),
+            name: text(item, "name"),
+            current: num(item, "trade").unwrap_or(0.0),
+            pre_close: num(item, "settlement").unwrap_or(0.0),
+            open: num(item, "open").unwrap_or(0.0),
+            high: num(item, "high").unwrap_or(0.0),
+            low: num(item, "low").unwrap_or(0.0),
+            volume: num(item, "volume").unwrap_or(0.0),
+            turnover: num(item, "amount").unwrap_or(0.0),
+            time: text(item, "ticktime"),
+            ext: QuoteExt {
+                turnover_rate: num(item, "turnoverratio"),
+                pe_ttm: num(item, "per").filter(|v| *v != 0.0),
+                market_cap: num(item, "mktcap").map(|v| v * 10000.0),
+                float_market_cap: num(item, "nmc").map(|v| v * 10000.0),
+                ..QuoteExt::default()
+            },
+            ..StockQuote::default()
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sina_boards() {
+        let text = r#"var S_Finance_bankuai_sinaindustry = {"new_blhy":"new_blhy,玻璃行业,19,19.30,0.12,0.62,110637283,1347396427,sh600586,3.46,6.89,0.22,金晶科技","new_cbzz":"new_cbzz,船舶制造,8,12.1,-0.2,-1.53,50000000,2347396427,sh600150,1.02,30.1,0.3,中国船舶","bad":"x"}"#;
+        let boards = parse_sina_boards(text).unwrap();
+        assert_eq!(boards.len(), 2);
+        assert_eq!(boards[0].name, "船舶制造");
+        assert_eq!(boards[0].change_percent, -1.53);
+        assert_eq!(boards[1].code, "new_blhy");
+        assert_eq!(boards[1].leader_name, "金晶科技");
+        assert_eq!(boards[1].count, 19);
+    }
+
+    #[test]
+    fn test_parse_node_quotes() {
+        let text = r#"[{"symbol":"sh600586","code":"600586","name":"金晶科技","trade":"6.890","pricechange":"0.220","changepercent":"3.298","settlement":"6.670","open":"6.700","high":"6.950","low":"6.650","volume":12345600,"amount":84512345,"ticktime":"15:00:00","per":12.5,"pb":1.2,"mktcap":984567.12,"nmc":984567.12,"turnoverratio":0.86}]"#;
+        let quotes = parse_node_quotes(text).unwrap();
+        let q = &quotes[0];
+        assert_eq!(
+            (q.symbol.as_str(), q.name.as_str()),
+            ("sh600586", "金晶科技")
+        );
+        assert_eq!((q.current, q.pre_close), (6.89, 6.67));
+        assert_eq!(q.ext.turnover_rate, Some(0.86));
+        assert!((q.ext.market_cap.unwrap() - 9845671200.0).abs() < 1.0);
+        assert!(parse_node_quotes("null").unwrap().is_empty());
+    }
+}
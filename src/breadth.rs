@@ -0,0 +1,269 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::limit::LimitState;
+use crate::models::StockQuote;
+use crate::provider::Provider;
+
+/// 沪深A股在新浪行情中心的节点
+pub const HS_A_NODE: &str = "hs_a";
+
+/// 分页获取时每页的数量（新浪接口上限为 100）
+const PAGE_SIZE: u32 = 100;
+
+/// 最多获取的页数，防止接口异常时无限翻页
+const MAX_PAGES: u32 = 100;
+
+/// 历史记录保留的天数
+const KEEP_DAYS: usize = 10;
+
+/// 涨跌幅分布的区间上界（百分比），最后一个区间无上界
+const BUCKETS: [(f64, &str); 11] = [
+    (-7.0, "<-7"),
+    (-5.0, "-7~-5"),
+    (-3.0, "-5~-3"),
+    (-1.0, "-3~-1"),
+    (-0.005, "-1~0"),
+    (0.005, "平"),
+    (1.0, "0~1"),
+    (3.0, "1~3"),
+    (5.0, "3~5"),
+    (7.0, "5~7"),
+    (f64::INFINITY, ">7"),
+];
+
+/// 分页获取行情中心某节点的全部股票
+pub fn fetch_all(provider: &dyn Provider, node: &str) -> Result<Vec<StockQuote>> {
+    let mut all = Vec::new();
+    for page in 1..=MAX_PAGES {
+        let quotes = provider.node_quotes(node, page, PAGE_SIZE)?;
+        let done = quotes.len() < PAGE_SIZE as usize;
+        all.extend(quotes);
+        if done {
+            break;
+        }
+    }
+    Ok(all)
+}
+
+/// 市场宽度统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Breadth {
+    /// 上涨家数
+    pub advancers: u32,
+    /// 下跌家数
+    pub decliners: u32,
+    /// 平盘家数
+    pub unchanged: u32,
+    /// 涨停家数
+    pub limit_up: u32,
+    /// 跌停家数
+    pub limit_down: u32,
+    /// 两市总成交额（元）
+    pub turnover: f64,
+    /// 涨跌幅分布: (区间名称, 家数)，从跌到涨
+    pub histogram: Vec<(&'static str, u32)>,
+}
+
+impl Breadth {
+    /// 由全市场行情计算（停牌、无成交的股票不计入）
+    pub fn compute(quotes: &[StockQuote]) -> Self {
+        let mut b = Breadth {
+            histogram: BUCKETS.iter().map(|&(_, label)| (label, 0)).collect(),
+            ..Self::default()
+        };
+        for q in quotes
+            .iter()
+            .filter(|q| q.current > 0.0 && q.pre_close > 0.0)
+        {
+            let pct = q.change_percent();
+            let change = q.change();
+            if change > 0.0005 {
+                b.advancers += 1;
+            } else if change < -0.0005 {
+                b.decliners += 1;
+            } else {
+                b.unchanged += 1;
+            }
+            match q.limit_state() {
+                Some(LimitState::LimitUp) => b.limit_up += 1,
+                Some(LimitState::LimitDown) => b.limit_down += 1,
+                _ => {}
+            }
+            b.turnover += q.turnover;
+            let bucket = BUCKETS
+                .iter()
+                .position(|&(upper, _)| pct < upper)
+                .unwrap_or(BUCKETS.len() - 1);
+            b.histogram[bucket].1 += 1;
+        }
+        b
+    }
+}
+
+/// 交易时段内的分钟序号（9:30-11:30 为 0-120，13:00-15:00 为 120-240），时段外为 None
+pub fn session_minute(time: &str) -> Option<u32> {
+    let (h, m) = time.split_once(':')?;
+    let minutes = h.parse::<u32>().ok()? * 60 + m.get(..2)?.parse::<u32>().ok()?;
+    match minutes {
+        570..=690 => Some(minutes - 570),
+        780..=900 => Some(minutes - 780 + 120),
+        _ => None,
+    }
+}
+
+/// 分时宽度采样
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BreadthSample {
+    /// 采样时间 (HH:MM)
+    pub time: String,
+    /// 上涨家数
+    pub advancers: u32,
+    /// 下跌家数
+    pub decliners: u32,
+}
+
+/// 某个交易日的记录
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayLog {
+    /// 最近一次统计的总成交额（元）
+    pub turnover: f64,
+    /// 当天的分时采样
+    pub samples: Vec<BreadthSample>,
+}
+
+/// 市场宽度历史：按日期保存成交额和分时采样，用于与昨日对比和绘制分时曲线
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreadthLog {
+    /// 日期 (YYYY-MM-DD) → 记录
+    pub days: BTreeMap<String, DayLog>,
+}
+
+impl BreadthLog {
+    /// 读取历史文件，不存在或格式错误时为空
+    pub fn load(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// 保存历史文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("保存市场宽度记录失败: {}", path.display()))
+    }
+
+    /// 记录一次统计：更新当天成交额，交易时段内追加分时采样（同一分钟只保留最后一次）
+    pub fn record(&mut self, date: &str, time: &str, breadth: &Breadth) {
+        let day = self.days.entry(date.to_string()).or_default();
+        day.turnover = breadth.turnover;
+        if session_minute(time).is_some() {
+            let sample = BreadthSample {
+                time: time.get(..5).unwrap_or(time).to_string(),
+                advancers: breadth.advancers,
+                decliners: breadth.decliners,
+            };
+            match day.samples.last_mut() {
+                Some(last) if last.time == sample.time => *last = sample,
+                _ => day.samples.push(sample),
+            }
+        }
+        while self.days.len() > KEEP_DAYS {
+            self.days.pop_first();
+        }
+    }
+
+    /// 某日之前最近一个交易日的成交额
+    pub fn previous_turnover(&self, date: &str) -> Option<f64> {
+        self.days
+            .range(..date.to_string())
+            .next_back()
+            .map(|(_, day)| day.turnover)
+    }
+
+    /// 某日的分时采样
+    pub fn samples(&self, date: &str) -> &[BreadthSample] {
+        self.days.get(date).map_or(&[], |day| &day.samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, pre_close: f64, current: f64, turnover: f64) -> StockQuote {
+        StockQuote {
+            symbol: symbol.to_string(),
+            name: "测试".to_string(),
+            pre_close,
+            current,
+            high: current.max(pre_close),
+            low: current.min(pre_close),
+            turnover,
+            ..StockQuote::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_breadth() {
+        let quotes = vec![
+            quote("sh600000", 10.0, 11.0, 100.0),
+            quote("sz300001", 10.0, 10.2, 50.0),
+            quote("sh600001", 10.0, 9.0, 20.0),
+            quote("sh600002", 10.0, 10.0, 5.0),
+            // 停牌
+            quote("sh600003", 10.0, 0.0, 0.0),
+        ];
+        let b = Breadth::compute(&quotes);
+        assert_eq!((b.advancers, b.decliners, b.unchanged), (2, 1, 1));
+        assert_eq!((b.limit_up, b.limit_down), (1, 1));
+        assert_eq!(b.turnover, 175.0);
+        let count = |label| b.histogram.iter().find(|(l, _)| *l == label).unwrap().1;
+        assert_eq!(
+            (count(">7"), count("1~3"), count("平"), count("<-7")),
+            (1, 1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_session_minute() {
+        assert_eq!(session_minute("09:30:00"), Some(0));
+        assert_eq!(session_minute("11:30"), Some(120));
+        assert_eq!(session_minute("13:01"), Some(121));
+        assert_eq!(session_minute("15:00:00"), Some(240));
+        assert_eq!(session_minute("12:00"), None);
+    }
+
+    #[test]
+    fn test_log_compares_with_previous_day() {
+        let mut log = BreadthLog::default();
+        let b = |advancers, turnover| Breadth {
+            advancers,
+            turnover,
+            ..Breadth::default()
+        };
+        log.record("2025-02-10", "15:00:03", &b(3000, 1.2e12));
+        log.record("2025-02-11", "10:00:01", &b(1000, 3e11));
+        log.record("2025-02-11", "10:00:40", &b(1100, 3.1e11));
+        log.record("2025-02-11", "12:00:00", &b(1200, 3.2e11));
+        assert_eq!(log.previous_turnover("2025-02-11"), Some(1.2e12));
+        assert_eq!(log.previous_turnover("2025-02-10"), None);
+        let samples = log.samples("2025-02-11");
+        assert_eq!(samples.len(), 1);
+        assert_eq!(
+            (samples[0].time.as_str(), samples[0].advancers),
+            ("10:00", 1100)
+        );
+        assert_eq!(log.days["2025-02-11"].turnover, 3.2e11);
+    }
+}
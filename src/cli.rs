@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+
+use stock_tui::adjust::Adjust;
+use stock_tui::config::Config;
+use stock_tui::export::{self, ExportFormat};
+use stock_tui::market::TradingCalendar;
+use stock_tui::models::{normalize_symbol, StockQuote, TimeFrame};
+use stock_tui::provider::{Provider, SinaProvider};
+use stock_tui::record::{RecordingProvider, ReplayProvider};
+use stock_tui::screener::{self, Field, Filter, Screen};
+use stock_tui::server::{self, ServerOptions};
+use stock_tui::tdx;
+
+/// 命令行用法说明
+const USAGE: &str = "\
+用法:
+  stock-tui [--record 目录 | --replay 目录] [子命令]
+                                     --record 将新浪原始响应录制到会话目录
+                                     --replay 离线回放录制的会话（可用于终端界面和子命令）
+  stock-tui                          启动终端界面
+  stock-tui quote <代码>... [--json]  查询实时行情
+  stock-tui kline <代码> [--tf daily] [--len 120] [--format csv|json] [--adjust qfq|hfq]
+                                     输出K线（含 MA5/MA10/MA20，可复权）到 stdout
+  stock-tui watchlist ls             列出自选股
+  stock-tui watchlist add <代码>...  添加自选股
+  stock-tui watchlist rm <代码>...   删除自选股
+  stock-tui serve [--port 8080] [--bind 127.0.0.1] [--interval 5] [--all-hours]
+                                     以 HTTP/JSON 服务器模式运行，开市期间轮询自选股行情
+                                     （--all-hours 忽略交易时段始终轮询）
+  stock-tui tdx <文件>... [--tail N] 读取通达信 .day/.lc5 文件并输出 CSV
+  stock-tui export kline <代码> [--tf daily] [--len 120] [--format csv|json] [--out 目录]
+                                     导出K线（含 MA5/MA10/MA20）
+  stock-tui export watchlist [--format csv|json] [--out 目录]
+                                     导出自选股行情快照
+  stock-tui screen ls                列出保存的选股方案
+  stock-tui screen run <名称|条件> [--universe hs_a] [--sort pct] [--asc] [--json]
+                                     条件选股，如 'pct > 5 and vr > 2 and price > ma20'
+  stock-tui screen save <名称> <条件> [--universe hs_a]
+                                     保存选股方案（同名替换）
+  stock-tui screen rm <名称>         删除选股方案
+  stock-tui help                     显示本帮助
+
+周期 (--tf): 5m 15m 30m 60m daily weekly monthly
+选股字段: price pct amount turnover_rate(hs) volume_ratio(vr) amplitude pe cap float_cap
+          maN rsi rsiN；运算 > >= < <= in 下限..上限，组合 and / or / 括号，数字可带 万/亿";
+
+/// 解析开头的 --record / --replay 选项，返回数据源和剩余参数
+pub fn provider(args: &[String]) -> Result<(Arc<dyn Provider>, &[String])> {
+    let sina = Config::load().sina;
+    let provider: Arc<dyn Provider> = match args.first().map(String::as_str) {
+        Some("--record") => {
+            let dir = args.get(1).context("--record 需要会话目录")?;
+            let recorder = RecordingProvider::new(sina, Path::new(dir))?;
+            eprintln!("正在录制到: {}", recorder.dir().display());
+            Arc::new(recorder)
+        }
+        Some("--replay") => {
+            let dir = args.get(1).context("--replay 需要会话目录")?;
+            Arc::new(ReplayProvider::open(Path::new(dir))?)
+        }
+        _ => return Ok((Arc::new(SinaProvider::new(sina)?), args)),
+    };
+    Ok((provider, &args[2..]))
+}
+
+/// 执行命令行子命令（不进入终端界面）
+pub fn run(args: &[String], provider: Arc<dyn Provider>) -> Result<()> {
+    match args[0].as_str() {
+        "quote" => run_quote(&args[1..], provider.as_ref()),
+        "kline" => run_kline(&args[1..], provider.as_ref()),
+        "watchlist" => run_watchlist(&args[1..]),
+        "serve" => run_serve(&args[1..], provider),
+        "tdx" => run_tdx(&args[1..]),
+        "export" => run_export(&args[1..], provider.as_ref()),
+        "screen" => run_screen(&args[1..], provider.as_ref()),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        other => anyhow::bail!("未知子命令: {}\n\n{}", other, USAGE),
+    }
+}
+
+/// 解析后的命令行参数：位置参数 + `--key value` 选项 + `--flag` 开关
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>,
+    flags: Vec<String>,
+}
+
+impl Args {
+    /// 解析参数，`valued` 为需要取值的选项名，`switches` 为开关名（均不含 `--`）
+    fn parse(args: &[String], valued: &[&str], switches: &[&str]) -> Result<Self> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut flags = Vec::new();
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            match arg.strip_prefix("--") {
+                Some(key) if valued.contains(&key) => {
+                    let value = iter.next().with_context(|| format!("--{} 需要参数", key))?;
+                    options.insert(key.to_string(), value.clone());
+                }
+                Some(key) if switches.contains(&key) => flags.push(key.to_string()),
+                Some(key) => anyhow::bail!("未知选项: --{}\n\n{}", key, USAGE),
+                None => positional.push(arg.clone()),
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+            flags,
+        })
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.flags.iter().any(|f| f == key)
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.options.get(key).map(String::as_str)
+    }
+
+    fn number(&self, key: &str) -> Result<Option<usize>> {
+        self.get(key)
+            .map(|v| {
+                v.parse()
+                    .with_context(|| format!("--{} 无效的数字: {}", key, v))
+            })
+            .transpose()
+    }
+
+    fn timeframe(&self) -> Result<TimeFrame> {
+        match self.get("tf") {
+            Some(code) => TimeFrame::from_code(code).with_context(|| format!("未知周期: {}", code)),
+            None => Ok(TimeFrame::Daily),
+        }
+    }
+
+    fn format(&self, default: ExportFormat) -> Result<ExportFormat> {
+        match self.get("format") {
+            Some(f) => ExportFormat::parse(f).with_context(|| format!("未知格式: {}", f)),
+            None => Ok(default),
+        }
+    }
+}
+
+/// quote 子命令：查询实时行情，任一股票获取失败时以非零状态退出
+fn run_quote(args: &[String], provider: &dyn Provider) -> Result<()> {
+    let args = Args::parse(args, &[], &["json"])?;
+    if args.positional.is_empty() {
+        anyhow::bail!("请指定至少一个股票代码\n\n{}", USAGE);
+    }
+    let symbols = args
+        .positional
+        .iter()
+        .map(|s| normalize_symbol(s).map_err(|e| anyhow::anyhow!("{}: {}", s, e)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut quotes: Vec<StockQuote> = Vec::new();
+    let mut failed = 0;
+    for (symbol, result) in symbols.iter().zip(provider.quotes(&symbols)) {
+        match result {
+            Ok(q) => quotes.push(q),
+            Err(e) => {
+                eprintln!("{}: {:#}", symbol, e);
+                failed += 1;
+            }
+        }
+    }
+
+    write_quotes(&quotes, args.flag("json"))?;
+    if failed > 0 {
+        anyhow::bail!("{} 只股票行情获取失败", failed);
+    }
+    Ok(())
+}
+
+/// 输出行情到 stdout：每行一只股票，或 JSON 数组
+fn write_quotes(quotes: &[StockQuote], json: bool) -> Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if json {
+        export::write_quotes(&mut out, quotes, ExportFormat::Json)?;
+        writeln!(out)?;
+        return Ok(());
+    }
+    for q in quotes {
+        let sign = if q.change() > 0.0 { "+" } else { "" };
+        writeln!(
+            out,
+            "{:<10} {:<8} {:>10.2} {:>9} {:>8} {} {}",
+            q.symbol,
+            q.name,
+            q.current,
+            format!("{}{:.2}", sign, q.change()),
+            format!("{}{:.2}%", sign, q.change_percent()),
+            q.date,
+            q.time
+        )?;
+    }
+    Ok(())
+}
+
+/// kline 子命令：输出K线到 stdout
+fn run_kline(args: &[String], provider: &dyn Provider) -> Result<()> {
+    let args = Args::parse(args, &["tf", "len", "format", "adjust"], &[])?;
+    let symbol = args
+        .positional
+        .first()
+        .context("请指定股票代码，如 sh600519")?;
+    let symbol = normalize_symbol(symbol).map_err(|e| anyhow::anyhow!("{}: {}", symbol, e))?;
+    let tf = args.timeframe()?;
+    let len = args.number("len")?.unwrap_or(120) as u32;
+    let format = args.format(ExportFormat::Csv)?;
+
+    let adjust = match args.get("adjust") {
+        Some(code) => Adjust::from_code(code).with_context(|| format!("未知复权方式: {}", code))?,
+        None => Adjust::None,
+    };
+
+    let mut data = provider.klines(&symbol, tf, len)?;
+    if data.is_empty() {
+        anyhow::bail!("{} 无K线数据", symbol);
+    }
+    if adjust != Adjust::None {
+        data = provider.adjust_factors(&symbol)?.apply(&data, adjust);
+    }
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    export::write_klines(&mut out, &data, format)?;
+    if format == ExportFormat::Json {
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// watchlist 子命令：读写与终端界面相同的 config.json
+fn run_watchlist(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[], &[])?;
+    let mut config = Config::load();
+    let symbols = args.positional.get(1..).unwrap_or_default();
+
+    match args.positional.first().map(String::as_str) {
+        Some("ls") | None => {
+            for symbol in &config.watchlist {
+                println!("{}", symbol);
+            }
+            Ok(())
+        }
+        Some("add") => {
+            if symbols.is_empty() {
+                anyhow::bail!("请指定要添加的股票代码");
+            }
+            // 先校验全部代码，有一个无效就不修改列表
+            let symbols = symbols
+                .iter()
+                .map(|input| {
+                    normalize_symbol(input).map_err(|e| anyhow::anyhow!("{}: {}", input, e))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let mut added = Vec::new();
+            for symbol in symbols {
+                if config.watchlist.contains(&symbol) || added.contains(&symbol) {
+                    eprintln!("{} 已在自选股列表中", symbol);
+                } else {
+                    added.push(symbol);
+                }
+            }
+            config.watchlist.extend(added.iter().cloned());
+            config.save()?;
+            for symbol in &added {
+                println!("已添加: {}", symbol);
+            }
+            Ok(())
+        }
+        Some("rm") => {
+            if symbols.is_empty() {
+                anyhow::bail!("请指定要删除的股票代码");
+            }
+            // 无法识别的代码按原样匹配，以便删除手工写入配置的条目
+            let symbols: Vec<String> = symbols
+                .iter()
+                .map(|input| {
+                    normalize_symbol(input).unwrap_or_else(|_| input.trim().to_lowercase())
+                })
+                .collect();
+            let (removed, missing): (Vec<_>, Vec<_>) = symbols
+                .into_iter()
+                .partition(|symbol| config.watchlist.contains(symbol));
+            let remaining: Vec<String> = config
+                .watchlist
+                .iter()
+                .filter(|s| !removed.contains(s))
+                .cloned()
+                .collect();
+            // 列表不会被删空时才修改并保存
+            if remaining.is_empty() {
+                anyhow::bail!("至少保留一只自选股");
+            }
+            if !removed.is_empty() {
+                config.watchlist = remaining;
+                config.save()?;
+                for symbol in &removed {
+                    println!("已删除: {}", symbol);
+                }
+            }
+            if !missing.is_empty() {
+                anyhow::bail!("{} 不在自选股列表中", missing.join(", "));
+            }
+            Ok(())
+        }
+        Some(other) => anyhow::bail!("未知操作: {} (支持 ls/add/rm)", other),
+    }
+}
+
+/// serve 子命令：以服务器模式运行，轮询 config.json 中的自选股
+fn run_serve(args: &[String], provider: Arc<dyn Provider>) -> Result<()> {
+    let args = Args::parse(args, &["port", "bind", "interval"], &["all-hours"])?;
+    let config = Config::load();
+    let port = args.number("port")?.unwrap_or(8080);
+    let bind = args.get("bind").unwrap_or("127.0.0.1");
+    let calendar = (!args.flag("all-hours"))
+        .then(|| TradingCalendar::load(Config::holidays_path().as_deref()));
+    let mut options = ServerOptions {
+        addr: format!("{}:{}", bind, port),
+        calendar,
+        ..ServerOptions::default()
+    };
+    if let Some(secs) = args.number("interval")? {
+        options.poll_interval = Duration::from_secs(secs.max(1) as u64);
+    }
+
+    eprintln!(
+        "stock-tui 服务器已启动: http://{} ({} 只自选股，每 {} 秒刷新)",
+        options.addr,
+        config.watchlist.len(),
+        options.poll_interval.as_secs()
+    );
+    server::serve(provider, config.watchlist, &options)
+}
+
+/// tdx 子命令：解析通达信文件并以 CSV 输出到 stdout
+fn run_tdx(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["tail"], &[])?;
+    let tail = args.number("tail")?;
+    if args.positional.is_empty() {
+        anyhow::bail!("请指定至少一个 .day 或 .lc5 文件\n\n{}", USAGE);
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    writeln!(out, "symbol,day,open,high,low,close,volume")?;
+    for file in &args.positional {
+        let path = Path::new(file);
+        let klines = tdx::read_file(path)?;
+        let symbol = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or_default();
+        let skip = tail.map_or(0, |n| klines.len().saturating_sub(n));
+        for k in &klines[skip..] {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                symbol, k.day, k.open, k.high, k.low, k.close, k.volume
+            )?;
+        }
+        eprintln!("{}: {} 条记录", path.display(), klines.len());
+    }
+    Ok(())
+}
+
+/// export 子命令：拉取数据后写入导出目录，并打印文件路径
+fn run_export(args: &[String], provider: &dyn Provider) -> Result<()> {
+    let args = Args::parse(args, &["tf", "len", "format", "out"], &[])?;
+    let config = Config::load();
+    let format = args.format(config.export_format)?;
+    let dir = args
+        .get("out")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config.export_dir());
+
+    let path = match args.positional.first().map(String::as_str) {
+        Some("kline") => {
+            let symbol = args
+                .positional
+                .get(1)
+                .context("请指定股票代码，如 sh600519")?;
+            let symbol =
+                normalize_symbol(symbol).map_err(|e| anyhow::anyhow!("{}: {}", symbol, e))?;
+            let tf = args.timeframe()?;
+            let len = args.number("len")?.unwrap_or(120) as u32;
+            let data = provider.klines(&symbol, tf, len)?;
+            export::export_klines(&dir, &symbol, tf, &data, format)?
+        }
+        Some("watchlist") => {
+            let quotes = provider
+                .quotes(&config.watchlist)
+                .into_iter()
+                .collect::<Result<Vec<_>>>()?;
+            export::export_quotes(&dir, &quotes, format)?
+        }
+        _ => anyhow::bail!("请指定导出内容: kline 或 watchlist\n\n{}", USAGE),
+    };
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// screen 子命令：执行、保存和删除选股方案（保存在 config.json）
+fn run_screen(args: &[String], provider: &dyn Provider) -> Result<()> {
+    let args = Args::parse(args, &["universe", "sort"], &["asc", "json"])?;
+    let mut config = Config::load();
+    let name = args.positional.get(1);
+
+    match args.positional.first().map(String::as_str) {
+        Some("ls") | None => {
+            for s in &config.screens {
+                println!("{:<12} [{}] {}", s.name, s.universe, s.filter);
+            }
+            return Ok(());
+        }
+        Some("run") => {
+            let input = name.context("请指定选股方案名称或条件表达式")?;
+            // 先按名称查找保存的方案，否则作为条件表达式
+            let mut screen = config
+                .screen(input)
+                .cloned()
+                .unwrap_or_else(|| Screen::new("", input));
+            if let Some(universe) = args.get("universe") {
+                screen.universe = universe.to_string();
+            }
+            let filter = Filter::parse(&screen.filter)?;
+            let sort = match args.get("sort") {
+                Some(name) => Field::parse(name).with_context(|| format!("未知字段: {}", name))?,
+                None => Field::ChangePercent,
+            };
+
+            let mut result = screener::run(provider, &screen.universe, &filter)?;
+            screener::sort(&mut result.matches, sort, !args.flag("asc"));
+            write_quotes(&result.matches, args.flag("json"))?;
+            eprintln!(
+                "扫描 {} 只，符合 {} 只{}",
+                result.scanned,
+                result.matches.len(),
+                if result.skipped > 0 {
+                    format!("，{} 只未获取K线", result.skipped)
+                } else {
+                    String::new()
+                }
+            );
+            return Ok(());
+        }
+        Some("save") => {
+            let (name, filter) = match args.positional.get(1..3) {
+                Some([name, filter]) => (name, filter),
+                _ => anyhow::bail!("用法: screen save <名称> <条件>"),
+            };
+            Filter::parse(filter)?;
+            let mut screen = Screen::new(name, filter);
+            if let Some(universe) = args.get("universe") {
+                screen.universe = universe.to_string();
+            }
+            config.set_screen(screen);
+            println!("已保存: {}", name);
+        }
+        Some("rm") => {
+            let name = name.context("请指定要删除的选股方案")?;
+            let before = config.screens.len();
+            config.screens.retain(|s| s.name != *name);
+            if config.screens.len() == before {
+                anyhow::bail!("没有名为 {} 的选股方案", name);
+            }
+            println!("已删除: {}", name);
+        }
+        Some(other) => anyhow::bail!("未知操作: {} (支持 ls/run/save/rm)", other),
+    }
+    config.save()
+}
@@ -0,0 +1,127 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::models::KLineData;
+
+/// 按日期对齐的多只股票收盘价
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Aligned {
+    /// 所有股票交易日期的并集（升序）
+    pub dates: Vec<String>,
+    /// 每只股票在各日期的收盘价，休市（该市场当天无K线）为 None
+    pub closes: Vec<Vec<Option<f64>>>,
+}
+
+impl Aligned {
+    /// 按日期对齐多只股票的K线（如港股与A股节假日不同）
+    pub fn new(series: &[Vec<KLineData>]) -> Self {
+        let dates: Vec<String> = series
+            .iter()
+            .flatten()
+            .map(|k| k.day.clone())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let closes = series
+            .iter()
+            .map(|data| {
+                let by_day: HashMap<&str, f64> = data
+                    .iter()
+                    .map(|k| (k.day.as_str(), k.close_f64()))
+                    .collect();
+                dates
+                    .iter()
+                    .map(|d| by_day.get(d.as_str()).copied())
+                    .collect()
+            })
+            .collect();
+        Self { dates, closes }
+    }
+
+    /// 共同基准日：第一个所有股票都有收盘价的日期下标，没有共同交易日时为 None
+    pub fn base_index(&self) -> Option<usize> {
+        (0..self.dates.len()).find(|&i| {
+            self.closes
+                .iter()
+                .all(|c| c.get(i).copied().flatten().is_some_and(|v| v > 0.0))
+        })
+    }
+}
+
+/// 相对基准日（`closes[base]`）收盘价的涨跌幅（百分比），休市日仍为 None
+pub fn percent_change(closes: &[Option<f64>], base: usize) -> Vec<Option<f64>> {
+    let base = closes.get(base).copied().flatten().filter(|&c| c > 0.0);
+    closes
+        .iter()
+        .map(|c| Some((c.filter(|&c| c > 0.0)? / base? - 1.0) * 100.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(day: &str, close: f64) -> KLineData {
+        KLineData {
+            day: day.to_string(),
+            open: close.to_string(),
+            high: close.to_string(),
+            low: close.to_string(),
+            close: close.to_string(),
+            volume: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_align_mismatched_calendars() {
+        // 2025-04-04 清明节 A股休市、港股交易；2025-04-07 港股休市
+        let a = vec![
+            bar("2025-04-03", 100.0),
+            bar("2025-04-07", 95.0),
+            bar("2025-04-08", 110.0),
+        ];
+        let hk = vec![
+            bar("2025-04-03", 20.0),
+            bar("2025-04-04", 21.0),
+            bar("2025-04-08", 22.0),
+        ];
+        let aligned = Aligned::new(&[a.clone(), hk]);
+        assert_eq!(
+            aligned.dates,
+            ["2025-04-03", "2025-04-04", "2025-04-07", "2025-04-08"]
+        );
+        assert_eq!(
+            aligned.closes[0],
+            [Some(100.0), None, Some(95.0), Some(110.0)]
+        );
+        assert_eq!(
+            aligned.closes[1],
+            [Some(20.0), Some(21.0), None, Some(22.0)]
+        );
+
+        // 所有股票以同一天为基准
+        assert_eq!(aligned.base_index(), Some(0));
+        let pct = percent_change(&aligned.closes[0], 0);
+        assert_eq!(pct[..2], [Some(0.0), None]);
+        assert!((pct[2].unwrap() + 5.0).abs() < 1e-9);
+        assert!((pct[3].unwrap() - 10.0).abs() < 1e-9);
+        let pct = percent_change(&aligned.closes[1], 0);
+        assert!((pct[1].unwrap() - 5.0).abs() < 1e-9);
+        assert!((pct[3].unwrap() - 10.0).abs() < 1e-9);
+
+        // 某只股票较晚上市时，以第一个共同交易日为基准，之前的日期也相对该日计算
+        let late = vec![bar("2025-04-07", 50.0), bar("2025-04-08", 55.0)];
+        let aligned = Aligned::new(&[a, late]);
+        assert_eq!(aligned.base_index(), Some(1));
+        let pct = percent_change(&aligned.closes[0], 1);
+        assert!((pct[0].unwrap() - (100.0 / 95.0 - 1.0) * 100.0).abs() < 1e-9);
+        assert_eq!(pct[1], Some(0.0));
+        assert_eq!(
+            percent_change(&aligned.closes[1], 1)[..2],
+            [None, Some(0.0)]
+        );
+
+        // 没有共同交易日
+        let aligned = Aligned::new(&[vec![bar("2025-04-03", 1.0)], vec![bar("2025-04-04", 1.0)]]);
+        assert_eq!(aligned.base_index(), None);
+    }
+}
@@ -1,12 +1,122 @@
 use anyhow::Result;
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use crate::api::SinaConfig;
+use crate::backtest::BacktestConfig;
+use crate::export::ExportFormat;
+use crate::limit::LimitAlertConfig;
+use crate::market::Market;
+use crate::models::QuoteField;
+use crate::paper::PaperConfig;
+use crate::replay::ReplayConfig;
+use crate::screener::Screen;
+
+/// 应用配置，保存在系统配置目录下的 config.json
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Config {
+    /// 自选股代码列表
     pub watchlist: Vec<String>,
+    /// 通达信 vipdoc 目录，网络无K线数据时从本地 .day/.lc5 文件读取
+    pub tdx_dir: Option<PathBuf>,
+    /// 除权文件目录，其中的 `{代码}.csv`（每行 `日期,后复权因子`）优先于网络获取的复权因子
+    pub adjust_dir: Option<PathBuf>,
+    /// 公司资料目录，其中的 `{代码}.json`（行业、板块、分红等）覆盖网络获取的资料
+    pub profile_dir: Option<PathBuf>,
+    /// 导出目录，未设置时使用数据目录下的 exports
+    pub export_dir: Option<PathBuf>,
+    /// 导出格式 (csv / json)
+    pub export_format: ExportFormat,
+    /// 新浪接口地址与超时
+    pub sina: SinaConfig,
+    /// 自动刷新间隔
+    pub refresh: RefreshConfig,
+    /// 顶部指数条显示的指数（`s_` 简版、`int_` 全球指数或普通代码），为空时不显示
+    pub indices: Vec<String>,
+    /// 自选股列表的附加列，如 `["turnover_rate", "pe", "market_cap"]`
+    pub columns: Vec<QuoteField>,
+    /// 保存的选股方案
+    pub screens: Vec<Screen>,
+    /// 策略回测的条件、资金和费用
+    pub backtest: BacktestConfig,
+    /// 模拟交易的开户资金和费用
+    pub paper: PaperConfig,
+    /// 历史回放的速度和K线数量
+    pub replay: ReplayConfig,
+    /// 自选股涨跌停提醒规则
+    pub limit_alerts: LimitAlertConfig,
+}
+
+/// 自动刷新间隔（秒），休市期间的股票不会刷新
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct RefreshConfig {
+    /// 当前股票的行情
+    pub quotes_secs: u64,
+    /// 按市场覆盖当前股票的行情刷新间隔，如 `{"us": 10}`
+    pub markets: HashMap<Market, u64>,
+    /// 当前股票的K线（开市期间刷新最新一根）
+    pub kline_secs: u64,
+    /// 自选股中其他股票的行情
+    pub inactive_secs: u64,
+    /// 顶部指数条
+    pub indices_secs: u64,
+    /// 市场宽度采样（A股开市期间在后台进行，不论是否打开视图）
+    pub breadth_secs: u64,
+}
+
+impl Default for RefreshConfig {
+    fn default() -> Self {
+        Self {
+            quotes_secs: 5,
+            markets: HashMap::new(),
+            kline_secs: 30,
+            inactive_secs: 30,
+            indices_secs: 10,
+            breadth_secs: 60,
+        }
+    }
+}
+
+impl RefreshConfig {
+    /// 当前股票所在市场的行情刷新间隔
+    pub fn quotes_interval(&self, market: Market) -> Duration {
+        Duration::from_secs(*self.markets.get(&market).unwrap_or(&self.quotes_secs))
+    }
+
+    /// 自选股中某只股票的行情刷新间隔：当前股票按所在市场的间隔，其他股票按 inactive_secs
+    pub fn symbol_interval(&self, symbol: &str, active: bool) -> Duration {
+        if active {
+            self.quotes_interval(Market::of(symbol))
+        } else {
+            self.inactive_interval()
+        }
+    }
+
+    /// 当前股票的K线刷新间隔
+    pub fn kline_interval(&self) -> Duration {
+        Duration::from_secs(self.kline_secs)
+    }
+
+    /// 其他股票的行情刷新间隔
+    pub fn inactive_interval(&self) -> Duration {
+        Duration::from_secs(self.inactive_secs)
+    }
+
+    /// 指数条的刷新间隔
+    pub fn indices_interval(&self) -> Duration {
+        Duration::from_secs(self.indices_secs)
+    }
+
+    /// 市场宽度视图的刷新间隔
+    pub fn breadth_interval(&self) -> Duration {
+        Duration::from_secs(self.breadth_secs)
+    }
 }
 
 impl Default for Config {
@@ -17,11 +127,43 @@ impl Default for Config {
                 "sz000858".to_string(), // 五粮液
                 "sh601318".to_string(), // 中国平安
             ],
+            tdx_dir: None,
+            adjust_dir: None,
+            profile_dir: None,
+            export_dir: None,
+            export_format: ExportFormat::default(),
+            sina: SinaConfig::default(),
+            refresh: RefreshConfig::default(),
+            indices: [
+                "s_sh000001", // 上证指数
+                "s_sz399001", // 深证成指
+                "s_sz399006", // 创业板指
+                "hkHSI",      // 恒生指数
+                "int_dji",    // 道琼斯
+                "int_nasdaq", // 纳斯达克
+                "int_sp500",  // 标普500
+            ]
+            .map(String::from)
+            .to_vec(),
+            columns: Vec::new(),
+            screens: vec![
+                Screen::new("放量大涨", "pct > 5 and vr > 2"),
+                Screen::new(
+                    "站上20日线",
+                    "pct > 2 and price > ma20 and cap in 100亿..1000亿",
+                ),
+                Screen::new("超跌", "pct < -3 and rsi < 30"),
+            ],
+            backtest: BacktestConfig::default(),
+            paper: PaperConfig::default(),
+            replay: ReplayConfig::default(),
+            limit_alerts: LimitAlertConfig::default(),
         }
     }
 }
 
 impl Config {
+    /// 读取配置，文件不存在或格式错误时返回默认配置
     pub fn load() -> Self {
         if let Some(path) = Self::get_config_path() {
             if path.exists() {
@@ -35,6 +177,7 @@ impl Config {
         Self::default()
     }
 
+    /// 保存配置到 config.json
     pub fn save(&self) -> Result<()> {
         if let Some(path) = Self::get_config_path() {
             if let Some(parent) = path.parent() {
@@ -46,8 +189,79 @@ impl Config {
         Ok(())
     }
 
+    /// 按名称查找选股方案
+    pub fn screen(&self, name: &str) -> Option<&Screen> {
+        self.screens.iter().find(|s| s.name == name)
+    }
+
+    /// 添加选股方案，同名的方案被替换
+    pub fn set_screen(&mut self, screen: Screen) {
+        match self.screens.iter_mut().find(|s| s.name == screen.name) {
+            Some(existing) => *existing = screen,
+            None => self.screens.push(screen),
+        }
+    }
+
+    /// 导出文件所在目录
+    pub fn export_dir(&self) -> PathBuf {
+        self.export_dir.clone().unwrap_or_else(|| {
+            Self::project_dirs()
+                .map(|dirs| dirs.data_dir().join("exports"))
+                .unwrap_or_else(|| PathBuf::from("exports"))
+        })
+    }
+
+    /// 用户节假日文件 holidays.json（与 config.json 同目录），格式见 data/holidays.json
+    pub fn holidays_path() -> Option<PathBuf> {
+        Self::project_dirs().map(|proj_dirs| proj_dirs.config_dir().join("holidays.json"))
+    }
+
+    /// 市场宽度历史 breadth.json（数据目录），保存每日成交额和分时涨跌家数
+    pub fn breadth_path() -> Option<PathBuf> {
+        Self::project_dirs().map(|proj_dirs| proj_dirs.data_dir().join("breadth.json"))
+    }
+
+    /// 模拟交易账户 paper.json（与 config.json 同目录），删除后以 paper.initial_cash 重新开户
+    pub fn paper_path() -> Option<PathBuf> {
+        Self::project_dirs().map(|proj_dirs| proj_dirs.config_dir().join("paper.json"))
+    }
+
     fn get_config_path() -> Option<PathBuf> {
+        Self::project_dirs().map(|proj_dirs| proj_dirs.config_dir().join("config.json"))
+    }
+
+    fn project_dirs() -> Option<ProjectDirs> {
         ProjectDirs::from("com", "stock-tui", "stock-tui")
-            .map(|proj_dirs| proj_dirs.config_dir().join("config.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_refresh_intervals() {
+        let refresh: RefreshConfig = serde_json::from_str(
+            r#"{"quotes_secs": 3, "markets": {"us": 10}, "inactive_secs": 60}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            refresh.symbol_interval("sh600519", true),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            refresh.symbol_interval("gb_aapl", true),
+            Duration::from_secs(10)
+        );
+        assert_eq!(
+            refresh.symbol_interval("gb_aapl", false),
+            Duration::from_secs(60)
+        );
+        assert_eq!(
+            refresh.symbol_interval("hk00700", false),
+            Duration::from_secs(60)
+        );
+        // 未设置的项目使用默认值
+        assert_eq!(refresh.kline_interval(), Duration::from_secs(30));
     }
 }
@@ -5,6 +5,8 @@ use std::time::Duration;
 use anyhow::Result;
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent};
 
+use crate::app::JobResult;
+
 /// 应用事件
 pub enum AppEvent {
     /// 键盘事件
@@ -12,7 +14,9 @@ pub enum AppEvent {
     /// 定时 Tick（用于自动刷新数据）
     Tick,
     /// 终端大小改变
-    Resize(u16, u16),
+    Resize,
+    /// 后台任务完成
+    Job(JobResult),
 }
 
 /// 事件处理器
@@ -34,7 +38,7 @@ impl EventHandler {
                     if let Ok(evt) = event::read() {
                         let app_event = match evt {
                             CrosstermEvent::Key(key) => AppEvent::Key(key),
-                            CrosstermEvent::Resize(w, h) => AppEvent::Resize(w, h),
+                            CrosstermEvent::Resize(_, _) => AppEvent::Resize,
                             _ => continue,
                         };
                         if event_tx.send(app_event).is_err() {
@@ -53,6 +57,11 @@ impl EventHandler {
         Self { rx, _tx: tx }
     }
 
+    /// 事件发送端，后台任务通过它送回结果
+    pub fn sender(&self) -> mpsc::Sender<AppEvent> {
+        self._tx.clone()
+    }
+
     /// 接收下一个事件
     pub fn next(&self) -> Result<AppEvent> {
         let event = self.rx.recv()?;
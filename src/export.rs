@@ -0,0 +1,324 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::indicators::calculate_ma;
+use crate::models::{KLineData, QuoteExt, StockQuote, TimeFrame};
+
+/// 导出时附带计算的均线周期（与K线图一致）
+pub const MA_WINDOWS: [usize; 3] = [5, 10, 20];
+
+/// 导出文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// 逗号分隔文本
+    #[default]
+    Csv,
+    /// JSON 数组
+    Json,
+}
+
+impl ExportFormat {
+    /// 从命令行参数解析（csv / json）
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Some(ExportFormat::Csv),
+            "json" => Some(ExportFormat::Json),
+            _ => None,
+        }
+    }
+
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// 导出的K线行（含均线列）
+#[derive(Debug, Serialize)]
+struct KlineRow<'a> {
+    day: &'a str,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    ma5: Option<f64>,
+    ma10: Option<f64>,
+    ma20: Option<f64>,
+}
+
+/// 导出的行情行（服务器模式的 JSON 也使用该结构）
+#[derive(Debug, Serialize)]
+pub(crate) struct QuoteRow<'a> {
+    symbol: &'a str,
+    name: &'a str,
+    current: f64,
+    change: f64,
+    change_percent: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    pre_close: f64,
+    volume: f64,
+    turnover: f64,
+    date: &'a str,
+    time: &'a str,
+    amplitude: f64,
+    #[serde(flatten)]
+    ext: QuoteExt,
+}
+
+impl<'a> From<&'a StockQuote> for QuoteRow<'a> {
+    fn from(q: &'a StockQuote) -> Self {
+        QuoteRow {
+            symbol: &q.symbol,
+            name: &q.name,
+            current: q.current,
+            change: q.change(),
+            change_percent: q.change_percent(),
+            open: q.open,
+            high: q.high,
+            low: q.low,
+            pre_close: q.pre_close,
+            volume: q.volume,
+            turnover: q.turnover,
+            date: &q.date,
+            time: &q.time,
+            amplitude: q.amplitude(),
+            ext: q.ext,
+        }
+    }
+}
+
+/// 写出K线数据（附带 MA5/MA10/MA20 列）
+pub fn write_klines<W: Write>(w: &mut W, data: &[KLineData], format: ExportFormat) -> Result<()> {
+    let mas: Vec<Vec<Option<f64>>> = MA_WINDOWS.iter().map(|&n| calculate_ma(data, n)).collect();
+    let rows: Vec<KlineRow> = data
+        .iter()
+        .enumerate()
+        .map(|(i, k)| KlineRow {
+            day: &k.day,
+            open: k.open_f64(),
+            high: k.high_f64(),
+            low: k.low_f64(),
+            close: k.close_f64(),
+            volume: k.volume_f64(),
+            ma5: mas[0][i],
+            ma10: mas[1][i],
+            ma20: mas[2][i],
+        })
+        .collect();
+
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut *w, &rows)?,
+        ExportFormat::Csv => {
+            writeln!(w, "day,open,high,low,close,volume,ma5,ma10,ma20")?;
+            for r in &rows {
+                writeln!(
+                    w,
+                    "{},{},{},{},{},{},{},{},{}",
+                    csv_field(r.day),
+                    r.open,
+                    r.high,
+                    r.low,
+                    r.close,
+                    r.volume,
+                    opt_field(r.ma5),
+                    opt_field(r.ma10),
+                    opt_field(r.ma20)
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 写出自选股行情快照
+pub fn write_quotes<W: Write>(
+    w: &mut W,
+    quotes: &[StockQuote],
+    format: ExportFormat,
+) -> Result<()> {
+    let rows: Vec<QuoteRow> = quotes.iter().map(QuoteRow::from).collect();
+
+    match format {
+        ExportFormat::Json => serde_json::to_writer_pretty(&mut *w, &rows)?,
+        ExportFormat::Csv => {
+            writeln!(
+                w,
+                "symbol,name,current,change,change_percent,open,high,low,pre_close,volume,turnover,date,time,\
+                 amplitude,turnover_rate,volume_ratio,week52_high,week52_low,pe_ttm,market_cap,float_market_cap"
+            )?;
+            for r in &rows {
+                writeln!(
+                    w,
+                    "{},{},{},{:.3},{:.2},{},{},{},{},{},{},{},{},{:.2},{},{},{},{},{},{},{}",
+                    csv_field(r.symbol),
+                    csv_field(r.name),
+                    r.current,
+                    r.change,
+                    r.change_percent,
+                    r.open,
+                    r.high,
+                    r.low,
+                    r.pre_close,
+                    r.volume,
+                    r.turnover,
+                    csv_field(r.date),
+                    csv_field(r.time),
+                    r.amplitude,
+                    opt_field(r.ext.turnover_rate),
+                    opt_field(r.ext.volume_ratio),
+                    opt_field(r.ext.week52_high),
+                    opt_field(r.ext.week52_low),
+                    opt_field(r.ext.pe_ttm),
+                    opt_field(r.ext.market_cap),
+                    opt_field(r.ext.float_market_cap)
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 导出K线到目录，文件名: {代码}_{周期}_{时间戳}.{扩展名}
+pub fn export_klines(
+    dir: &Path,
+    symbol: &str,
+    tf: TimeFrame,
+    data: &[KLineData],
+    format: ExportFormat,
+) -> Result<PathBuf> {
+    let name = format!("{}_{}_{}", symbol, tf.code(), timestamp());
+    write_file(dir, &name, format, |w| write_klines(w, data, format))
+}
+
+/// 导出自选股行情到目录，文件名: watchlist_{时间戳}.{扩展名}
+pub fn export_quotes(dir: &Path, quotes: &[StockQuote], format: ExportFormat) -> Result<PathBuf> {
+    let name = format!("watchlist_{}", timestamp());
+    write_file(dir, &name, format, |w| write_quotes(w, quotes, format))
+}
+
+fn write_file<F>(dir: &Path, name: &str, format: ExportFormat, write: F) -> Result<PathBuf>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<()>,
+{
+    fs::create_dir_all(dir).with_context(|| format!("创建导出目录失败: {}", dir.display()))?;
+    let path = dir.join(format!("{}.{}", name, format.extension()));
+    let file = File::create(&path).with_context(|| format!("创建文件失败: {}", path.display()))?;
+    let mut w = BufWriter::new(file);
+    write(&mut w)?;
+    w.flush()?;
+    Ok(path)
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%Y%m%d_%H%M%S").to_string()
+}
+
+/// CSV 字段转义（含逗号、引号或换行时加引号）
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn opt_field(v: Option<f64>) -> String {
+    v.map(|v| format!("{:.3}", v)).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(day: &str, close: f64) -> KLineData {
+        KLineData {
+            day: day.to_string(),
+            open: close.to_string(),
+            high: close.to_string(),
+            low: close.to_string(),
+            close: close.to_string(),
+            volume: "100".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_write_klines_csv() {
+        let data: Vec<KLineData> = (1..=5)
+            .map(|i| kline(&format!("2024-01-0{}", i), i as f64 * 10.0))
+            .collect();
+        let mut out = Vec::new();
+        write_klines(&mut out, &data, ExportFormat::Csv).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[0], "day,open,high,low,close,volume,ma5,ma10,ma20");
+        assert_eq!(lines[1], "2024-01-01,10,10,10,10,100,,,");
+        assert_eq!(lines[5], "2024-01-05,50,50,50,50,100,30.000,,");
+    }
+
+    #[test]
+    fn test_write_klines_json() {
+        let data = vec![kline("2024-01-01", 10.0)];
+        let mut out = Vec::new();
+        write_klines(&mut out, &data, ExportFormat::Json).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(v[0]["close"], 10.0);
+        assert!(v[0]["ma5"].is_null());
+    }
+
+    #[test]
+    fn test_write_quotes_csv_matches_json_fields() {
+        let q = StockQuote {
+            symbol: "sh600519".to_string(),
+            name: "贵州茅台".to_string(),
+            current: 10.5,
+            pre_close: 10.0,
+            high: 10.6,
+            low: 9.9,
+            ext: QuoteExt {
+                turnover_rate: Some(1.25),
+                pe_ttm: Some(20.0),
+                ..QuoteExt::default()
+            },
+            ..StockQuote::default()
+        };
+        let mut out = Vec::new();
+        write_quotes(&mut out, std::slice::from_ref(&q), ExportFormat::Csv).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        let header: Vec<&str> = lines[0].split(',').collect();
+        let row: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(header.len(), row.len());
+        let cell = |name: &str| row[header.iter().position(|h| *h == name).unwrap()];
+        assert_eq!(cell("amplitude"), "7.00");
+        assert_eq!(cell("turnover_rate"), "1.250");
+        assert_eq!(cell("pe_ttm"), "20.000");
+        assert_eq!(cell("volume_ratio"), "");
+
+        let mut out = Vec::new();
+        write_quotes(&mut out, &[q], ExportFormat::Json).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        let keys: Vec<&String> = v[0].as_object().unwrap().keys().collect();
+        for key in keys {
+            assert!(header.contains(&key.as_str()), "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_csv_field() {
+        assert_eq!(csv_field("贵州茅台"), "贵州茅台");
+        assert_eq!(csv_field("A,B"), "\"A,B\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{NaiveDate, NaiveTime, Timelike};
+
+use crate::adjust;
+use crate::models::{KLineData, StockQuote, TimeFrame};
+use crate::profile::CompanyProfile;
+use crate::provider::Provider;
+
+/// 一轮行情轮询的结果
+pub struct QuotePoll {
+    /// 与 symbols 一一对应的行情，获取失败为 None
+    pub quotes: Vec<Option<StockQuote>>,
+    /// 获取失败的股票及错误信息
+    pub errors: Vec<(String, String)>,
+}
+
+impl QuotePoll {
+    /// 将部分股票 (polled) 的结果合并到与 symbols 对齐的行情列表，获取失败的保留原有行情
+    pub fn merge_into(
+        &self,
+        polled: &[String],
+        symbols: &[String],
+        quotes: &mut Vec<Option<StockQuote>>,
+    ) {
+        quotes.resize(symbols.len(), None);
+        for (symbol, quote) in polled.iter().zip(&self.quotes) {
+            if let (Some(q), Some(i)) = (quote, symbols.iter().position(|s| s == symbol)) {
+                quotes[i] = Some(q.clone());
+            }
+        }
+    }
+}
+
+/// 轮询一组股票的实时行情（终端界面和服务器模式共用）
+pub fn poll_quotes(provider: &dyn Provider, symbols: &[String]) -> QuotePoll {
+    let mut errors = Vec::new();
+    let quotes = symbols
+        .iter()
+        .zip(provider.quotes(symbols))
+        .map(|(symbol, r)| match r {
+            Ok(q) => Some(q),
+            Err(e) => {
+                errors.push((symbol.clone(), e.to_string()));
+                None
+            }
+        })
+        .collect();
+    QuotePoll { quotes, errors }
+}
+
+struct CacheEntry {
+    data: Vec<KLineData>,
+    len: u32,
+    fetched_at: Instant,
+}
+
+/// K线缓存，按 (代码, 周期) 保存最近一次获取的数据
+pub struct KlineCache {
+    entries: HashMap<(String, TimeFrame), CacheEntry>,
+    ttl: Duration,
+}
+
+impl KlineCache {
+    /// 创建缓存，超过 ttl 的数据会重新获取
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// 获取K线，缓存未过期且根数足够时直接返回缓存
+    pub fn get(
+        &mut self,
+        provider: &dyn Provider,
+        symbol: &str,
+        tf: TimeFrame,
+        len: u32,
+    ) -> Result<Vec<KLineData>> {
+        if let Some(data) = self.cached(symbol, tf, len) {
+            return Ok(data);
+        }
+        let data = provider.klines(symbol, tf, len)?;
+        self.insert(symbol, tf, len, data.clone());
+        Ok(data)
+    }
+
+    /// 查询缓存：未过期且根数足够时返回最新的 len 根，不发起网络请求
+    pub fn cached(&self, symbol: &str, tf: TimeFrame, len: u32) -> Option<Vec<KLineData>> {
+        let entry = self.entries.get(&(symbol.to_string(), tf))?;
+        if entry.len < len || entry.fetched_at.elapsed() >= self.ttl {
+            return None;
+        }
+        let skip = entry.data.len().saturating_sub(len as usize);
+        Some(entry.data[skip..].to_vec())
+    }
+
+    /// 保存获取到的K线（len 为请求的根数）
+    pub fn insert(&mut self, symbol: &str, tf: TimeFrame, len: u32, data: Vec<KLineData>) {
+        self.entries.insert(
+            (symbol.to_string(), tf),
+            CacheEntry {
+                data,
+                len,
+                fetched_at: Instant::now(),
+            },
+        );
+    }
+
+    /// 使某只股票的全部缓存失效（手动刷新时使用）
+    pub fn invalidate(&mut self, symbol: &str) {
+        self.entries.retain(|(s, _), _| s != symbol);
+    }
+}
+
+/// 公司资料缓存，每只股票每天最多获取一次
+#[derive(Default)]
+pub struct ProfileCache {
+    entries: HashMap<String, (NaiveDate, CompanyProfile)>,
+}
+
+impl ProfileCache {
+    /// 获取公司资料，today 当天已获取过的直接返回缓存
+    pub fn get(
+        &mut self,
+        provider: &dyn Provider,
+        symbol: &str,
+        today: NaiveDate,
+    ) -> Result<CompanyProfile> {
+        if let Some(profile) = self.cached(symbol, today) {
+            return Ok(profile);
+        }
+        let profile = provider.profile(symbol)?;
+        self.insert(symbol, today, profile.clone());
+        Ok(profile)
+    }
+
+    /// today 当天已获取过的公司资料（不请求网络）
+    pub fn cached(&self, symbol: &str, today: NaiveDate) -> Option<CompanyProfile> {
+        self.entries
+            .get(symbol)
+            .filter(|(day, _)| *day == today)
+            .map(|(_, profile)| profile.clone())
+    }
+
+    /// 保存 today 获取到的公司资料
+    pub fn insert(&mut self, symbol: &str, today: NaiveDate, profile: CompanyProfile) {
+        self.entries.insert(symbol.to_string(), (today, profile));
+    }
+}
+
+/// 一只A股当天的补充数据
+pub struct DailyExtra {
+    date: String,
+    profile: Option<CompanyProfile>,
+    /// 之前 5 个交易日的平均成交量（股）
+    avg_volume: Option<f64>,
+    /// 之前约一年的 (最高, 最低)
+    week52: Option<(f64, f64)>,
+}
+
+impl DailyExtra {
+    /// 获取某只股票在行情日期 date 的补充数据（日K线和公司资料各请求一次，失败的项为 None）
+    pub fn fetch(provider: &dyn Provider, symbol: &str, date: &str) -> Self {
+        let klines = provider
+            .klines(symbol, TimeFrame::Daily, 250)
+            .unwrap_or_default();
+        let before: Vec<KLineData> = klines
+            .into_iter()
+            .filter(|k| k.day.as_str() < date)
+            .collect();
+        DailyExtra {
+            date: date.to_string(),
+            profile: provider.profile(symbol).ok(),
+            avg_volume: average_volume(&before, 5),
+            week52: high_low(&before),
+        }
+    }
+}
+
+/// 为A股行情补充换手率、量比、市盈率、市值和52周高低（新浪A股行情接口不提供这些字段）
+///
+/// 股本和每股收益来自公司资料，平均成交量和52周高低来自日K线，每只股票每个交易日各获取一次，
+/// 获取失败的当天不再重试。
+#[derive(Default)]
+pub struct QuoteEnricher {
+    entries: HashMap<String, DailyExtra>,
+}
+
+impl QuoteEnricher {
+    /// 补充一轮轮询结果中的A股行情，缺少当天补充数据时先获取，其他市场不变
+    pub fn enrich(&mut self, provider: &dyn Provider, quotes: &mut [Option<StockQuote>]) {
+        for (symbol, date) in self.missing(quotes) {
+            let extra = DailyExtra::fetch(provider, &symbol, &date);
+            self.insert(symbol, extra);
+        }
+        self.apply(quotes);
+    }
+
+    /// 还没有当天补充数据的A股: (代码, 行情日期)
+    pub fn missing(&self, quotes: &[Option<StockQuote>]) -> Vec<(String, String)> {
+        let mut missing: Vec<(String, String)> = Vec::new();
+        for quote in quotes.iter().flatten() {
+            if adjust::supports(&quote.symbol)
+                && self.fresh(quote).is_none()
+                && !missing.iter().any(|(s, _)| *s == quote.symbol)
+            {
+                missing.push((quote.symbol.clone(), quote.date.clone()));
+            }
+        }
+        missing
+    }
+
+    /// 保存获取到的补充数据
+    pub fn insert(&mut self, symbol: String, extra: DailyExtra) {
+        self.entries.insert(symbol, extra);
+    }
+
+    /// 只用已有的当天补充数据补充行情（不请求网络），没有的保持不变
+    pub fn apply(&self, quotes: &mut [Option<StockQuote>]) {
+        for quote in quotes.iter_mut().flatten() {
+            let Some(extra) = self.fresh(quote) else {
+                continue;
+            };
+            if let Some(profile) = &extra.profile {
+                profile.fill_quote(quote);
+            }
+            if let Some((high, low)) = extra.week52 {
+                let ext = &mut quote.ext;
+                ext.week52_high.get_or_insert(high.max(quote.high));
+                ext.week52_low.get_or_insert(if quote.low > 0.0 {
+                    low.min(quote.low)
+                } else {
+                    low
+                });
+            }
+            if quote.ext.volume_ratio.is_none() {
+                quote.ext.volume_ratio = extra
+                    .avg_volume
+                    .and_then(|avg| volume_ratio(quote.volume, avg, &quote.time));
+            }
+        }
+    }
+
+    /// 与行情同一天的补充数据
+    fn fresh(&self, quote: &StockQuote) -> Option<&DailyExtra> {
+        self.entries
+            .get(&quote.symbol)
+            .filter(|e| e.date == quote.date)
+    }
+}
+
+/// 最近 n 根K线的平均成交量
+pub fn average_volume(klines: &[KLineData], n: usize) -> Option<f64> {
+    let recent = &klines[klines.len().saturating_sub(n)..];
+    let avg = recent.iter().map(KLineData::volume_f64).sum::<f64>() / recent.len() as f64;
+    (!recent.is_empty() && avg > 0.0).then_some(avg)
+}
+
+fn high_low(klines: &[KLineData]) -> Option<(f64, f64)> {
+    let highs = klines.iter().filter_map(|k| k.high.parse::<f64>().ok());
+    let lows = klines.iter().filter_map(|k| k.low.parse::<f64>().ok());
+    let high = highs.fold(None, |m: Option<f64>, v| Some(m.map_or(v, |m| m.max(v))))?;
+    let low = lows.fold(None, |m: Option<f64>, v| Some(m.map_or(v, |m| m.min(v))))?;
+    Some((high, low))
+}
+
+/// 盘中量比：当前成交量 / (5 日平均每分钟成交量 × 已交易分钟数)，A股每天交易 240 分钟
+pub fn volume_ratio(volume: f64, avg_daily: f64, time: &str) -> Option<f64> {
+    let minutes = cn_trading_minutes(time)?;
+    (avg_daily > 0.0 && minutes > 0.0).then(|| volume / (avg_daily * minutes / 240.0))
+}
+
+/// A股当天截至 time (HH:MM:SS) 已交易的分钟数 (9:30-11:30, 13:00-15:00)
+fn cn_trading_minutes(time: &str) -> Option<f64> {
+    let t = NaiveTime::parse_from_str(time, "%H:%M:%S").ok()?;
+    let m = (t.hour() * 60 + t.minute()) as f64 + t.second() as f64 / 60.0;
+    Some((m - 570.0).clamp(0.0, 120.0) + (m - 780.0).clamp(0.0, 120.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingProvider {
+        kline_calls: AtomicUsize,
+        profile_calls: AtomicUsize,
+    }
+
+    impl Provider for CountingProvider {
+        fn quote(&self, symbol: &str) -> Result<StockQuote> {
+            anyhow::bail!("no quote for {}", symbol)
+        }
+
+        fn klines(&self, _symbol: &str, _tf: TimeFrame, len: u32) -> Result<Vec<KLineData>> {
+            self.kline_calls.fetch_add(1, Ordering::SeqCst);
+            Ok((0..len)
+                .map(|i| KLineData {
+                    day: format!("2024-01-{:02}", i + 1),
+                    open: "1".to_string(),
+                    high: "1".to_string(),
+                    low: "1".to_string(),
+                    close: "1".to_string(),
+                    volume: "1".to_string(),
+                })
+                .collect())
+        }
+
+        fn profile(&self, _symbol: &str) -> Result<CompanyProfile> {
+            self.profile_calls.fetch_add(1, Ordering::SeqCst);
+            Ok(CompanyProfile::default())
+        }
+    }
+
+    #[test]
+    fn test_kline_cache_reuses_entries() {
+        let provider = CountingProvider::default();
+        let mut cache = KlineCache::new(Duration::from_secs(60));
+        assert_eq!(
+            cache
+                .get(&provider, "sh600519", TimeFrame::Daily, 20)
+                .unwrap()
+                .len(),
+            20
+        );
+        // 更少的根数命中缓存并截取最新的部分
+        let short = cache
+            .get(&provider, "sh600519", TimeFrame::Daily, 5)
+            .unwrap();
+        assert_eq!(short.len(), 5);
+        assert_eq!(short[4].day, "2024-01-20");
+        assert_eq!(provider.kline_calls.load(Ordering::SeqCst), 1);
+        // 其他周期 / 失效后重新获取
+        cache
+            .get(&provider, "sh600519", TimeFrame::Weekly, 5)
+            .unwrap();
+        cache.invalidate("sh600519");
+        cache
+            .get(&provider, "sh600519", TimeFrame::Daily, 5)
+            .unwrap();
+        assert_eq!(provider.kline_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_profile_cached_per_day() {
+        let provider = CountingProvider::default();
+        let mut cache = ProfileCache::default();
+        let day = |d| NaiveDate::from_ymd_opt(2025, 2, d).unwrap();
+        cache.get(&provider, "sh600519", day(11)).unwrap();
+        cache.get(&provider, "sh600519", day(11)).unwrap();
+        assert_eq!(provider.profile_calls.load(Ordering::SeqCst), 1);
+        cache.get(&provider, "sh600519", day(12)).unwrap();
+        cache.get(&provider, "sz000858", day(12)).unwrap();
+        assert_eq!(provider.profile_calls.load(Ordering::SeqCst), 3);
+        assert!(cache.cached("sz000858", day(12)).is_some());
+        assert!(cache.cached("sz000858", day(13)).is_none());
+    }
+
+    #[test]
+    fn test_volume_ratio_by_elapsed_minutes() {
+        assert_eq!(cn_trading_minutes("09:00:00"), Some(0.0));
+        assert_eq!(cn_trading_minutes("10:30:00"), Some(60.0));
+        assert_eq!(cn_trading_minutes("12:00:00"), Some(120.0));
+        assert_eq!(cn_trading_minutes("15:00:03"), Some(240.0));
+        // 开盘一小时成交了全天均量的一半 -> 量比 2
+        assert_eq!(volume_ratio(50.0, 100.0, "10:30:00"), Some(2.0));
+        assert_eq!(volume_ratio(100.0, 100.0, "15:00:00"), Some(1.0));
+        assert_eq!(volume_ratio(100.0, 100.0, "09:15:00"), None);
+        assert_eq!(volume_ratio(100.0, 0.0, "15:00:00"), None);
+    }
+
+    #[test]
+    fn test_enrich_cn_quotes_once_per_day() {
+        let provider = CountingProvider::default();
+        let mut enricher = QuoteEnricher::default();
+        let quote = |symbol: &str, date: &str| StockQuote {
+            symbol: symbol.to_string(),
+            current: 1.0,
+            volume: 2.0,
+            date: date.to_string(),
+            time: "15:00:00".to_string(),
+            ..Default::default()
+        };
+        let mut quotes = vec![
+            Some(quote("sh600519", "2024-01-10")),
+            Some(quote("gb_aapl", "2024-01-10")),
+        ];
+        enricher.enrich(&provider, &mut quotes);
+        enricher.enrich(&provider, &mut quotes);
+        assert_eq!(provider.kline_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(provider.profile_calls.load(Ordering::SeqCst), 1);
+        // 之前的日K成交量均为 1
+        let cn = quotes[0].as_ref().unwrap();
+        assert_eq!(cn.ext.volume_ratio, Some(2.0));
+        assert_eq!(cn.ext.week52_high, Some(1.0));
+        assert_eq!(quotes[1].as_ref().unwrap().ext, Default::default());
+
+        let mut next = vec![Some(quote("sh600519", "2024-01-11"))];
+        enricher.enrich(&provider, &mut next);
+        assert_eq!(provider.kline_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_apply_uses_cached_extras_only() {
+        let provider = CountingProvider::default();
+        let mut enricher = QuoteEnricher::default();
+        let quote = StockQuote {
+            symbol: "sh600519".to_string(),
+            current: 1.0,
+            volume: 2.0,
+            date: "2024-01-10".to_string(),
+            time: "15:00:00".to_string(),
+            ..Default::default()
+        };
+        let mut quotes = vec![Some(quote.clone()), Some(quote)];
+        enricher.apply(&mut quotes);
+        assert_eq!(quotes[0].as_ref().unwrap().ext.volume_ratio, None);
+        assert_eq!(
+            enricher.missing(&quotes),
+            vec![("sh600519".to_string(), "2024-01-10".to_string())]
+        );
+        assert_eq!(provider.kline_calls.load(Ordering::SeqCst), 0);
+
+        let extra = DailyExtra::fetch(&provider, "sh600519", "2024-01-10");
+        enricher.insert("sh600519".to_string(), extra);
+        assert!(enricher.missing(&quotes).is_empty());
+        enricher.apply(&mut quotes);
+        assert_eq!(quotes[1].as_ref().unwrap().ext.volume_ratio, Some(2.0));
+    }
+
+    #[test]
+    fn test_poll_quotes_collects_errors() {
+        let provider = CountingProvider::default();
+        let poll = poll_quotes(&provider, &["sh600519".to_string()]);
+        assert!(poll.quotes[0].is_none());
+        assert_eq!(poll.errors[0].0, "sh600519");
+    }
+
+    #[test]
+    fn test_merge_partial_poll() {
+        let symbols: Vec<String> = ["sh600519", "gb_aapl"].map(String::from).to_vec();
+        let quote = |symbol: &str, current| StockQuote {
+            symbol: symbol.to_string(),
+            current,
+            ..Default::default()
+        };
+        let mut quotes = vec![Some(quote("sh600519", 1.0))];
+        let poll = QuotePoll {
+            quotes: vec![Some(quote("gb_aapl", 2.0))],
+            errors: Vec::new(),
+        };
+        poll.merge_into(&symbols[1..], &symbols, &mut quotes);
+        assert_eq!(quotes[0].as_ref().unwrap().current, 1.0);
+        assert_eq!(quotes[1].as_ref().unwrap().current, 2.0);
+    }
+}
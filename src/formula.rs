@@ -0,0 +1,525 @@
+use anyhow::{Context, Result};
+
+use crate::models::KLineData;
+
+/// 通达信公式（单个表达式），如 `CROSS(MA(C,5),MA(C,20))`
+///
+/// 支持的行情变量: `C`/`CLOSE`、`O`/`OPEN`、`H`/`HIGH`、`L`/`LOW`、`V`/`VOL`；
+/// 函数: `MA`、`EMA`、`REF`、`HHV`、`LLV`、`SUM`、`COUNT`、`CROSS`、`ABS`、`MAX`、`MIN`、`IF`；
+/// 运算符: `+ - * /`、`> >= < <= = <>`、`AND`/`&&`、`OR`/`||`、`NOT`。
+/// 逻辑值按 1 / 0 计算，指标未就绪的K线取值为 None。
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(f64),
+    Var(Var),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Var {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Ma,
+    Ema,
+    Ref,
+    Hhv,
+    Llv,
+    Sum,
+    Count,
+    Cross,
+    Abs,
+    Max,
+    Min,
+    If,
+}
+
+impl Func {
+    fn parse(name: &str) -> Option<Self> {
+        let func = match name {
+            "MA" => Func::Ma,
+            "EMA" => Func::Ema,
+            "REF" => Func::Ref,
+            "HHV" => Func::Hhv,
+            "LLV" => Func::Llv,
+            "SUM" => Func::Sum,
+            "COUNT" => Func::Count,
+            "CROSS" => Func::Cross,
+            "ABS" => Func::Abs,
+            "MAX" => Func::Max,
+            "MIN" => Func::Min,
+            "IF" => Func::If,
+            _ => return None,
+        };
+        Some(func)
+    }
+
+    /// 参数个数，以及第二个参数是否为周期（须为正整数常量）
+    fn arity(&self) -> (usize, bool) {
+        match self {
+            Func::Ma | Func::Ema | Func::Ref | Func::Hhv | Func::Llv | Func::Sum | Func::Count => {
+                (2, true)
+            }
+            Func::Cross | Func::Max | Func::Min => (2, false),
+            Func::Abs => (1, false),
+            Func::If => (3, false),
+        }
+    }
+}
+
+impl Formula {
+    /// 解析公式，末尾的分号可省略，函数和变量名不区分大小写
+    pub fn parse(text: &str) -> Result<Self> {
+        let text = text.trim().trim_end_matches(';');
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        let expr = parser.or_expr()?;
+        if parser.pos < parser.tokens.len() {
+            anyhow::bail!("公式有多余的内容: {}", text);
+        }
+        Ok(Self { expr })
+    }
+
+    /// 逐根K线计算公式的值
+    pub fn eval(&self, data: &[KLineData]) -> Vec<Option<f64>> {
+        eval(&self.expr, data)
+    }
+
+    /// 逐根K线计算公式是否成立（非 0 为成立），未就绪为 None
+    pub fn states(&self, data: &[KLineData]) -> Vec<Option<bool>> {
+        self.eval(data)
+            .into_iter()
+            .map(|v| v.map(|v| v != 0.0))
+            .collect()
+    }
+}
+
+fn bool_value(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn eval(expr: &Expr, data: &[KLineData]) -> Vec<Option<f64>> {
+    match expr {
+        Expr::Number(n) => vec![Some(*n); data.len()],
+        Expr::Var(var) => data
+            .iter()
+            .map(|k| {
+                Some(match var {
+                    Var::Open => k.open_f64(),
+                    Var::High => k.high_f64(),
+                    Var::Low => k.low_f64(),
+                    Var::Close => k.close_f64(),
+                    Var::Volume => k.volume_f64(),
+                })
+            })
+            .collect(),
+        Expr::Neg(e) => eval(e, data).into_iter().map(|v| v.map(|v| -v)).collect(),
+        Expr::Not(e) => eval(e, data)
+            .into_iter()
+            .map(|v| v.map(|v| bool_value(v == 0.0)))
+            .collect(),
+        Expr::Binary(op, a, b) => {
+            let (a, b) = (eval(a, data), eval(b, data));
+            a.into_iter()
+                .zip(b)
+                .map(|(a, b)| {
+                    let (a, b) = (a?, b?);
+                    Some(match op {
+                        BinOp::Add => a + b,
+                        BinOp::Sub => a - b,
+                        BinOp::Mul => a * b,
+                        BinOp::Div if b == 0.0 => return None,
+                        BinOp::Div => a / b,
+                        BinOp::Gt => bool_value(a > b),
+                        BinOp::Ge => bool_value(a >= b),
+                        BinOp::Lt => bool_value(a < b),
+                        BinOp::Le => bool_value(a <= b),
+                        BinOp::Eq => bool_value(a == b),
+                        BinOp::Ne => bool_value(a != b),
+                        BinOp::And => bool_value(a != 0.0 && b != 0.0),
+                        BinOp::Or => bool_value(a != 0.0 || b != 0.0),
+                    })
+                })
+                .collect()
+        }
+        Expr::Call(func, args) => call(*func, args, data),
+    }
+}
+
+fn call(func: Func, args: &[Expr], data: &[KLineData]) -> Vec<Option<f64>> {
+    let x = eval(&args[0], data);
+    // 周期参数在解析时已检查为正整数常量
+    let n = match args.get(1) {
+        Some(Expr::Number(n)) => *n as usize,
+        _ => 1,
+    };
+    let indices = 0..data.len();
+    match func {
+        Func::Ma | Func::Sum | Func::Count | Func::Hhv | Func::Llv => indices
+            .map(|i| {
+                let window: Vec<f64> = x[(i + 1).checked_sub(n)?..=i]
+                    .iter()
+                    .copied()
+                    .collect::<Option<_>>()?;
+                match func {
+                    Func::Ma => Some(window.iter().sum::<f64>() / n as f64),
+                    Func::Sum => Some(window.iter().sum()),
+                    Func::Count => Some(window.iter().filter(|v| **v != 0.0).count() as f64),
+                    Func::Hhv => window.into_iter().reduce(f64::max),
+                    _ => window.into_iter().reduce(f64::min),
+                }
+            })
+            .collect(),
+        Func::Ref => indices.map(|i| x[i.checked_sub(n)?]).collect(),
+        // 从第一个有效值开始递推: Y = (2X + (N-1)Y') / (N+1)
+        Func::Ema => {
+            let mut prev: Option<f64> = None;
+            x.iter()
+                .map(|v| {
+                    prev = match (*v, prev) {
+                        (Some(v), Some(p)) => {
+                            Some((2.0 * v + (n as f64 - 1.0) * p) / (n as f64 + 1.0))
+                        }
+                        (v, p) => v.or(p),
+                    };
+                    v.and(prev)
+                })
+                .collect()
+        }
+        Func::Abs => x.iter().map(|v| v.map(f64::abs)).collect(),
+        Func::Cross | Func::Max | Func::Min => {
+            let y = eval(&args[1], data);
+            indices
+                .map(|i| {
+                    let (a, b) = (x[i]?, y[i]?);
+                    match func {
+                        Func::Cross => {
+                            let p = i.checked_sub(1)?;
+                            Some(bool_value(a > b && x[p]? <= y[p]?))
+                        }
+                        Func::Max => Some(a.max(b)),
+                        _ => Some(a.min(b)),
+                    }
+                })
+                .collect()
+        }
+        Func::If => {
+            let (y, z) = (eval(&args[1], data), eval(&args[2], data));
+            indices
+                .map(|i| if x[i]? != 0.0 { y[i] } else { z[i] })
+                .collect()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Comma,
+    Op(BinOp),
+    Minus,
+    Not,
+    Number(f64),
+    Ident(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let number: String = chars[start..i].iter().collect();
+            tokens.push(Token::Number(
+                number
+                    .parse()
+                    .with_context(|| format!("无效的数字: {}", number))?,
+            ));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word = chars[start..i]
+                .iter()
+                .collect::<String>()
+                .to_ascii_uppercase();
+            tokens.push(match word.as_str() {
+                "AND" => Token::Op(BinOp::And),
+                "OR" => Token::Op(BinOp::Or),
+                "NOT" => Token::Not,
+                _ => Token::Ident(word),
+            });
+        } else {
+            let (token, len) = match (c, next) {
+                ('(', _) => (Token::LParen, 1),
+                (')', _) => (Token::RParen, 1),
+                (',', _) => (Token::Comma, 1),
+                ('+', _) => (Token::Op(BinOp::Add), 1),
+                ('-', _) => (Token::Minus, 1),
+                ('*', _) => (Token::Op(BinOp::Mul), 1),
+                ('/', _) => (Token::Op(BinOp::Div), 1),
+                ('&', Some('&')) => (Token::Op(BinOp::And), 2),
+                ('|', Some('|')) => (Token::Op(BinOp::Or), 2),
+                ('>', Some('=')) => (Token::Op(BinOp::Ge), 2),
+                ('<', Some('=')) => (Token::Op(BinOp::Le), 2),
+                ('<', Some('>')) | ('!', Some('=')) => (Token::Op(BinOp::Ne), 2),
+                ('=', Some('=')) => (Token::Op(BinOp::Eq), 2),
+                ('>', _) => (Token::Op(BinOp::Gt), 1),
+                ('<', _) => (Token::Op(BinOp::Lt), 1),
+                ('=', _) => (Token::Op(BinOp::Eq), 1),
+                _ => anyhow::bail!("公式无法识别的字符: {}", c),
+            };
+            tokens.push(token);
+            i += len;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self.peek() == Some(token);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    /// 按优先级解析左结合的二元运算
+    fn binary(&mut self, ops: &[BinOp], operand: fn(&mut Self) -> Result<Expr>) -> Result<Expr> {
+        let mut left = operand(self)?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if !ops.contains(&op) {
+                break;
+            }
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(operand(self)?));
+        }
+        Ok(left)
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        self.binary(&[BinOp::Or], Self::and_expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        self.binary(&[BinOp::And], Self::compare)
+    }
+
+    fn compare(&mut self) -> Result<Expr> {
+        use BinOp::*;
+        self.binary(&[Gt, Ge, Lt, Le, Eq, Ne], Self::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        let mut left = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op(BinOp::Add)) => BinOp::Add,
+                Some(Token::Minus) => BinOp::Sub,
+                _ => return Ok(left),
+            };
+            self.pos += 1;
+            left = Expr::Binary(op, Box::new(left), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr> {
+        self.binary(&[BinOp::Mul, BinOp::Div], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.eat(&Token::Minus) {
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        if self.eat(&Token::Not) {
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Expr::Number(n)),
+            Some(Token::LParen) => {
+                let expr = self.or_expr()?;
+                if !self.eat(&Token::RParen) {
+                    anyhow::bail!("公式缺少 )");
+                }
+                Ok(expr)
+            }
+            Some(Token::Ident(name)) => {
+                if self.eat(&Token::LParen) {
+                    return self.call(&name);
+                }
+                let var = match name.as_str() {
+                    "C" | "CLOSE" => Var::Close,
+                    "O" | "OPEN" => Var::Open,
+                    "H" | "HIGH" => Var::High,
+                    "L" | "LOW" => Var::Low,
+                    "V" | "VOL" => Var::Volume,
+                    _ => anyhow::bail!("未知的公式变量: {}", name),
+                };
+                Ok(Expr::Var(var))
+            }
+            _ => anyhow::bail!("公式不完整: 缺少变量、数字或函数"),
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr> {
+        let func = Func::parse(name).with_context(|| format!("不支持的公式函数: {}", name))?;
+        let mut args = vec![self.or_expr()?];
+        while self.eat(&Token::Comma) {
+            args.push(self.or_expr()?);
+        }
+        if !self.eat(&Token::RParen) {
+            anyhow::bail!("公式函数 {} 缺少 )", name);
+        }
+        let (arity, period) = func.arity();
+        if args.len() != arity {
+            anyhow::bail!("公式函数 {} 需要 {} 个参数", name, arity);
+        }
+        if period && !matches!(args[1], Expr::Number(n) if n >= 1.0 && n.fract() == 0.0) {
+            anyhow::bail!("公式函数 {} 的周期应为正整数", name);
+        }
+        Ok(Expr::Call(func, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn klines(closes: &[f64]) -> Vec<KLineData> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, c)| KLineData {
+                day: format!("2025-01-{:02}", i + 1),
+                open: c.to_string(),
+                high: (c + 1.0).to_string(),
+                low: (c - 1.0).to_string(),
+                close: c.to_string(),
+                volume: "100".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(Formula::parse("CROSS(MA(C,5),MA(C,20));").is_ok());
+        assert!(Formula::parse("c > ma(c, 3) and not v > 1000").is_ok());
+        assert!(Formula::parse("MA(C,0) > 1").is_err());
+        assert!(Formula::parse("MA(C) > 1").is_err());
+        assert!(Formula::parse("FOO(C,3)").is_err());
+        assert!(Formula::parse("X > 1").is_err());
+        assert!(Formula::parse("C > (1").is_err());
+        assert!(Formula::parse("C > 1 1").is_err());
+    }
+
+    #[test]
+    fn test_series_functions() {
+        let data = klines(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+        let f = |text: &str| Formula::parse(text).unwrap().eval(&data);
+        assert_eq!(
+            f("MA(C,3)"),
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+        assert_eq!(f("REF(C,2)")[4], Some(3.0));
+        assert_eq!(f("HHV(H,2)")[1], Some(3.0));
+        assert_eq!(f("LLV(L,3)")[4], Some(2.0));
+        assert_eq!(f("SUM(C,2)")[4], Some(9.0));
+        assert_eq!(f("COUNT(C>2,5)")[4], Some(3.0));
+        assert_eq!(f("EMA(C,3)")[..3], [Some(1.0), Some(1.5), Some(2.25)]);
+        assert_eq!(f("-C + 10 * 2 / 4")[0], Some(4.0));
+        assert_eq!(f("MAX(C,3)")[0], Some(3.0));
+        assert_eq!(
+            f("IF(C>2,C,0)")[..4],
+            [Some(0.0), Some(0.0), Some(3.0), Some(4.0)]
+        );
+        assert_eq!(f("ABS(C-3)")[0], Some(2.0));
+        assert_eq!(f("C/0")[0], None);
+    }
+
+    #[test]
+    fn test_cross() {
+        let data = klines(&[5.0, 4.0, 3.0, 4.0, 6.0, 7.0, 5.0, 3.0]);
+        let f = Formula::parse("CROSS(C, MA(C,3)) AND C > 3 || C = 3").unwrap();
+        let states = f.states(&data);
+        // MA3 未就绪时为 None；第 3、8 根 C = 3，第 4 根收盘 4 上穿 MA3 (3.67)
+        assert_eq!(
+            states,
+            vec![
+                None,
+                None,
+                Some(true),
+                Some(true),
+                Some(false),
+                Some(false),
+                Some(false),
+                Some(true)
+            ]
+        );
+    }
+}
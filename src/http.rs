@@ -0,0 +1,229 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+
+use crate::api::SinaConfig;
+
+/// 新浪接口类别（分别统计延迟和错误）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endpoint {
+    /// 实时行情 hq.sinajs.cn
+    Realtime,
+    /// A股K线
+    KlineCn,
+    /// 美股日K线
+    KlineUs,
+    /// A股复权因子
+    Factors,
+    /// A股公司资料
+    Profile,
+    /// 板块与行情中心列表
+    Market,
+}
+
+impl Endpoint {
+    /// 全部接口类别
+    pub fn all() -> [Endpoint; 6] {
+        [
+            Endpoint::Realtime,
+            Endpoint::KlineCn,
+            Endpoint::KlineUs,
+            Endpoint::Factors,
+            Endpoint::Profile,
+            Endpoint::Market,
+        ]
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Endpoint::Realtime => "实时行情",
+            Endpoint::KlineCn => "A股K线",
+            Endpoint::KlineUs => "美股K线",
+            Endpoint::Factors => "复权因子",
+            Endpoint::Profile => "公司资料",
+            Endpoint::Market => "板块列表",
+        }
+    }
+}
+
+/// 单个接口的请求统计
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EndpointStats {
+    /// 接口类别
+    pub endpoint: Endpoint,
+    /// 请求次数（重试不重复计数）
+    pub requests: u64,
+    /// 最终失败的请求次数
+    pub errors: u64,
+    /// 重试次数
+    pub retries: u64,
+    /// 成功请求的累计耗时（毫秒）
+    pub total_ms: u64,
+    /// 最近一次请求耗时（毫秒，含重试）
+    pub last_ms: u64,
+    /// 最近一次错误
+    pub last_error: Option<String>,
+}
+
+impl EndpointStats {
+    fn new(endpoint: Endpoint) -> Self {
+        Self {
+            endpoint,
+            requests: 0,
+            errors: 0,
+            retries: 0,
+            total_ms: 0,
+            last_ms: 0,
+            last_error: None,
+        }
+    }
+
+    /// 成功请求的平均耗时（毫秒）
+    pub fn avg_ms(&self) -> Option<u64> {
+        let ok = self.requests - self.errors;
+        (ok > 0).then(|| self.total_ms / ok)
+    }
+}
+
+/// 共享的 HTTP 客户端：连接池、超时、带抖动的指数退避重试和按接口的统计
+pub struct HttpClient {
+    client: reqwest::blocking::Client,
+    retries: u32,
+    backoff: Duration,
+    stats: Mutex<Vec<EndpointStats>>,
+}
+
+impl HttpClient {
+    /// 按接口设置创建客户端；未配置 proxy 时使用环境变量 HTTP_PROXY / HTTPS_PROXY
+    pub fn new(config: &SinaConfig) -> Result<Self> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .connect_timeout(Duration::from_millis(config.connect_timeout_ms))
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .pool_idle_timeout(Duration::from_secs(90))
+            .pool_max_idle_per_host(4);
+        if let Some(proxy) = config.proxy.as_deref().filter(|p| !p.is_empty()) {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("代理地址无效: {}", proxy))?,
+            );
+        }
+        Ok(Self {
+            client: builder.build().context("创建 HTTP 客户端失败")?,
+            retries: config.retries,
+            backoff: Duration::from_millis(config.backoff_ms),
+            stats: Mutex::new(
+                Endpoint::all()
+                    .into_iter()
+                    .map(EndpointStats::new)
+                    .collect(),
+            ),
+        })
+    }
+
+    /// GET 请求，连接失败、超时和 429/5xx 时重试，返回响应正文
+    pub fn get(&self, endpoint: Endpoint, url: &str, referer: &str) -> Result<Vec<u8>> {
+        let start = Instant::now();
+        let mut attempt = 0;
+        let result = loop {
+            match self.try_get(url, referer) {
+                Err(e) if e.transient && attempt < self.retries => {
+                    thread::sleep(self.backoff_delay(attempt));
+                    attempt += 1;
+                }
+                other => break other.map_err(|e| e.error),
+            }
+        };
+
+        let elapsed = start.elapsed().as_millis() as u64;
+        let mut stats = self.stats.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(s) = stats.iter_mut().find(|s| s.endpoint == endpoint) {
+            s.requests += 1;
+            s.retries += attempt as u64;
+            s.last_ms = elapsed;
+            match &result {
+                Ok(_) => s.total_ms += elapsed,
+                Err(e) => {
+                    s.errors += 1;
+                    s.last_error = Some(format!("{:#}", e));
+                }
+            }
+        }
+        result
+    }
+
+    /// 各接口的请求统计
+    pub fn stats(&self) -> Vec<EndpointStats> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    fn try_get(&self, url: &str, referer: &str) -> Result<Vec<u8>, AttemptError> {
+        let resp = self
+            .client
+            .get(url)
+            .header("Referer", referer)
+            .send()
+            .map_err(|e| AttemptError {
+                transient: e.is_connect() || e.is_timeout(),
+                error: anyhow::Error::new(e),
+            })?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            return Err(AttemptError {
+                transient: status.is_server_error() || status.as_u16() == 429,
+                error: anyhow::anyhow!("HTTP {}", status),
+            });
+        }
+        resp.bytes().map(|b| b.to_vec()).map_err(|e| AttemptError {
+            transient: e.is_timeout(),
+            error: anyhow::Error::new(e),
+        })
+    }
+
+    /// 第 attempt 次重试前的等待：backoff * 2^attempt，乘以 0.5~1.5 的随机抖动
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let base = self.backoff.saturating_mul(1 << attempt.min(16));
+        let jitter = 0.5 + (RandomState::new().build_hasher().finish() % 1000) as f64 / 1000.0;
+        base.mul_f64(jitter)
+    }
+}
+
+/// 单次请求的错误，transient 表示可以重试
+struct AttemptError {
+    transient: bool,
+    error: anyhow::Error,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_delay_grows_with_jitter() {
+        let client = HttpClient::new(&SinaConfig {
+            backoff_ms: 100,
+            ..SinaConfig::default()
+        })
+        .unwrap();
+        for attempt in 0..4 {
+            let base = 100 * (1 << attempt);
+            let ms = client.backoff_delay(attempt).as_millis() as u64;
+            assert!(ms >= base / 2 && ms <= base * 3 / 2, "{attempt}: {ms}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_proxy() {
+        let config = SinaConfig {
+            proxy: Some("not a url".to_string()),
+            ..SinaConfig::default()
+        };
+        assert!(HttpClient::new(&config).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::models::KLineData;
+
+/// 计算移动平均线 (MA)
+/// data: K线数据
+/// window: 窗口大小 (如 5, 10, 20)，为 0 时全部为 None
+pub fn calculate_ma(data: &[KLineData], window: usize) -> Vec<Option<f64>> {
+    if window == 0 {
+        return vec![None; data.len()];
+    }
+    let mut ma = Vec::with_capacity(data.len());
+    let mut sum = 0.0;
+    for i in 0..data.len() {
+        sum += data[i].close_f64();
+        if i >= window {
+            sum -= data[i - window].close_f64();
+        }
+        if i >= window - 1 {
+            ma.push(Some(sum / window as f64));
+        } else {
+            ma.push(None);
+        }
+    }
+    ma
+}
+
+/// 计算相对强弱指标 (RSI)，采用 Wilder 平滑
+/// period: 周期 (如 6, 14)，前 period 根为 None
+pub fn calculate_rsi(data: &[KLineData], period: usize) -> Vec<Option<f64>> {
+    let mut rsi = vec![None; data.len()];
+    if period == 0 || data.len() <= period {
+        return rsi;
+    }
+    let (mut gain, mut loss) = (0.0, 0.0);
+    for i in 1..data.len() {
+        let diff = data[i].close_f64() - data[i - 1].close_f64();
+        let (up, down) = (diff.max(0.0), (-diff).max(0.0));
+        if i <= period {
+            gain += up / period as f64;
+            loss += down / period as f64;
+        } else {
+            gain = (gain * (period - 1) as f64 + up) / period as f64;
+            loss = (loss * (period - 1) as f64 + down) / period as f64;
+        }
+        if i >= period {
+            rsi[i] = Some(if loss == 0.0 {
+                100.0
+            } else {
+                100.0 - 100.0 / (1.0 + gain / loss)
+            });
+        }
+    }
+    rsi
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calculate_ma() {
+        // Create dummy data
+        let prices = [10.0, 20.0, 30.0, 40.0, 50.0];
+        let data: Vec<KLineData> = prices
+            .iter()
+            .map(|&p| KLineData {
+                day: "2023-01-01".to_string(),
+                open: "0.0".to_string(),
+                high: "0.0".to_string(),
+                low: "0.0".to_string(),
+                close: p.to_string(),
+                volume: "0".to_string(),
+            })
+            .collect();
+
+        // MA 3
+        // 10, 20, 30 -> 20
+        // 20, 30, 40 -> 30
+        // 30, 40, 50 -> 40
+        let ma3 = calculate_ma(&data, 3);
+        assert_eq!(ma3.len(), 5);
+        assert_eq!(ma3[0], None);
+        assert_eq!(ma3[1], None);
+        assert_eq!(ma3[2], Some(20.0));
+        assert_eq!(ma3[3], Some(30.0));
+        assert_eq!(ma3[4], Some(40.0));
+        assert!(calculate_ma(&data, 0).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_calculate_rsi() {
+        let data: Vec<KLineData> = [10.0, 11.0, 10.5, 11.5, 11.0]
+            .iter()
+            .map(|&p| KLineData {
+                day: "2023-01-01".to_string(),
+                open: "0.0".to_string(),
+                high: "0.0".to_string(),
+                low: "0.0".to_string(),
+                close: p.to_string(),
+                volume: "0".to_string(),
+            })
+            .collect();
+
+        // 前两日: 涨 1.0 跌 0.5 -> 平均涨 0.5 平均跌 0.25 -> RS 2 -> 66.67
+        let rsi2 = calculate_rsi(&data, 2);
+        assert_eq!(&rsi2[..2], &[None, None]);
+        assert!((rsi2[2].unwrap() - 66.667).abs() < 1e-3);
+        // 涨 1.0: 平均涨 0.75 平均跌 0.125 -> RS 6 -> 85.71
+        assert!((rsi2[3].unwrap() - 85.714).abs() < 1e-3);
+        assert!(rsi2[4].unwrap() < rsi2[3].unwrap());
+        assert!(calculate_rsi(&data[..2], 2).iter().all(Option::is_none));
+    }
+}
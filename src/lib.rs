@@ -0,0 +1,79 @@
+//! 新浪财经行情数据库
+//!
+//! 提供实时行情 / K线数据的获取与解析、数据模型、技术指标、通达信本地文件读取和数据导出。
+//! `stock-tui` 终端界面是基于本库的一个二进制程序（由默认开启的 `tui` feature 控制）；
+//! 仅使用数据接口时可以关闭默认 feature，不引入 ratatui / crossterm：
+//!
+//! ```toml
+//! stock-tui = { version = "0.1", default-features = false }
+//! ```
+//!
+//! # 示例
+//!
+//! ```no_run
+//! use stock_tui::{api, calculate_ma, TimeFrame};
+//!
+//! let quote = api::fetch_realtime_quote("sh600519")?;
+//! println!("{} {:.2} {:+.2}%", quote.name, quote.current, quote.change_percent());
+//!
+//! let tf = TimeFrame::Daily;
+//! let klines = api::fetch_kline_data("sh600519", tf.scale(), 120)?;
+//! let ma20 = calculate_ma(&klines, 20);
+//! println!("MA20: {:?}", ma20.last());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+#![warn(missing_docs)]
+
+/// 前复权 / 后复权
+pub mod adjust;
+/// 新浪财经 HTTP 接口：实时行情与K线数据
+pub mod api;
+/// 策略回测：A股交易规则、费用与绩效统计
+pub mod backtest;
+/// 行业 / 概念板块与行情中心列表
+pub mod board;
+/// 市场宽度：涨跌家数、涨跌幅分布与涨跌停统计
+pub mod breadth;
+/// 多股票走势对比：按日期对齐与涨跌幅归一化
+pub mod compare;
+/// 配置文件读写（自选股、导出设置等）
+pub mod config;
+/// K线与行情快照导出 (CSV / JSON)
+pub mod export;
+/// 行情轮询与K线缓存（终端界面和服务器模式共用）
+pub mod feed;
+/// 通达信公式：常用函数的解析与逐K线计算（回测信号使用）
+pub mod formula;
+/// 共享 HTTP 客户端：超时、重试、代理和请求统计
+pub mod http;
+/// 技术指标计算
+pub mod indicators;
+/// A股涨跌停价格与涨停 / 跌停 / 炸板判断
+pub mod limit;
+/// 交易时段、节假日日历与行情刷新调度
+pub mod market;
+/// 数据模型：行情、K线、周期
+pub mod models;
+/// 模拟交易：委托撮合、A股 T+1 规则、持仓与每日盈亏
+pub mod paper;
+/// 公司基本面与简介
+pub mod profile;
+/// 行情数据源抽象
+pub mod provider;
+/// 原始响应录制与离线回放
+pub mod record;
+/// 历史回放：逐根显示K线
+pub mod replay;
+/// 条件选股：条件表达式解析与全市场筛选
+pub mod screener;
+/// 本地 HTTP/JSON 服务器模式
+pub mod server;
+/// 通达信 .day / .lc5 本地数据文件读取
+pub mod tdx;
+/// K线图可见窗口：可见数量、缩放时的滚动偏移与游标位置
+pub mod viewport;
+
+pub use indicators::calculate_ma;
+pub use models::{KLineData, StockQuote, TimeFrame};
+pub use provider::{Provider, SinaProvider};
@@ -0,0 +1,359 @@
+use serde::{Deserialize, Serialize};
+
+use crate::adjust::AdjustFactors;
+use crate::models::{KLineData, StockQuote};
+
+/// 价格比较容差（价格最小变动单位为 0.01）
+const EPSILON: f64 = 0.005;
+
+/// A股板块（决定涨跌幅限制）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Board {
+    /// 沪深主板 ±10%
+    Main,
+    /// 创业板 (300/301) ±20%
+    ChiNext,
+    /// 科创板 (688/689) ±20%
+    Star,
+    /// 北交所 ±30%
+    Bse,
+    /// 主板 ST / *ST ±5%
+    St,
+}
+
+impl Board {
+    /// 根据代码和名称判断板块，非 A股股票返回 None
+    ///
+    /// 指数 (sh000/sz399)、债券 (sh01-sh2x/sz10-sz13)、基金 / ETF (sh5x/sz15-sz18)
+    /// 没有 ±10% 的涨跌幅限制，也返回 None。
+    pub fn of(symbol: &str, name: &str) -> Option<Board> {
+        let board = if symbol.starts_with("bj") {
+            Board::Bse
+        } else if symbol.starts_with("sz300") || symbol.starts_with("sz301") {
+            Board::ChiNext
+        } else if symbol.starts_with("sh688") || symbol.starts_with("sh689") {
+            Board::Star
+        } else if ["sh60", "sh90", "sz00", "sz20"]
+            .iter()
+            .any(|prefix| symbol.starts_with(prefix))
+        {
+            // 创业板、科创板的 ST 股仍为 ±20%，只有主板 ST 为 ±5%
+            if name.contains("ST") {
+                Board::St
+            } else {
+                Board::Main
+            }
+        } else {
+            return None;
+        };
+        Some(board)
+    }
+
+    /// 涨跌幅限制比例
+    pub fn ratio(&self) -> f64 {
+        match self {
+            Board::Main => 0.10,
+            Board::ChiNext | Board::Star => 0.20,
+            Board::Bse => 0.30,
+            Board::St => 0.05,
+        }
+    }
+}
+
+/// 涨停价与跌停价
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LimitPrices {
+    /// 涨停价
+    pub up: f64,
+    /// 跌停价
+    pub down: f64,
+}
+
+impl LimitPrices {
+    /// 由昨收价和涨跌幅比例计算（四舍五入到分）
+    pub fn new(pre_close: f64, ratio: f64) -> Self {
+        let round = |v: f64| (v * 100.0 + 1e-6).round() / 100.0;
+        Self {
+            up: round(pre_close * (1.0 + ratio)),
+            down: round(pre_close * (1.0 - ratio)),
+        }
+    }
+
+    /// 某价格是否为涨停价
+    pub fn is_up(&self, price: f64) -> bool {
+        price >= self.up - EPSILON
+    }
+
+    /// 某价格是否为跌停价
+    pub fn is_down(&self, price: f64) -> bool {
+        price <= self.down + EPSILON
+    }
+}
+
+/// 涨跌停状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitState {
+    /// 涨停
+    LimitUp,
+    /// 跌停
+    LimitDown,
+    /// 炸板：盘中触及涨停后打开
+    OpenedUp,
+    /// 翘板：盘中触及跌停后打开
+    OpenedDown,
+}
+
+impl LimitState {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            LimitState::LimitUp => "涨停",
+            LimitState::LimitDown => "跌停",
+            LimitState::OpenedUp => "炸板",
+            LimitState::OpenedDown => "翘板",
+        }
+    }
+
+    /// 是否与涨停相关
+    pub fn is_up(&self) -> bool {
+        matches!(self, LimitState::LimitUp | LimitState::OpenedUp)
+    }
+}
+
+impl StockQuote {
+    /// 涨跌停价（仅 A股，且有昨收价时）
+    pub fn limit_prices(&self) -> Option<LimitPrices> {
+        let board = Board::of(&self.symbol, &self.name)?;
+        (self.pre_close > 0.0).then(|| LimitPrices::new(self.pre_close, board.ratio()))
+    }
+
+    /// 当前涨跌停状态，未触及涨跌停时为 None
+    pub fn limit_state(&self) -> Option<LimitState> {
+        let limits = self.limit_prices()?;
+        if self.current <= 0.0 {
+            return None;
+        }
+        if limits.is_up(self.current) {
+            Some(LimitState::LimitUp)
+        } else if limits.is_down(self.current) {
+            Some(LimitState::LimitDown)
+        } else if limits.is_up(self.high) {
+            Some(LimitState::OpenedUp)
+        } else if self.low > 0.0 && limits.is_down(self.low) {
+            Some(LimitState::OpenedDown)
+        } else {
+            None
+        }
+    }
+}
+
+/// 行情更新后是否需要提醒：涨跌停状态发生变化（封板、炸板等）时返回新状态
+///
+/// 首次获取的行情没有上一状态，不触发提醒。
+pub fn touched(prev: &StockQuote, cur: &StockQuote) -> Option<LimitState> {
+    let state = cur.limit_state()?;
+    (prev.limit_state() != Some(state)).then_some(state)
+}
+
+/// 涨跌停提醒规则
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LimitAlertConfig {
+    /// 是否提醒
+    pub enabled: bool,
+    /// 只提醒这些股票，为空时提醒全部自选股
+    pub symbols: Vec<String>,
+    /// 提醒涨停、炸板
+    pub up: bool,
+    /// 提醒跌停、翘板
+    pub down: bool,
+}
+
+impl Default for LimitAlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            symbols: Vec::new(),
+            up: true,
+            down: true,
+        }
+    }
+}
+
+impl LimitAlertConfig {
+    /// 按规则判断行情更新后是否提醒，返回新的涨跌停状态
+    pub fn check(&self, prev: &StockQuote, cur: &StockQuote) -> Option<LimitState> {
+        if !self.enabled || !(self.symbols.is_empty() || self.symbols.contains(&cur.symbol)) {
+            return None;
+        }
+        touched(prev, cur).filter(|state| if state.is_up() { self.up } else { self.down })
+    }
+}
+
+/// 日K线中收于涨跌停的K线（与前一根的收盘价比较）：涨停为 Some(true)，跌停为 Some(false)
+///
+/// data 为不复权数据时传入复权因子：除权日的涨跌停价按除权参考价（前收盘 × 前一日因子 / 当日因子）
+/// 计算，避免把除权缺口标为跌停。复权后的数据价格连续，传 None 即可。
+pub fn closed_at_limit(
+    symbol: &str,
+    name: &str,
+    data: &[KLineData],
+    ex_rights: Option<&AdjustFactors>,
+) -> Vec<Option<bool>> {
+    let Some(board) = Board::of(symbol, name) else {
+        return vec![None; data.len()];
+    };
+    let mut marks = vec![None; data.len()];
+    for (i, pair) in data.windows(2).enumerate() {
+        let mut pre_close = pair[0].close_f64();
+        if let Some(factors) = ex_rights {
+            pre_close *= factors.factor_at(&pair[0].day) / factors.factor_at(&pair[1].day);
+        }
+        let close = pair[1].close_f64();
+        if pre_close <= 0.0 || close <= 0.0 {
+            continue;
+        }
+        let limits = LimitPrices::new(pre_close, board.ratio());
+        if limits.is_up(close) {
+            marks[i + 1] = Some(true);
+        } else if limits.is_down(close) {
+            marks[i + 1] = Some(false);
+        }
+    }
+    marks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, name: &str, pre_close: f64, current: f64, high: f64) -> StockQuote {
+        StockQuote {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            pre_close,
+            current,
+            high,
+            low: current.min(pre_close),
+            ..StockQuote::default()
+        }
+    }
+
+    #[test]
+    fn test_board_limits() {
+        let cases = [
+            ("sh600519", "贵州茅台", 11.0, 9.0),
+            ("sz300750", "宁德时代", 12.0, 8.0),
+            ("sh688981", "中芯国际", 12.0, 8.0),
+            ("bj430047", "诺思兰德", 13.0, 7.0),
+            ("sz000004", "*ST国华", 10.5, 9.5),
+        ];
+        for (symbol, name, up, down) in cases {
+            let q = quote(symbol, name, 10.0, 10.0, 10.0);
+            assert_eq!(q.limit_prices(), Some(LimitPrices { up, down }), "{symbol}");
+        }
+        for (symbol, name) in [
+            ("hk00700", "腾讯控股"),
+            ("sh000001", "上证指数"),
+            ("sz399001", "深证成指"),
+            ("sh113050", "南银转债"),
+            ("sz123107", "温氏转债"),
+            ("sh510300", "沪深300ETF"),
+            ("sz159915", "创业板ETF"),
+        ] {
+            let q = quote(symbol, name, 10.0, 10.0, 10.0);
+            assert_eq!(q.limit_prices(), None, "{symbol}");
+        }
+        // 四舍五入到分: 10.05 * 1.1 = 11.055
+        assert_eq!(LimitPrices::new(10.05, 0.10).up, 11.06);
+    }
+
+    #[test]
+    fn test_limit_state_and_alerts() {
+        let sealed = quote("sh600000", "浦发银行", 10.0, 11.0, 11.0);
+        let opened = quote("sh600000", "浦发银行", 10.0, 10.8, 11.0);
+        let down = quote("sh600000", "浦发银行", 10.0, 9.0, 10.0);
+        assert_eq!(sealed.limit_state(), Some(LimitState::LimitUp));
+        assert_eq!(opened.limit_state(), Some(LimitState::OpenedUp));
+        assert_eq!(down.limit_state(), Some(LimitState::LimitDown));
+
+        let normal = quote("sh600000", "浦发银行", 10.0, 10.5, 10.6);
+        assert_eq!(normal.limit_state(), None);
+        assert_eq!(touched(&normal, &sealed), Some(LimitState::LimitUp));
+        assert_eq!(touched(&sealed, &sealed), None);
+        assert_eq!(touched(&sealed, &opened), Some(LimitState::OpenedUp));
+        assert_eq!(touched(&opened, &normal), None);
+    }
+
+    #[test]
+    fn test_limit_alert_rules() {
+        let normal = quote("sh600000", "浦发银行", 10.0, 10.5, 10.6);
+        let sealed = quote("sh600000", "浦发银行", 10.0, 11.0, 11.0);
+        let down = quote("sh600000", "浦发银行", 10.0, 9.0, 10.0);
+        let rules = LimitAlertConfig::default();
+        assert_eq!(rules.check(&normal, &sealed), Some(LimitState::LimitUp));
+
+        let only_down = LimitAlertConfig {
+            up: false,
+            ..LimitAlertConfig::default()
+        };
+        assert_eq!(only_down.check(&normal, &sealed), None);
+        assert_eq!(only_down.check(&normal, &down), Some(LimitState::LimitDown));
+
+        let other = LimitAlertConfig {
+            symbols: vec!["sz000001".to_string()],
+            ..LimitAlertConfig::default()
+        };
+        assert_eq!(other.check(&normal, &sealed), None);
+        let off = LimitAlertConfig {
+            enabled: false,
+            ..LimitAlertConfig::default()
+        };
+        assert_eq!(off.check(&normal, &sealed), None);
+    }
+
+    #[test]
+    fn test_closed_at_limit() {
+        let k = |close: &str| KLineData {
+            day: "2025-02-11".to_string(),
+            open: close.to_string(),
+            high: close.to_string(),
+            low: close.to_string(),
+            close: close.to_string(),
+            volume: "0".to_string(),
+        };
+        let data = vec![k("10.00"), k("11.00"), k("10.50"), k("9.45")];
+        assert_eq!(
+            closed_at_limit("sh600000", "浦发银行", &data, None),
+            vec![None, Some(true), None, Some(false)]
+        );
+        assert_eq!(
+            closed_at_limit("gb_aapl", "苹果", &data, None),
+            vec![None; 4]
+        );
+
+        // 2025-02-12 十送十除权：参考价 5.00，收 5.00 不是跌停，收 5.50 为涨停
+        let day = |day: &str, close: &str| KLineData {
+            day: day.to_string(),
+            ..k(close)
+        };
+        let factors = AdjustFactors::new(vec![
+            ("2020-01-01".to_string(), 1.0),
+            ("2025-02-12".to_string(), 2.0),
+        ]);
+        let data = vec![day("2025-02-11", "10.00"), day("2025-02-12", "5.00")];
+        assert_eq!(
+            closed_at_limit("sh600000", "浦发银行", &data, None),
+            vec![None, Some(false)]
+        );
+        assert_eq!(
+            closed_at_limit("sh600000", "浦发银行", &data, Some(&factors)),
+            vec![None, None]
+        );
+        let data = vec![day("2025-02-11", "10.00"), day("2025-02-12", "5.50")];
+        assert_eq!(
+            closed_at_limit("sh600000", "浦发银行", &data, Some(&factors)),
+            vec![None, Some(true)]
+        );
+    }
+}
@@ -1,8 +1,6 @@
-mod api;
 mod app;
-mod config;
+mod cli;
 mod event;
-mod models;
 mod ui;
 
 use std::io;
@@ -16,11 +14,19 @@ use crossterm::{
 };
 use ratatui::prelude::*;
 
-use app::App;
+use app::{App, InputMode, ViewMode};
 use event::{AppEvent, EventHandler};
-use models::{InputMode, TimeFrame, ViewMode};
+use stock_tui::models::TimeFrame;
+use stock_tui::paper::Side;
 
 fn main() -> Result<()> {
+    // --record / --replay 选择数据源，其余参数为命令行子命令（无子命令时进入终端界面）
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let (provider, args) = cli::provider(&args)?;
+    if !args.is_empty() {
+        return cli::run(args, provider);
+    }
+
     // 初始化终端
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -28,18 +34,15 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    // 创建应用
-    let mut app = App::new();
+    // 创建事件处理器（每秒 Tick，实际刷新间隔见 config.json 的 refresh 项）
+    let events = EventHandler::new(Duration::from_secs(1));
 
-    // 创建事件处理器（每5秒自动刷新）
-    let events = EventHandler::new(Duration::from_secs(5));
+    // 创建应用
+    let mut app = App::new(provider, events.sender());
 
     // 主循环
     loop {
-        // 获取终端宽度用于游标边界计算
-        let term_width = terminal.size()?.width as usize;
-
-        // 渲染
+        // 渲染（同时记录K线图宽度，用于游标边界和缩放计算）
         terminal.draw(|f| ui::draw(f, &mut app))?;
 
         // 处理事件
@@ -50,15 +53,150 @@ fn main() -> Result<()> {
                 }
 
                 match app.input_mode {
+                    // 板块热力图
+                    InputMode::Normal if app.view_mode == ViewMode::Heatmap => match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => {
+                            app.heatmap_back()
+                        }
+                        KeyCode::Left | KeyCode::Char('h') | KeyCode::Up | KeyCode::Char('k') => {
+                            app.board_move(-1)
+                        }
+                        KeyCode::Right
+                        | KeyCode::Char('l')
+                        | KeyCode::Down
+                        | KeyCode::Char('j') => app.board_move(1),
+                        KeyCode::Tab => app.cycle_board_kind(),
+                        KeyCode::Enter if app.board_stocks.is_none() => app.drill_into_board(),
+                        KeyCode::Char('a') => app.add_board_stock(),
+                        KeyCode::Char('r') => app.load_boards(),
+                        _ => {}
+                    },
+                    // 市场宽度
+                    InputMode::Normal if app.view_mode == ViewMode::Breadth => match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('B') => {
+                            app.toggle_breadth()
+                        }
+                        KeyCode::Char('r') => app.refresh_breadth(),
+                        _ => {}
+                    },
+                    // 条件选股结果
+                    InputMode::Normal if app.view_mode == ViewMode::Screener => match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('s') => {
+                            app.view_mode = ViewMode::Normal;
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => app.screen_move(-1),
+                        KeyCode::Down | KeyCode::Char('j') => app.screen_move(1),
+                        KeyCode::Tab => app.next_screen(),
+                        KeyCode::Char('/') => app.start_screen_filter(),
+                        KeyCode::Char('w') => app.start_save_screen(),
+                        KeyCode::Char('o') => app.cycle_screen_sort(),
+                        KeyCode::Char('O') => app.reverse_screen_sort(),
+                        KeyCode::Char('a') => app.add_screen_stock(),
+                        KeyCode::Char('r') => app.run_screen(),
+                        _ => {}
+                    },
+                    InputMode::Normal
+                        if app.replay.is_some() && app.view_mode != ViewMode::Paper =>
+                    {
+                        match key.code {
+                            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                                app.should_quit = true;
+                            }
+                            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('R') => {
+                                app.stop_replay()
+                            }
+                            KeyCode::Char(' ') => app.toggle_replay_play(),
+                            KeyCode::Right | KeyCode::Char('n') => app.replay_step(1),
+                            KeyCode::Char('N') => app.replay_step(10),
+                            KeyCode::Char('+') | KeyCode::Char('=') => app.replay_speed(true),
+                            KeyCode::Char('-') => app.replay_speed(false),
+                            KeyCode::Char('b') => app.start_paper_order(Side::Buy),
+                            KeyCode::Char('s') => app.start_paper_order(Side::Sell),
+                            KeyCode::Char('P') => app.toggle_paper(),
+                            KeyCode::Char('f') => app.toggle_fullscreen(),
+                            _ => {}
+                        }
+                    }
+                    InputMode::Normal if app.view_mode == ViewMode::Grid => match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        KeyCode::Esc | KeyCode::Char('f') if app.grid_expanded => {
+                            app.toggle_grid_expand()
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') => app.toggle_grid(),
+                        KeyCode::Char('f') => app.toggle_grid_expand(),
+                        KeyCode::Left | KeyCode::Char('h') => app.move_grid_focus(-1, 0),
+                        KeyCode::Right | KeyCode::Char('l') => app.move_grid_focus(1, 0),
+                        KeyCode::Up | KeyCode::Char('k') => app.move_grid_focus(0, -1),
+                        KeyCode::Down | KeyCode::Char('j') => app.move_grid_focus(0, 1),
+                        KeyCode::Char(c @ '1'..='7') => {
+                            app.set_grid_timeframe(TimeFrame::all()[c as usize - '1' as usize])
+                        }
+                        KeyCode::Char('[') => app.cycle_grid_symbol(-1),
+                        KeyCode::Char(']') => app.cycle_grid_symbol(1),
+                        KeyCode::Char(',') => app.scroll_grid_cell(true),
+                        KeyCode::Char('.') => app.scroll_grid_cell(false),
+                        KeyCode::Char('e') => app.start_grid_symbol(),
+                        KeyCode::Char('g') => app.cycle_grid_size(),
+                        KeyCode::Char('r') => app.refresh_grid(),
+                        KeyCode::Char('F') => app.cycle_grid_adjust(),
+                        KeyCode::Char('+') | KeyCode::Char('=') => app.zoom_chart(true),
+                        KeyCode::Char('-') => app.zoom_chart(false),
+                        _ => {}
+                    },
+                    InputMode::Normal if app.view_mode == ViewMode::Compare => match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        KeyCode::Esc if app.compare_cursor.is_some() => app.compare_cursor = None,
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') => {
+                            app.toggle_compare()
+                        }
+                        KeyCode::Left | KeyCode::Char('h') => app.move_compare_cursor(-1),
+                        KeyCode::Right | KeyCode::Char('l') => app.move_compare_cursor(1),
+                        KeyCode::Char(c @ '1'..='7') => {
+                            app.set_timeframe(TimeFrame::all()[c as usize - '1' as usize]);
+                            app.load_comparison();
+                        }
+                        KeyCode::Char('r') => app.load_comparison(),
+                        _ => {}
+                    },
+                    InputMode::Normal if app.view_mode == ViewMode::Paper => match key.code {
+                        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                            app.should_quit = true;
+                        }
+                        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => {
+                            app.toggle_paper()
+                        }
+                        KeyCode::Up | KeyCode::Char('k') => app.paper_move(-1),
+                        KeyCode::Down | KeyCode::Char('j') => app.paper_move(1),
+                        KeyCode::Char('b') => app.start_paper_order(Side::Buy),
+                        KeyCode::Char('s') => app.start_paper_order(Side::Sell),
+                        KeyCode::Char('c') => app.cancel_paper_order(),
+                        _ => {}
+                    },
                     InputMode::Normal => {
                         // 计算当前K线图的可见数量
-                        let visible = app.visible_kline_count(term_width);
+                        let visible = app.visible_kline_count();
 
                         match key.code {
                             // 退出
                             KeyCode::Char('q') => {
                                 app.should_quit = true;
                             }
+                            // K线缩放
+                            KeyCode::Char('+') | KeyCode::Char('=') => app.zoom_chart(true),
+                            KeyCode::Char('-') => app.zoom_chart(false),
                             KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
                                 app.should_quit = true;
                             }
@@ -80,15 +218,15 @@ fn main() -> Result<()> {
                                 app.on_enter();
                             }
                             // 自选股上下选择（仅在非全屏时）
-                            KeyCode::Up | KeyCode::Char('k') => {
-                                if app.view_mode == ViewMode::Normal {
-                                    app.select_prev();
-                                }
+                            KeyCode::Up | KeyCode::Char('k')
+                                if app.view_mode == ViewMode::Normal =>
+                            {
+                                app.select_prev();
                             }
-                            KeyCode::Down | KeyCode::Char('j') => {
-                                if app.view_mode == ViewMode::Normal {
-                                    app.select_next();
-                                }
+                            KeyCode::Down | KeyCode::Char('j')
+                                if app.view_mode == ViewMode::Normal =>
+                            {
+                                app.select_next();
                             }
                             // 左右：移动K线游标
                             KeyCode::Left | KeyCode::Char('h') => {
@@ -102,11 +240,12 @@ fn main() -> Result<()> {
                             KeyCode::PageDown => app.scroll_kline_right(),
                             // 添加/删除股票
                             KeyCode::Char('a') => app.start_add_stock(),
-                            KeyCode::Char('d') => {
-                                if app.view_mode == ViewMode::Normal {
-                                    app.delete_selected();
-                                }
+                            KeyCode::Char('d') if app.view_mode == ViewMode::Normal => {
+                                app.delete_selected();
                             }
+                            // 导出K线 / 自选股行情
+                            KeyCode::Char('e') => app.export_kline(),
+                            KeyCode::Char('E') => app.export_watchlist(),
                             // 手动刷新
                             KeyCode::Char('r') => {
                                 app.status_message = "正在刷新...".to_string();
@@ -124,6 +263,32 @@ fn main() -> Result<()> {
                             KeyCode::Char('?') => {
                                 app.input_mode = InputMode::HelpScreen;
                             }
+                            // 板块热力图
+                            KeyCode::Char('H') => app.open_heatmap(),
+                            KeyCode::Char('B') => app.toggle_breadth(),
+                            // 模拟交易
+                            KeyCode::Char('P') => app.toggle_paper(),
+                            // 历史回放
+                            KeyCode::Char('R') => app.start_replay_prompt(),
+                            // 走势对比
+                            KeyCode::Char('x') => app.toggle_compare_symbol(),
+                            KeyCode::Char('C') => app.toggle_compare(),
+                            // 多图网格
+                            KeyCode::Char('g') => app.toggle_grid(),
+                            // 条件选股
+                            KeyCode::Char('s') => app.open_screener(),
+                            // 详情面板
+                            KeyCode::Char('i') => app.toggle_detail(),
+                            // 策略回测
+                            KeyCode::Char('T') => app.toggle_backtest(),
+                            // 切换复权方式
+                            KeyCode::Char('F') => app.cycle_adjust(),
+                            // 暂停 / 恢复自动刷新
+                            KeyCode::Char('p') => app.toggle_pause(),
+                            // 网络诊断
+                            KeyCode::Char('D') => {
+                                app.input_mode = InputMode::Diagnostics;
+                            }
                             _ => {}
                         }
                     }
@@ -138,6 +303,39 @@ fn main() -> Result<()> {
                         }
                         _ => {}
                     },
+                    InputMode::PaperOrder | InputMode::ReplayDate | InputMode::GridSymbol => {
+                        match key.code {
+                            KeyCode::Enter if app.input_mode == InputMode::ReplayDate => {
+                                app.confirm_replay()
+                            }
+                            KeyCode::Enter if app.input_mode == InputMode::GridSymbol => {
+                                app.confirm_grid_symbol()
+                            }
+                            KeyCode::Enter => app.confirm_paper_order(),
+                            KeyCode::Esc => app.cancel_input(),
+                            KeyCode::Backspace => {
+                                app.input_buffer.pop();
+                            }
+                            KeyCode::Char(c) => {
+                                app.input_buffer.push(c);
+                            }
+                            _ => {}
+                        }
+                    }
+                    InputMode::ScreenFilter | InputMode::ScreenName => match key.code {
+                        KeyCode::Enter if app.input_mode == InputMode::ScreenFilter => {
+                            app.confirm_screen_filter()
+                        }
+                        KeyCode::Enter => app.confirm_save_screen(),
+                        KeyCode::Esc => app.cancel_input(),
+                        KeyCode::Backspace => {
+                            app.input_buffer.pop();
+                        }
+                        KeyCode::Char(c) => {
+                            app.input_buffer.push(c);
+                        }
+                        _ => {}
+                    },
                     InputMode::HelpScreen => match key.code {
                         KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => {
                             app.input_mode = InputMode::Normal;
@@ -173,15 +371,21 @@ fn main() -> Result<()> {
                         }
                         _ => {}
                     },
+                    InputMode::Diagnostics => {
+                        if let KeyCode::Esc | KeyCode::Char('D') | KeyCode::Char('q') = key.code {
+                            app.input_mode = InputMode::Normal;
+                        }
+                    }
                 }
             }
             AppEvent::Tick => {
-                // 自动刷新行情
-                app.refresh_quotes();
+                // 自动刷新（休市的市场不请求）
+                app.on_tick();
             }
-            AppEvent::Resize(_, _) => {
+            AppEvent::Resize => {
                 // 终端大小变化会自动重绘
             }
+            AppEvent::Job(result) => app.on_job(result),
         }
 
         if app.should_quit {
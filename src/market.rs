@@ -0,0 +1,401 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+/// 内置节假日表（用户可在配置目录放置 holidays.json 覆盖）
+const DEFAULT_HOLIDAYS: &str = include_str!("../data/holidays.json");
+
+/// 交易市场
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Market {
+    /// 沪深北 A股
+    Cn,
+    /// 港股
+    Hk,
+    /// 美股
+    Us,
+}
+
+/// 市场状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketPhase {
+    /// 盘前（A股、港股为集合竞价）
+    PreMarket,
+    /// 连续交易
+    Open,
+    /// 午间休市
+    Break,
+    /// 已收盘 / 周末 / 节假日
+    Closed,
+}
+
+impl MarketPhase {
+    /// 是否需要轮询行情（盘前和交易中）
+    pub fn is_live(&self) -> bool {
+        matches!(self, MarketPhase::PreMarket | MarketPhase::Open)
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            MarketPhase::PreMarket => "盘前",
+            MarketPhase::Open => "交易中",
+            MarketPhase::Break => "午休",
+            MarketPhase::Closed => "休市",
+        }
+    }
+}
+
+impl Market {
+    /// 全部市场
+    pub fn all() -> [Market; 3] {
+        [Market::Cn, Market::Hk, Market::Us]
+    }
+
+    /// 根据股票代码判断市场（hk 前缀为港股，gb_ 前缀为美股，其余为 A股）
+    pub fn of(symbol: &str) -> Market {
+        if symbol.starts_with("hk") {
+            Market::Hk
+        } else if symbol.starts_with("gb_") {
+            Market::Us
+        } else {
+            Market::Cn
+        }
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Market::Cn => "A股",
+            Market::Hk => "港股",
+            Market::Us => "美股",
+        }
+    }
+
+    /// 交易所所在时区（美股按 America/New_York 自动处理夏令时）
+    pub fn timezone(&self) -> Tz {
+        match self {
+            Market::Cn => chrono_tz::Asia::Shanghai,
+            Market::Hk => chrono_tz::Asia::Hong_Kong,
+            Market::Us => chrono_tz::America::New_York,
+        }
+    }
+
+    /// 盘前开始时间与各交易时段（当地时间）
+    fn sessions(&self) -> (NaiveTime, Vec<(NaiveTime, NaiveTime)>) {
+        let t = |h, m| NaiveTime::from_hms_opt(h, m, 0).unwrap();
+        match self {
+            // 9:15 集合竞价，9:30-11:30 / 13:00-15:00
+            Market::Cn => (t(9, 15), vec![(t(9, 30), t(11, 30)), (t(13, 0), t(15, 0))]),
+            // 9:00 开市前时段，9:30-12:00 / 13:00-16:10（含收市竞价）
+            Market::Hk => (t(9, 0), vec![(t(9, 30), t(12, 0)), (t(13, 0), t(16, 10))]),
+            // 4:00 盘前交易，9:30-16:00
+            Market::Us => (t(4, 0), vec![(t(9, 30), t(16, 0))]),
+        }
+    }
+}
+
+/// 交易日历：周末和节假日休市
+#[derive(Debug, Clone, Default)]
+pub struct TradingCalendar {
+    holidays: HashMap<Market, HashSet<NaiveDate>>,
+}
+
+impl TradingCalendar {
+    /// 解析节假日 JSON：`{"cn": ["2025-01-01", ...], "hk": [...], "us": [...]}`
+    pub fn parse(json: &str) -> Result<Self> {
+        let holidays: HashMap<Market, HashSet<NaiveDate>> =
+            serde_json::from_str(json).context("节假日文件格式错误")?;
+        Ok(Self { holidays })
+    }
+
+    /// 读取用户节假日文件，不存在或格式错误时使用内置节假日表
+    pub fn load(path: Option<&Path>) -> Self {
+        path.and_then(|p| fs::read_to_string(p).ok())
+            .and_then(|json| Self::parse(&json).ok())
+            .unwrap_or_else(|| Self::parse(DEFAULT_HOLIDAYS).expect("内置节假日表格式正确"))
+    }
+
+    /// 当地日期是否为交易日
+    pub fn is_trading_day(&self, market: Market, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+            && !self
+                .holidays
+                .get(&market)
+                .is_some_and(|days| days.contains(&date))
+    }
+
+    /// 某一时刻的市场状态
+    pub fn phase(&self, market: Market, now: DateTime<Utc>) -> MarketPhase {
+        let local = now.with_timezone(&market.timezone());
+        if !self.is_trading_day(market, local.date_naive()) {
+            return MarketPhase::Closed;
+        }
+        let time = local.time();
+        let (pre_open, sessions) = market.sessions();
+        let first_open = sessions[0].0;
+        let last_close = sessions[sessions.len() - 1].1;
+        if time < pre_open || time >= last_close {
+            MarketPhase::Closed
+        } else if time < first_open {
+            MarketPhase::PreMarket
+        } else if sessions
+            .iter()
+            .any(|&(open, close)| open <= time && time < close)
+        {
+            MarketPhase::Open
+        } else {
+            MarketPhase::Break
+        }
+    }
+}
+
+/// 行情刷新调度：只轮询开市中的股票，收盘（或进入午休）后再刷新一次
+pub struct Scheduler {
+    calendar: TradingCalendar,
+    live: HashMap<Market, bool>,
+    polled: HashSet<String>,
+}
+
+impl Scheduler {
+    /// 使用指定交易日历
+    pub fn new(calendar: TradingCalendar) -> Self {
+        Self {
+            calendar,
+            live: HashMap::new(),
+            polled: HashSet::new(),
+        }
+    }
+
+    /// 交易日历
+    pub fn calendar(&self) -> &TradingCalendar {
+        &self.calendar
+    }
+
+    /// 返回本轮需要刷新的股票：开市中的、刚收盘需最后刷新一次的、以及从未获取过的
+    pub fn due(&mut self, symbols: &[String], now: DateTime<Utc>) -> Vec<String> {
+        let mut final_refresh = HashSet::new();
+        for market in Market::all() {
+            let live = self.calendar.phase(market, now).is_live();
+            let was_live = self.live.insert(market, live).unwrap_or(false);
+            if was_live && !live {
+                final_refresh.insert(market);
+            }
+        }
+
+        let due: Vec<String> = symbols
+            .iter()
+            .filter(|s| {
+                let market = Market::of(s);
+                self.live[&market] || final_refresh.contains(&market) || !self.polled.contains(*s)
+            })
+            .cloned()
+            .collect();
+        self.polled.extend(due.iter().cloned());
+        due
+    }
+
+    /// 对几组股票一起调用 [`Scheduler::due`]，再按组拆分结果
+    ///
+    /// 收盘后的最后一次刷新只在一次调用中出现，分组分别调用时后面的组会错过它。
+    pub fn due_groups(&mut self, groups: &[&[String]], now: DateTime<Utc>) -> Vec<Vec<String>> {
+        let all: Vec<String> = groups.iter().flat_map(|g| g.iter().cloned()).collect();
+        let due = self.due(&all, now);
+        groups
+            .iter()
+            .map(|g| g.iter().filter(|s| due.contains(s)).cloned().collect())
+            .collect()
+    }
+}
+
+/// 自动刷新的项目
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RefreshItem {
+    /// 某只股票的行情
+    Quote(String),
+    /// 当前股票的K线
+    Kline,
+    /// 顶部指数条
+    Indices,
+    /// 市场宽度
+    Breadth,
+    /// 多图网格
+    Grid,
+}
+
+/// 自动刷新计时：记录各项最近一次刷新的时间，暂停期间所有项目都不到期
+#[derive(Debug, Clone, Default)]
+pub struct RefreshTimer {
+    paused: bool,
+    last: HashMap<RefreshItem, Instant>,
+}
+
+impl RefreshTimer {
+    /// 是否已暂停
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// 暂停 / 恢复，返回切换后是否暂停
+    pub fn toggle_pause(&mut self) -> bool {
+        self.paused = !self.paused;
+        self.paused
+    }
+
+    /// 未暂停且从未刷新或距最近一次刷新已超过 interval 时到期
+    pub fn due(&self, item: &RefreshItem, interval: Duration, now: Instant) -> bool {
+        !self.paused
+            && self
+                .last
+                .get(item)
+                .is_none_or(|t| now.saturating_duration_since(*t) >= interval)
+    }
+
+    /// 记录刷新时间（手动刷新也应记录，避免紧接着重复刷新）
+    pub fn mark(&mut self, item: RefreshItem, now: Instant) {
+        self.last.insert(item, now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(market: Market, y: i32, mo: u32, d: u32, h: u32, mi: u32) -> DateTime<Utc> {
+        market
+            .timezone()
+            .with_ymd_and_hms(y, mo, d, h, mi, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_phases() {
+        let cal = TradingCalendar::load(None);
+        // 2025-02-11 周二
+        let cases = [
+            (Market::Cn, 9, 0, MarketPhase::Closed),
+            (Market::Cn, 9, 20, MarketPhase::PreMarket),
+            (Market::Cn, 10, 0, MarketPhase::Open),
+            (Market::Cn, 12, 0, MarketPhase::Break),
+            (Market::Cn, 14, 59, MarketPhase::Open),
+            (Market::Cn, 15, 0, MarketPhase::Closed),
+            (Market::Hk, 12, 30, MarketPhase::Break),
+            (Market::Hk, 16, 5, MarketPhase::Open),
+            (Market::Us, 8, 0, MarketPhase::PreMarket),
+            (Market::Us, 15, 59, MarketPhase::Open),
+        ];
+        for (market, h, m, phase) in cases {
+            assert_eq!(
+                cal.phase(market, at(market, 2025, 2, 11, h, m)),
+                phase,
+                "{:?} {}:{}",
+                market,
+                h,
+                m
+            );
+        }
+        // 周六 / 春节
+        assert_eq!(
+            cal.phase(Market::Cn, at(Market::Cn, 2025, 2, 8, 10, 0)),
+            MarketPhase::Closed
+        );
+        assert_eq!(
+            cal.phase(Market::Cn, at(Market::Cn, 2025, 1, 29, 10, 0)),
+            MarketPhase::Closed
+        );
+    }
+
+    #[test]
+    fn test_us_daylight_saving() {
+        let cal = TradingCalendar::default();
+        // 冬令时 9:30 ET = 14:30 UTC，夏令时 9:30 ET = 13:30 UTC
+        let winter = Utc.with_ymd_and_hms(2025, 2, 11, 14, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2025, 7, 8, 14, 0, 0).unwrap();
+        assert_eq!(cal.phase(Market::Us, winter), MarketPhase::PreMarket);
+        assert_eq!(cal.phase(Market::Us, summer), MarketPhase::Open);
+    }
+
+    #[test]
+    fn test_scheduler_polls_open_markets_then_final_refresh() {
+        let mut scheduler = Scheduler::new(TradingCalendar::default());
+        let symbols = vec!["sh600519".to_string(), "gb_aapl".to_string()];
+        // 首次全部刷新
+        let open = at(Market::Cn, 2025, 2, 11, 10, 0);
+        assert_eq!(scheduler.due(&symbols, open), symbols);
+        // A股交易中，美股（夜间）休市
+        assert_eq!(scheduler.due(&symbols, open), vec!["sh600519"]);
+        // 午休开始时最后刷新一次，之后不再轮询
+        let lunch = at(Market::Cn, 2025, 2, 11, 11, 31);
+        assert_eq!(scheduler.due(&symbols, lunch), vec!["sh600519"]);
+        assert!(scheduler.due(&symbols, lunch).is_empty());
+        // 新加入的股票即使休市也获取一次
+        let added = vec!["gb_msft".to_string()];
+        assert_eq!(scheduler.due(&added, lunch), added);
+    }
+
+    #[test]
+    fn test_scheduler_groups_share_final_refresh() {
+        let mut scheduler = Scheduler::new(TradingCalendar::default());
+        let watchlist = vec!["sh600519".to_string()];
+        let paper = vec!["sz000001".to_string()];
+        let open = at(Market::Cn, 2025, 2, 11, 14, 0);
+        assert_eq!(
+            scheduler.due_groups(&[&watchlist, &paper], open),
+            vec![watchlist.clone(), paper.clone()]
+        );
+        // 收盘时只在自选股之外持有的股票也最后刷新一次
+        let close = at(Market::Cn, 2025, 2, 11, 15, 1);
+        assert_eq!(
+            scheduler.due_groups(&[&watchlist, &paper], close),
+            vec![watchlist.clone(), paper.clone()]
+        );
+        assert_eq!(
+            scheduler.due_groups(&[&watchlist, &paper], close),
+            vec![Vec::<String>::new(), Vec::new()]
+        );
+    }
+
+    #[test]
+    fn test_user_calendar() {
+        let cal = TradingCalendar::parse(r#"{"us": ["2025-02-11"]}"#).unwrap();
+        let day = NaiveDate::from_ymd_opt(2025, 2, 11).unwrap();
+        assert!(!cal.is_trading_day(Market::Us, day));
+        assert!(cal.is_trading_day(Market::Cn, day));
+        assert!(TradingCalendar::parse(r#"{"xx": []}"#).is_err());
+    }
+
+    #[test]
+    fn test_refresh_timer_intervals_and_pause() {
+        let mut timer = RefreshTimer::default();
+        let t0 = Instant::now();
+        let secs = Duration::from_secs;
+        let quote = RefreshItem::Quote("sh600519".to_string());
+        // 从未刷新的项目立即到期
+        assert!(timer.due(&quote, secs(5), t0));
+        timer.mark(quote.clone(), t0);
+        timer.mark(RefreshItem::Kline, t0);
+        assert!(!timer.due(&quote, secs(5), t0 + secs(4)));
+        assert!(timer.due(&quote, secs(5), t0 + secs(5)));
+        // 各项目独立计时
+        assert!(timer.due(&RefreshItem::Quote("gb_aapl".to_string()), secs(30), t0));
+        assert!(!timer.due(&RefreshItem::Kline, secs(30), t0 + secs(10)));
+
+        // 暂停期间都不到期，恢复后按原来的时间判断
+        assert!(timer.toggle_pause());
+        assert!(timer.paused());
+        assert!(!timer.due(&quote, secs(5), t0 + secs(60)));
+        assert!(!timer.due(&RefreshItem::Indices, secs(5), t0 + secs(60)));
+        assert!(!timer.toggle_pause());
+        assert!(timer.due(&quote, secs(5), t0 + secs(60)));
+        assert!(timer.due(&RefreshItem::Kline, secs(30), t0 + secs(60)));
+    }
+}
@@ -1,7 +1,16 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+/// 盘口档位
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PriceLevel {
+    /// 价格
+    pub price: f64,
+    /// 挂单量（股），接口不提供时为 0
+    pub volume: f64,
+}
 
 /// 实时行情数据
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct StockQuote {
     /// 股票名称
     pub name: String,
@@ -25,6 +34,31 @@ pub struct StockQuote {
     pub date: String,
     /// 时间
     pub time: String,
+    /// 买盘（买一在前），A股五档、港股一档、美股无
+    pub bids: Vec<PriceLevel>,
+    /// 卖盘（卖一在前）
+    pub asks: Vec<PriceLevel>,
+    /// 扩展指标（市盈率、市值等，数据源提供时才有值）
+    pub ext: QuoteExt,
+}
+
+/// 扩展行情指标，数据源不提供的为 None
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct QuoteExt {
+    /// 换手率（百分比）
+    pub turnover_rate: Option<f64>,
+    /// 量比（成交量 / 近期日均成交量）
+    pub volume_ratio: Option<f64>,
+    /// 52周最高
+    pub week52_high: Option<f64>,
+    /// 52周最低
+    pub week52_low: Option<f64>,
+    /// 市盈率 (TTM)
+    pub pe_ttm: Option<f64>,
+    /// 总市值
+    pub market_cap: Option<f64>,
+    /// 流通市值
+    pub float_market_cap: Option<f64>,
 }
 
 impl StockQuote {
@@ -42,6 +76,15 @@ impl StockQuote {
         }
     }
 
+    /// 振幅（百分比）: (最高 - 最低) / 昨收
+    pub fn amplitude(&self) -> f64 {
+        if self.pre_close == 0.0 || self.high == 0.0 {
+            0.0
+        } else {
+            (self.high - self.low) / self.pre_close * 100.0
+        }
+    }
+
     /// 格式化成交量（万手）
     pub fn volume_display(&self) -> String {
         let lots = self.volume / 100.0; // 股 -> 手
@@ -54,40 +97,158 @@ impl StockQuote {
 
     /// 格式化成交额（亿/万）
     pub fn turnover_display(&self) -> String {
-        if self.turnover >= 1_0000_0000.0 {
-            format!("{:.2}亿", self.turnover / 1_0000_0000.0)
-        } else if self.turnover >= 10000.0 {
-            format!("{:.1}万", self.turnover / 10000.0)
-        } else {
-            format!("{:.0}元", self.turnover)
+        format_amount(self.turnover)
+    }
+}
+
+/// 格式化金额（万亿/亿/万）
+pub fn format_amount(v: f64) -> String {
+    if v >= 1_0000_0000_0000.0 {
+        format!("{:.2}万亿", v / 1_0000_0000_0000.0)
+    } else if v >= 1_0000_0000.0 {
+        format!("{:.2}亿", v / 1_0000_0000.0)
+    } else if v >= 10000.0 {
+        format!("{:.1}万", v / 10000.0)
+    } else {
+        format!("{:.0}元", v)
+    }
+}
+
+/// 自选股列表可选的附加列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuoteField {
+    /// 成交额
+    Turnover,
+    /// 换手率
+    TurnoverRate,
+    /// 量比
+    VolumeRatio,
+    /// 振幅
+    Amplitude,
+    /// 52周最高
+    Week52High,
+    /// 52周最低
+    Week52Low,
+    /// 市盈率 (TTM)
+    Pe,
+    /// 总市值
+    MarketCap,
+    /// 流通市值
+    FloatMarketCap,
+}
+
+impl QuoteField {
+    /// 全部附加列
+    pub fn all() -> [QuoteField; 9] {
+        [
+            QuoteField::Turnover,
+            QuoteField::TurnoverRate,
+            QuoteField::VolumeRatio,
+            QuoteField::Amplitude,
+            QuoteField::Week52High,
+            QuoteField::Week52Low,
+            QuoteField::Pe,
+            QuoteField::MarketCap,
+            QuoteField::FloatMarketCap,
+        ]
+    }
+
+    /// 列标题
+    pub fn label(&self) -> &'static str {
+        match self {
+            QuoteField::Turnover => "成交额",
+            QuoteField::TurnoverRate => "换手率",
+            QuoteField::VolumeRatio => "量比",
+            QuoteField::Amplitude => "振幅",
+            QuoteField::Week52High => "52周高",
+            QuoteField::Week52Low => "52周低",
+            QuoteField::Pe => "市盈率",
+            QuoteField::MarketCap => "总市值",
+            QuoteField::FloatMarketCap => "流通市值",
+        }
+    }
+
+    /// 格式化后的值，数据源不提供时为 "--"
+    pub fn display(&self, q: &StockQuote) -> String {
+        let ext = &q.ext;
+        let value = match self {
+            QuoteField::Turnover => return q.turnover_display(),
+            QuoteField::Amplitude => return format!("{:.2}%", q.amplitude()),
+            QuoteField::TurnoverRate => ext.turnover_rate.map(|v| format!("{:.2}%", v)),
+            QuoteField::VolumeRatio => ext.volume_ratio.map(|v| format!("{:.2}", v)),
+            QuoteField::Week52High => ext.week52_high.map(|v| format!("{:.2}", v)),
+            QuoteField::Week52Low => ext.week52_low.map(|v| format!("{:.2}", v)),
+            QuoteField::Pe => ext.pe_ttm.map(|v| format!("{:.2}", v)),
+            QuoteField::MarketCap => ext.market_cap.map(format_amount),
+            QuoteField::FloatMarketCap => ext.float_market_cap.map(format_amount),
+        };
+        value.unwrap_or_else(|| "--".to_string())
+    }
+}
+
+/// 指数行情（指数条使用）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct IndexQuote {
+    /// 请求使用的代码，如 s_sh000001、int_dji、hkHSI
+    pub symbol: String,
+    /// 指数名称
+    pub name: String,
+    /// 当前点位
+    pub current: f64,
+    /// 涨跌点数
+    pub change: f64,
+    /// 涨跌幅（百分比）
+    pub change_percent: f64,
+}
+
+impl From<StockQuote> for IndexQuote {
+    fn from(q: StockQuote) -> Self {
+        Self {
+            change: q.change(),
+            change_percent: q.change_percent(),
+            symbol: q.symbol,
+            name: q.name,
+            current: q.current,
         }
     }
 }
 
 /// K线数据（从新浪财经 JSON API 返回）
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KLineData {
+    /// 日期 (YYYY-MM-DD)，分钟线带时间 (YYYY-MM-DD HH:MM:SS)
     pub day: String,
+    /// 开盘价
     pub open: String,
+    /// 最高价
     pub high: String,
+    /// 最低价
     pub low: String,
+    /// 收盘价
     pub close: String,
+    /// 成交量（股）
     pub volume: String,
 }
 
 impl KLineData {
+    /// 开盘价（解析失败为 0）
     pub fn open_f64(&self) -> f64 {
         self.open.parse().unwrap_or(0.0)
     }
+    /// 最高价（解析失败为 0）
     pub fn high_f64(&self) -> f64 {
         self.high.parse().unwrap_or(0.0)
     }
+    /// 最低价（解析失败为 0）
     pub fn low_f64(&self) -> f64 {
         self.low.parse().unwrap_or(0.0)
     }
+    /// 收盘价（解析失败为 0）
     pub fn close_f64(&self) -> f64 {
         self.close.parse().unwrap_or(0.0)
     }
+    /// 成交量（解析失败为 0）
     pub fn volume_f64(&self) -> f64 {
         self.volume.parse().unwrap_or(0.0)
     }
@@ -99,14 +260,21 @@ impl KLineData {
 }
 
 /// K线周期
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TimeFrame {
+    /// 5分钟
     Min5,
+    /// 15分钟
     Min15,
+    /// 30分钟
     Min30,
+    /// 60分钟
     Min60,
+    /// 日K
     Daily,
+    /// 周K
     Weekly,
+    /// 月K
     Monthly,
 }
 
@@ -124,6 +292,7 @@ impl TimeFrame {
         }
     }
 
+    /// 中文名称，如 "5分钟"、"日K"
     pub fn label(&self) -> &'static str {
         match self {
             TimeFrame::Min5 => "5分钟",
@@ -136,6 +305,7 @@ impl TimeFrame {
         }
     }
 
+    /// 简短名称，如 "5m"、"日K"
     pub fn short_label(&self) -> &'static str {
         match self {
             TimeFrame::Min5 => "5m",
@@ -148,6 +318,25 @@ impl TimeFrame {
         }
     }
 
+    /// 英文代码，用于文件名和命令行参数
+    pub fn code(&self) -> &'static str {
+        match self {
+            TimeFrame::Min5 => "5m",
+            TimeFrame::Min15 => "15m",
+            TimeFrame::Min30 => "30m",
+            TimeFrame::Min60 => "60m",
+            TimeFrame::Daily => "daily",
+            TimeFrame::Weekly => "weekly",
+            TimeFrame::Monthly => "monthly",
+        }
+    }
+
+    /// 从英文代码解析（与 code() 对应）
+    pub fn from_code(code: &str) -> Option<TimeFrame> {
+        Self::all().iter().copied().find(|tf| tf.code() == code)
+    }
+
+    /// 全部周期（从短到长）
     pub fn all() -> &'static [TimeFrame] {
         &[
             TimeFrame::Min5,
@@ -161,44 +350,26 @@ impl TimeFrame {
     }
 }
 
-/// 输入模式
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum InputMode {
-    /// 正常浏览模式
-    Normal,
-    /// 输入股票代码模式
-    AddStock,
-    /// 快捷键帮助页面
-    HelpScreen,
-}
+/// 规范化用户输入的股票代码
+/// 转小写、us 前缀转 gb_，并检查前缀: sh, sz, bj, hk, gb_
+pub fn normalize_symbol(input: &str) -> Result<String, &'static str> {
+    let mut symbol = input.trim().to_lowercase();
+    if symbol.is_empty() {
+        return Err("股票代码不能为空");
+    }
 
-/// 视图模式
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ViewMode {
-    /// 正常布局（行情 + K线 + 自选股）
-    Normal,
-    /// 全屏K线图
-    FullscreenChart,
-}
+    // 处理美股 us 前缀转 gb_
+    if symbol.starts_with("us") {
+        symbol = symbol.replacen("us", "gb_", 1);
+    }
 
-/// 计算移动平均线 (MA)
-/// data: K线数据
-/// window: 窗口大小 (如 5, 10, 20)
-pub fn calculate_ma(data: &[KLineData], window: usize) -> Vec<Option<f64>> {
-    let mut ma = Vec::with_capacity(data.len());
-    let mut sum = 0.0;
-    for i in 0..data.len() {
-        sum += data[i].close_f64();
-        if i >= window {
-            sum -= data[i - window].close_f64();
-        }
-        if i >= window - 1 {
-            ma.push(Some(sum / window as f64));
-        } else {
-            ma.push(None);
-        }
+    if !["sh", "sz", "bj", "hk", "gb_"]
+        .iter()
+        .any(|prefix| symbol.starts_with(prefix))
+    {
+        return Err("格式错误，支持前缀: sh/sz/bj(A股/北交), hk(港股), gb_/us(美股)");
     }
-    ma
+    Ok(symbol)
 }
 
 #[cfg(test)]
@@ -206,31 +377,30 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_calculate_ma() {
-        // Create dummy data
-        let prices = vec![10.0, 20.0, 30.0, 40.0, 50.0];
-        let data: Vec<KLineData> = prices
-            .iter()
-            .map(|&p| KLineData {
-                day: "2023-01-01".to_string(),
-                open: "0.0".to_string(),
-                high: "0.0".to_string(),
-                low: "0.0".to_string(),
-                close: p.to_string(),
-                volume: "0".to_string(),
-            })
-            .collect();
-
-        // MA 3
-        // 10, 20, 30 -> 20
-        // 20, 30, 40 -> 30
-        // 30, 40, 50 -> 40
-        let ma3 = calculate_ma(&data, 3);
-        assert_eq!(ma3.len(), 5);
-        assert_eq!(ma3[0], None);
-        assert_eq!(ma3[1], None);
-        assert_eq!(ma3[2], Some(20.0));
-        assert_eq!(ma3[3], Some(30.0));
-        assert_eq!(ma3[4], Some(40.0));
+    fn test_normalize_symbol() {
+        assert_eq!(normalize_symbol(" SH600519 "), Ok("sh600519".to_string()));
+        assert_eq!(normalize_symbol("usAAPL"), Ok("gb_aapl".to_string()));
+        assert!(normalize_symbol("").is_err());
+        assert!(normalize_symbol("600519").is_err());
+    }
+
+    #[test]
+    fn test_extended_fields() {
+        let q = StockQuote {
+            pre_close: 10.0,
+            high: 10.5,
+            low: 9.8,
+            ext: QuoteExt {
+                pe_ttm: Some(34.81),
+                market_cap: Some(4053169131200.0),
+                ..QuoteExt::default()
+            },
+            ..StockQuote::default()
+        };
+        assert!((q.amplitude() - 7.0).abs() < 1e-9);
+        assert_eq!(QuoteField::Amplitude.display(&q), "7.00%");
+        assert_eq!(QuoteField::Pe.display(&q), "34.81");
+        assert_eq!(QuoteField::MarketCap.display(&q), "4.05万亿");
+        assert_eq!(QuoteField::TurnoverRate.display(&q), "--");
     }
 }
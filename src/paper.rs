@@ -0,0 +1,589 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::backtest::Fees;
+use crate::market::Market;
+use crate::models::StockQuote;
+
+/// 模拟交易设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaperConfig {
+    /// 开户资金（元）
+    pub initial_cash: f64,
+    /// 交易费用
+    pub fees: Fees,
+}
+
+impl Default for PaperConfig {
+    fn default() -> Self {
+        Self {
+            initial_cash: 1_000_000.0,
+            fees: Fees::default(),
+        }
+    }
+}
+
+/// 买卖方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    /// 买入
+    Buy,
+    /// 卖出
+    Sell,
+}
+
+impl Side {
+    /// 显示名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            Side::Buy => "买入",
+            Side::Sell => "卖出",
+        }
+    }
+}
+
+/// 委托状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderStatus {
+    /// 未成交
+    Open,
+    /// 已成交
+    Filled,
+    /// 已撤单
+    Cancelled,
+    /// 废单（成交时资金或持仓不足）
+    Rejected(String),
+}
+
+impl OrderStatus {
+    /// 显示名称
+    pub fn label(&self) -> &str {
+        match self {
+            OrderStatus::Open => "未成交",
+            OrderStatus::Filled => "已成交",
+            OrderStatus::Cancelled => "已撤单",
+            OrderStatus::Rejected(reason) => reason,
+        }
+    }
+}
+
+/// 委托
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    /// 委托编号
+    pub id: u64,
+    /// 股票代码
+    pub symbol: String,
+    /// 股票名称
+    pub name: String,
+    /// 方向
+    pub side: Side,
+    /// 数量（股）
+    pub shares: f64,
+    /// 限价，None 为市价
+    pub limit: Option<f64>,
+    /// 委托时间
+    pub time: String,
+    /// 状态
+    pub status: OrderStatus,
+}
+
+/// 成交
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fill {
+    /// 委托编号
+    pub order_id: u64,
+    /// 股票代码
+    pub symbol: String,
+    /// 股票名称
+    pub name: String,
+    /// 方向
+    pub side: Side,
+    /// 成交价
+    pub price: f64,
+    /// 成交数量（股）
+    pub shares: f64,
+    /// 费用（佣金、印花税、过户费）
+    pub fee: f64,
+    /// 成交时间 (YYYY-MM-DD HH:MM:SS)
+    pub time: String,
+}
+
+/// 持仓
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Position {
+    /// 股票名称
+    pub name: String,
+    /// 持股数量
+    pub shares: f64,
+    /// 持仓成本（含费用）
+    pub cost: f64,
+    /// 最新价
+    pub last_price: f64,
+    /// 当日买入的数量（A股 T+1，当日不可卖出）
+    pub bought_today: f64,
+    /// bought_today 对应的日期
+    pub bought_date: String,
+}
+
+impl Position {
+    /// 某日可卖出的数量
+    pub fn sellable(&self, date: &str) -> f64 {
+        if self.bought_date == date {
+            self.shares - self.bought_today
+        } else {
+            self.shares
+        }
+    }
+
+    /// 市值
+    pub fn value(&self) -> f64 {
+        self.shares * self.last_price
+    }
+
+    /// 浮动盈亏
+    pub fn profit(&self) -> f64 {
+        self.value() - self.cost
+    }
+}
+
+/// 每日盈亏
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DayPnl {
+    /// 日期 (YYYY-MM-DD)
+    pub date: String,
+    /// 收盘总资产
+    pub equity: f64,
+    /// 当日盈亏
+    pub pnl: f64,
+}
+
+/// 模拟账户：资金、委托、成交、持仓和每日盈亏日志
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Account {
+    /// 开户资金
+    pub initial_cash: f64,
+    /// 可用资金
+    pub cash: f64,
+    /// 持仓（按股票代码）
+    pub positions: BTreeMap<String, Position>,
+    /// 委托（按时间顺序）
+    pub orders: Vec<Order>,
+    /// 成交（按时间顺序）
+    pub fills: Vec<Fill>,
+    /// 每日盈亏日志
+    pub journal: Vec<DayPnl>,
+    /// 下一个委托编号
+    pub next_id: u64,
+}
+
+impl Default for Account {
+    fn default() -> Self {
+        Self::new(PaperConfig::default().initial_cash)
+    }
+}
+
+/// A股一手的股数，其他市场为 1
+fn lot_size(symbol: &str) -> f64 {
+    if Market::of(symbol) == Market::Cn {
+        100.0
+    } else {
+        1.0
+    }
+}
+
+impl Account {
+    /// 以给定资金开户
+    pub fn new(initial_cash: f64) -> Self {
+        Self {
+            initial_cash,
+            cash: initial_cash,
+            positions: BTreeMap::new(),
+            orders: Vec::new(),
+            fills: Vec::new(),
+            journal: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// 读取账户文件，不存在或格式错误时以给定资金开户
+    pub fn load(path: &Path, initial_cash: f64) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_else(|| Self::new(initial_cash))
+    }
+
+    /// 保存账户文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("保存模拟账户失败: {}", path.display()))
+    }
+
+    /// 总资产（现金 + 持仓市值）
+    pub fn equity(&self) -> f64 {
+        self.cash + self.positions.values().map(Position::value).sum::<f64>()
+    }
+
+    /// 未成交的委托
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.orders.iter().filter(|o| o.status == OrderStatus::Open)
+    }
+
+    /// 需要行情的股票：有持仓或未成交委托的股票（按代码排序，不重复）
+    pub fn symbols(&self) -> Vec<String> {
+        let mut symbols: Vec<String> = self
+            .positions
+            .keys()
+            .cloned()
+            .chain(self.open_orders().map(|o| o.symbol.clone()))
+            .collect();
+        symbols.sort();
+        symbols.dedup();
+        symbols
+    }
+
+    /// 下单，返回委托编号
+    ///
+    /// A股买入须为 100 股整数倍，卖出不超过可卖数量（当日买入的部分 T+1 后才可卖出）。
+    /// `date` 为当前交易日，`time` 为委托时间。
+    pub fn place(
+        &mut self,
+        quote: &StockQuote,
+        side: Side,
+        shares: f64,
+        limit: Option<f64>,
+        date: &str,
+        time: &str,
+    ) -> Result<u64> {
+        let lot = lot_size(&quote.symbol);
+        if shares <= 0.0 || shares.fract() != 0.0 {
+            bail!("委托数量必须为正整数");
+        }
+        if limit.is_some_and(|p| p <= 0.0) {
+            bail!("限价必须大于 0");
+        }
+        let reserved = |account: &Self| {
+            account
+                .open_orders()
+                .filter(|o| o.symbol == quote.symbol && o.side == side)
+                .map(|o| o.shares)
+                .sum::<f64>()
+        };
+        match side {
+            Side::Buy => {
+                if shares % lot != 0.0 {
+                    bail!("买入数量必须为 {} 股的整数倍", lot);
+                }
+                let price = limit.unwrap_or(quote.current);
+                if price * shares > self.cash {
+                    bail!(
+                        "可用资金不足: 需要 {:.2}，可用 {:.2}",
+                        price * shares,
+                        self.cash
+                    );
+                }
+            }
+            Side::Sell => {
+                let sellable = self
+                    .positions
+                    .get(&quote.symbol)
+                    .map_or(0.0, |p| p.sellable(date))
+                    - reserved(self);
+                if shares > sellable {
+                    bail!("可卖数量不足: 可卖 {} 股", sellable.max(0.0));
+                }
+            }
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.push(Order {
+            id,
+            symbol: quote.symbol.clone(),
+            name: quote.name.clone(),
+            side,
+            shares,
+            limit,
+            time: time.to_string(),
+            status: OrderStatus::Open,
+        });
+        Ok(id)
+    }
+
+    /// 撤销未成交的委托，返回是否撤单成功
+    pub fn cancel(&mut self, id: u64) -> bool {
+        match self
+            .orders
+            .iter_mut()
+            .find(|o| o.id == id && o.status == OrderStatus::Open)
+        {
+            Some(order) => {
+                order.status = OrderStatus::Cancelled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 用最新行情撮合该股票的未成交委托并更新持仓市值，返回本次成交
+    ///
+    /// 买入按卖一价、卖出按买一价成交（接口不提供盘口时用最新价），限价单在对手价优于限价时成交。
+    /// A股卖一为空（涨停封板）时无法买入，买一为空（跌停封板）时无法卖出。
+    pub fn match_quote(&mut self, quote: &StockQuote, fees: &Fees) -> Vec<Fill> {
+        if quote.current <= 0.0 {
+            return Vec::new();
+        }
+        let a_share = Market::of(&quote.symbol) == Market::Cn;
+        let best = |levels: &[crate::models::PriceLevel]| {
+            levels
+                .first()
+                .map(|l| l.price)
+                .filter(|&p| p > 0.0)
+                .or((!a_share).then_some(quote.current))
+        };
+        let (ask, bid) = (best(&quote.asks), best(&quote.bids));
+        let date = quote.date.as_str();
+        let time = format!("{} {}", quote.date, quote.time);
+
+        let mut fills = Vec::new();
+        for i in 0..self.orders.len() {
+            let order = &self.orders[i];
+            if order.symbol != quote.symbol || order.status != OrderStatus::Open {
+                continue;
+            }
+            let price = match order.side {
+                Side::Buy => ask.filter(|&p| order.limit.is_none_or(|limit| p <= limit)),
+                Side::Sell => bid.filter(|&p| order.limit.is_none_or(|limit| p >= limit)),
+            };
+            let Some(price) = price else {
+                continue;
+            };
+            let (side, shares) = (order.side, order.shares);
+            let amount = price * shares;
+            let status = match side {
+                Side::Buy => {
+                    let fee = fees.buy_cost(amount);
+                    if amount + fee > self.cash {
+                        OrderStatus::Rejected("资金不足".to_string())
+                    } else {
+                        self.cash -= amount + fee;
+                        let position = self.positions.entry(quote.symbol.clone()).or_default();
+                        if position.bought_date != date {
+                            position.bought_today = 0.0;
+                            position.bought_date = date.to_string();
+                        }
+                        if a_share {
+                            position.bought_today += shares;
+                        }
+                        position.name = quote.name.clone();
+                        position.shares += shares;
+                        position.cost += amount + fee;
+                        fills.push((i, price, fee));
+                        OrderStatus::Filled
+                    }
+                }
+                Side::Sell => {
+                    let position = self.positions.get_mut(&quote.symbol);
+                    match position.filter(|p| p.sellable(date) >= shares) {
+                        None => OrderStatus::Rejected("可卖不足".to_string()),
+                        Some(position) => {
+                            let fee = fees.sell_cost(amount);
+                            self.cash += amount - fee;
+                            position.cost -= position.cost * shares / position.shares;
+                            position.shares -= shares;
+                            if position.shares <= 0.0 {
+                                self.positions.remove(&quote.symbol);
+                            }
+                            fills.push((i, price, fee));
+                            OrderStatus::Filled
+                        }
+                    }
+                }
+            };
+            self.orders[i].status = status;
+        }
+        if let Some(position) = self.positions.get_mut(&quote.symbol) {
+            position.last_price = quote.current;
+        }
+
+        let fills: Vec<Fill> = fills
+            .into_iter()
+            .map(|(i, price, fee)| {
+                let order = &self.orders[i];
+                Fill {
+                    order_id: order.id,
+                    symbol: order.symbol.clone(),
+                    name: order.name.clone(),
+                    side: order.side,
+                    price,
+                    shares: order.shares,
+                    fee,
+                    time: time.clone(),
+                }
+            })
+            .collect();
+        self.fills.extend(fills.iter().cloned());
+        fills
+    }
+
+    /// 记录某日的总资产和当日盈亏（同一天多次记录时保留最后一次，即收盘盈亏）
+    pub fn record_day(&mut self, date: &str) {
+        let equity = self.equity();
+        if let Some(last) = self.journal.last_mut().filter(|d| d.date == date) {
+            last.pnl += equity - last.equity;
+            last.equity = equity;
+            return;
+        }
+        let previous = self.journal.last().map_or(self.initial_cash, |d| d.equity);
+        self.journal.push(DayPnl {
+            date: date.to_string(),
+            equity,
+            pnl: equity - previous,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::PriceLevel;
+
+    fn quote(symbol: &str, date: &str, bid: f64, ask: f64) -> StockQuote {
+        let level = |price| {
+            vec![PriceLevel {
+                price,
+                volume: 1000.0,
+            }]
+        };
+        StockQuote {
+            symbol: symbol.to_string(),
+            name: "测试".to_string(),
+            pre_close: 10.0,
+            current: (bid + ask) / 2.0,
+            date: date.to_string(),
+            time: "10:00:00".to_string(),
+            bids: level(bid),
+            asks: level(ask),
+            ..StockQuote::default()
+        }
+    }
+
+    #[test]
+    fn test_market_and_limit_orders() {
+        let fees = Fees::default();
+        let mut account = Account::new(100_000.0);
+        let q = quote("sh600000", "2025-02-10", 10.0, 10.02);
+        assert!(account
+            .place(&q, Side::Buy, 150.0, None, "2025-02-10", "10:00:00")
+            .is_err());
+        account
+            .place(&q, Side::Buy, 1000.0, None, "2025-02-10", "10:00:00")
+            .unwrap();
+        let limit = account
+            .place(&q, Side::Buy, 100.0, Some(9.9), "2025-02-10", "10:00:00")
+            .unwrap();
+
+        // 市价单按卖一成交，限价单等待
+        let fills = account.match_quote(&q, &fees);
+        assert_eq!(fills.len(), 1);
+        assert_eq!((fills[0].price, fills[0].shares), (10.02, 1000.0));
+        assert!((account.cash - (100_000.0 - 10_020.0 - 5.1002)).abs() < 1e-6);
+        assert_eq!(account.open_orders().count(), 1);
+
+        // 卖一跌到限价以下时按卖一成交
+        let fills = account.match_quote(&quote("sh600000", "2025-02-10", 9.85, 9.88), &fees);
+        assert_eq!((fills[0].order_id, fills[0].price), (limit, 9.88));
+        let position = &account.positions["sh600000"];
+        assert_eq!(position.shares, 1100.0);
+        assert_eq!(position.last_price, 9.865);
+        assert!(!account.cancel(limit));
+    }
+
+    #[test]
+    fn test_t_plus_one_and_limit_up() {
+        let fees = Fees::default();
+        let mut account = Account::new(100_000.0);
+        let q = quote("sz000001", "2025-02-10", 10.0, 10.01);
+        account
+            .place(&q, Side::Buy, 500.0, None, "2025-02-10", "10:00:00")
+            .unwrap();
+        account.match_quote(&q, &fees);
+        // 当日买入不可卖出
+        assert!(account
+            .place(&q, Side::Sell, 500.0, None, "2025-02-10", "10:01:00")
+            .is_err());
+        let id = account
+            .place(&q, Side::Sell, 300.0, None, "2025-02-11", "09:31:00")
+            .unwrap();
+        assert!(account
+            .place(&q, Side::Sell, 300.0, None, "2025-02-11", "09:31:00")
+            .is_err());
+        account.match_quote(&quote("sz000001", "2025-02-11", 10.5, 10.51), &fees);
+        assert_eq!(account.positions["sz000001"].shares, 200.0);
+        assert_eq!(account.orders[1].id, id);
+        assert_eq!(account.orders[1].status, OrderStatus::Filled);
+
+        // 涨停封板：卖一为空，买单不成交
+        let mut sealed = quote("sz000001", "2025-02-12", 11.0, 0.0);
+        sealed.current = 11.0;
+        let id = account
+            .place(&sealed, Side::Buy, 100.0, None, "2025-02-12", "10:00:00")
+            .unwrap();
+        assert!(account.match_quote(&sealed, &fees).is_empty());
+        assert!(account.cancel(id));
+        assert_eq!(account.open_orders().count(), 0);
+    }
+
+    #[test]
+    fn test_journal_records_daily_pnl() {
+        let mut account = Account::new(1000.0);
+        account.record_day("2025-02-10");
+        account.cash = 1100.0;
+        account.record_day("2025-02-11");
+        account.cash = 1050.0;
+        account.record_day("2025-02-11");
+        let days: Vec<(&str, f64, f64)> = account
+            .journal
+            .iter()
+            .map(|d| (d.date.as_str(), d.equity, d.pnl))
+            .collect();
+        assert_eq!(
+            days,
+            [("2025-02-10", 1000.0, 0.0), ("2025-02-11", 1050.0, 50.0)]
+        );
+    }
+
+    #[test]
+    fn test_symbols_with_positions_or_open_orders() {
+        let mut account = Account::new(100_000.0);
+        let q = quote("sh600519", "2025-02-10", 10.0, 10.02);
+        account
+            .place(&q, Side::Buy, 100.0, None, &q.date, "10:00:00")
+            .unwrap();
+        account.match_quote(&q, &Fees::default());
+        let other = quote("sz000858", "2025-02-10", 10.0, 10.02);
+        let id = account
+            .place(&other, Side::Buy, 100.0, Some(9.9), &other.date, "10:00:00")
+            .unwrap();
+        account
+            .place(&other, Side::Buy, 100.0, Some(9.8), &other.date, "10:00:01")
+            .unwrap();
+        assert_eq!(account.symbols(), ["sh600519", "sz000858"]);
+        account.cancel(id);
+        account.cancel(id + 1);
+        assert_eq!(account.symbols(), ["sh600519"]);
+    }
+}
@@ -0,0 +1,325 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::StockQuote;
+
+/// 保留的最近分红次数
+const MAX_DIVIDENDS: usize = 10;
+
+/// 一次分红送转
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Dividend {
+    /// 除权除息日 (YYYY-MM-DD)
+    pub date: String,
+    /// 分红方案，如 "10派276.24元"
+    pub plan: String,
+}
+
+/// 公司基本面与简介（详情面板使用）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompanyProfile {
+    /// 每股收益（最近四个季度）
+    pub eps: Option<f64>,
+    /// 每股净资产
+    pub bvps: Option<f64>,
+    /// 净资产收益率（百分比）
+    pub roe: Option<f64>,
+    /// 总股本（股）
+    pub total_shares: Option<f64>,
+    /// 流通股本（股）
+    pub float_shares: Option<f64>,
+    /// 所属行业
+    pub industry: Option<String>,
+    /// 所属概念 / 板块
+    pub sectors: Vec<String>,
+    /// 近期分红（最近的在前）
+    pub dividends: Vec<Dividend>,
+}
+
+impl CompanyProfile {
+    /// 解析新浪个股变量脚本 (jsvar.js)
+    ///
+    /// 格式为若干行 `var 名称 = 值;`，使用其中的 `fourQ_mgsy`（四季度每股收益）、
+    /// `mgjzc`（每股净资产）、`totalcapital` / `currcapital`（总股本 / 流通股本，万股）。
+    pub fn parse_sina_jsvar(text: &str) -> Result<Self> {
+        let vars: HashMap<&str, f64> = text
+            .split(';')
+            .filter_map(|stmt| {
+                let (name, value) = stmt.trim().strip_prefix("var ")?.split_once('=')?;
+                let value = value.trim().trim_matches(|c| c == '\'' || c == '"');
+                Some((name.trim(), value.parse().ok()?))
+            })
+            .collect();
+        if vars.is_empty() {
+            anyhow::bail!("公司资料格式错误: 未找到变量");
+        }
+
+        let eps = vars.get("fourQ_mgsy").copied();
+        let bvps = vars.get("mgjzc").copied().filter(|v| *v != 0.0);
+        Ok(Self {
+            eps,
+            bvps,
+            roe: eps.zip(bvps).map(|(e, b)| e / b * 100.0),
+            total_shares: vars.get("totalcapital").map(|v| v * 10000.0),
+            float_shares: vars.get("currcapital").map(|v| v * 10000.0),
+            ..Self::default()
+        })
+    }
+
+    /// 解析新浪公司资料页的“相关资料”（vCI_CorpOtherInfo），填入所属行业和概念板块
+    ///
+    /// 页面中“所属行业板块”和“所属概念板块”各为表格中的一段，每行第一列为板块名称。
+    pub fn parse_sina_corp_info(&mut self, html: &str) {
+        #[derive(PartialEq)]
+        enum Section {
+            Other,
+            Industry,
+            Concept,
+        }
+        let mut section = Section::Other;
+        for row in html_rows(html) {
+            let first = row.first().map(String::as_str).unwrap_or_default();
+            if first.contains("所属行业板块") {
+                section = Section::Industry;
+            } else if first.contains("所属概念板块") {
+                section = Section::Concept;
+            } else if first.starts_with("所属") {
+                section = Section::Other;
+            } else if first.is_empty() || first.contains("板块名称") || first == "行业" {
+                continue;
+            } else if section == Section::Industry && self.industry.is_none() {
+                self.industry = Some(first.to_string());
+            } else if section == Section::Concept && !self.sectors.iter().any(|s| s == first) {
+                self.sectors.push(first.to_string());
+            }
+        }
+    }
+
+    /// 解析新浪分红送配页（vISSUE_ShareBonus），填入已实施的分红（最近的在前）
+    ///
+    /// 每行依次为公告日期、送股、转增、派息（每 10 股）、进度、除权除息日等，没有除权除息日的跳过。
+    pub fn parse_sina_bonus(&mut self, html: &str) {
+        let number = |s: &str| s.parse::<f64>().ok().filter(|v| *v > 0.0);
+        let mut dividends: Vec<Dividend> = html_rows(html)
+            .into_iter()
+            .filter(|row| row.len() >= 6)
+            .filter_map(|row| {
+                let date = NaiveDate::parse_from_str(&row[5], "%Y-%m-%d").ok()?;
+                let mut plan = "10".to_string();
+                if let Some(v) = number(&row[1]) {
+                    plan.push_str(&format!("送{}", v));
+                }
+                if let Some(v) = number(&row[2]) {
+                    plan.push_str(&format!("转{}", v));
+                }
+                if let Some(v) = number(&row[3]) {
+                    plan.push_str(&format!("派{}元", v));
+                }
+                (plan.len() > 2).then(|| Dividend {
+                    date: date.to_string(),
+                    plan,
+                })
+            })
+            .collect();
+        dividends.sort_by(|a, b| b.date.cmp(&a.date));
+        dividends.truncate(MAX_DIVIDENDS);
+        self.dividends = dividends;
+    }
+
+    /// 读取导入的公司资料 JSON（字段同 [`CompanyProfile`]，均可省略）
+    pub fn load_json(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("读取公司资料失败: {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("{} 格式错误", path.display()))
+    }
+
+    /// 用股本和每股收益补充行情中缺少的换手率、市盈率和市值（已有的值不覆盖）
+    pub fn fill_quote(&self, quote: &mut StockQuote) {
+        let price = if quote.current > 0.0 {
+            quote.current
+        } else {
+            quote.pre_close
+        };
+        let ext = &mut quote.ext;
+        if let Some(total) = self.total_shares.filter(|v| *v > 0.0) {
+            ext.market_cap.get_or_insert(price * total);
+        }
+        if let Some(float) = self.float_shares.filter(|v| *v > 0.0) {
+            ext.float_market_cap.get_or_insert(price * float);
+            ext.turnover_rate
+                .get_or_insert(quote.volume / float * 100.0);
+        }
+        if let Some(eps) = self.eps.filter(|v| *v > 0.0) {
+            ext.pe_ttm.get_or_insert(price / eps);
+        }
+    }
+
+    /// 用另一份资料中已有的字段覆盖当前字段
+    pub fn merge(&mut self, other: CompanyProfile) {
+        self.eps = other.eps.or(self.eps);
+        self.bvps = other.bvps.or(self.bvps);
+        self.roe = other.roe.or(self.roe);
+        self.total_shares = other.total_shares.or(self.total_shares);
+        self.float_shares = other.float_shares.or(self.float_shares);
+        self.industry = other.industry.or(self.industry.take());
+        if !other.sectors.is_empty() {
+            self.sectors = other.sectors;
+        }
+        if !other.dividends.is_empty() {
+            self.dividends = other.dividends;
+        }
+    }
+}
+
+/// 将 HTML 中的表格行拆成单元格文本（去掉标签和空白，`&nbsp;` 视为空格）
+fn html_rows(html: &str) -> Vec<Vec<String>> {
+    let lower = html.to_ascii_lowercase();
+    let mut rows = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = lower[pos..].find("<tr").map(|i| pos + i) {
+        let end = lower[start..]
+            .find("</tr")
+            .map_or(html.len(), |i| start + i);
+        let row = &html[start..end];
+        let row_lower = &lower[start..end];
+        let mut cells = Vec::new();
+        let mut cell_pos = 0;
+        while let Some(open) = row_lower[cell_pos..]
+            .find("<td")
+            .into_iter()
+            .chain(row_lower[cell_pos..].find("<th"))
+            .min()
+            .map(|i| cell_pos + i)
+        {
+            let close = ["</td", "</th"]
+                .iter()
+                .filter_map(|tag| row_lower[open..].find(tag))
+                .min()
+                .map_or(row.len(), |i| open + i);
+            cells.push(strip_tags(&row[open..close]));
+            cell_pos = close.max(open + 1);
+        }
+        rows.push(cells);
+        pos = end.max(start + 1);
+    }
+    rows
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&nbsp;", " ").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_sina_jsvar() {
+        let text = "var lastfive = 1720.05;\nvar totalcapital = 125619.78;\nvar currcapital = 125619.78;\nvar fourQ_mgsy = 68.64;\nvar mgjzc = 205.28;\nvar stock_state = 1;";
+        let p = CompanyProfile::parse_sina_jsvar(text).unwrap();
+        assert_eq!(p.eps, Some(68.64));
+        assert_eq!(p.bvps, Some(205.28));
+        assert!((p.roe.unwrap() - 33.437).abs() < 1e-3);
+        assert_eq!(p.total_shares, Some(1256197800.0));
+        assert!(CompanyProfile::parse_sina_jsvar("<html>").is_err());
+    }
+
+    #[test]
+    fn test_merge_imported_profile() {
+        let mut p = CompanyProfile {
+            eps: Some(68.64),
+            industry: Some("酿酒行业".to_string()),
+            ..CompanyProfile::default()
+        };
+        let imported: CompanyProfile = serde_json::from_str(
+            r#"{"sectors": ["白酒"], "dividends": [{"date": "2025-06-26", "plan": "10派276.24元"}]}"#,
+        )
+        .unwrap();
+        p.merge(imported);
+        assert_eq!(p.eps, Some(68.64));
+        assert_eq!(p.industry.as_deref(), Some("酿酒行业"));
+        assert_eq!(p.sectors, vec!["白酒"]);
+        assert_eq!(p.dividends[0].plan, "10派276.24元");
+    }
+
+    #[test]
+    fn test_parse_sina_corp_info() {
+        let html = r#"<table class="comInfo1">
+<tr><th colspan="2" class="tdr">所属行业板块</th></tr>
+<tr><td class="ct">行业</td><td class="ct">行业版块</td></tr>
+<tr><td class="ct">酿酒行业</td><td class="ct"><a href="x">申万二级</a></td></tr>
+</table><table class="comInfo1">
+<tr><th colspan="2">所属概念板块</th></tr>
+<tr><td class="ct">板块名称</td><td class="ct">同板块个股</td></tr>
+<tr><td class="ct">白酒&nbsp;</td><td><a>查看</a></td></tr>
+<tr><td class="ct">MSCI中国</td><td><a>查看</a></td></tr>
+</table>"#;
+        let mut p = CompanyProfile::default();
+        p.parse_sina_corp_info(html);
+        assert_eq!(p.industry.as_deref(), Some("酿酒行业"));
+        assert_eq!(p.sectors, vec!["白酒", "MSCI中国"]);
+    }
+
+    #[test]
+    fn test_parse_sina_bonus() {
+        let html = r#"<table id="sharebonus_1"><thead><tr><th>公告日期</th><th>送股</th></tr></thead><tbody>
+<tr><td>2024-06-12</td><td>0</td><td>0</td><td>308.76</td><td>实施</td><td>2024-06-19</td><td>2024-06-18</td><td>--</td></tr>
+<tr><td>2025-03-29</td><td>0</td><td>0</td><td>276.24</td><td>预案</td><td>--</td><td>--</td><td>--</td></tr>
+<tr><td>2025-06-19</td><td>0</td><td>0</td><td>276.24</td><td>实施</td><td>2025-06-26</td><td>2025-06-25</td><td>--</td></tr>
+<tr><td>2006-05-17</td><td>0</td><td>10</td><td>0</td><td>实施</td><td>2006-05-25</td><td>2006-05-24</td><td>--</td></tr>
+</tbody></table>"#;
+        let mut p = CompanyProfile::default();
+        p.parse_sina_bonus(html);
+        let plans: Vec<(&str, &str)> = p
+            .dividends
+            .iter()
+            .map(|d| (d.date.as_str(), d.plan.as_str()))
+            .collect();
+        assert_eq!(
+            plans,
+            vec![
+                ("2025-06-26", "10派276.24元"),
+                ("2024-06-19", "10派308.76元"),
+                ("2006-05-25", "10转10"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fill_quote_from_shares() {
+        let p = CompanyProfile {
+            eps: Some(50.0),
+            total_shares: Some(1000.0),
+            float_shares: Some(800.0),
+            ..CompanyProfile::default()
+        };
+        let mut q = StockQuote {
+            current: 1500.0,
+            volume: 8.0,
+            ..StockQuote::default()
+        };
+        q.ext.pe_ttm = Some(25.0);
+        p.fill_quote(&mut q);
+        assert_eq!(q.ext.market_cap, Some(1_500_000.0));
+        assert_eq!(q.ext.float_market_cap, Some(1_200_000.0));
+        assert_eq!(q.ext.turnover_rate, Some(1.0));
+        // 数据源已提供的值保留
+        assert_eq!(q.ext.pe_ttm, Some(25.0));
+    }
+}
@@ -0,0 +1,105 @@
+use anyhow::Result;
+
+use crate::adjust::{self, AdjustFactors};
+use crate::api::{SinaClient, SinaConfig};
+use crate::board::{BoardKind, Sector};
+use crate::http::EndpointStats;
+use crate::models::{IndexQuote, KLineData, StockQuote, TimeFrame};
+use crate::profile::CompanyProfile;
+
+/// 行情数据源
+///
+/// 终端界面、服务器模式和命令行都通过该 trait 取数，便于替换为本地回放或测试用的假数据源。
+pub trait Provider: Send + Sync {
+    /// 获取单只股票实时行情
+    fn quote(&self, symbol: &str) -> Result<StockQuote>;
+
+    /// 获取K线数据，len 为最多返回的根数
+    fn klines(&self, symbol: &str, tf: TimeFrame, len: u32) -> Result<Vec<KLineData>>;
+
+    /// 批量获取实时行情（默认逐只请求）
+    fn quotes(&self, symbols: &[String]) -> Vec<Result<StockQuote>> {
+        symbols.iter().map(|s| self.quote(s)).collect()
+    }
+
+    /// 获取指数行情（默认按普通行情获取）
+    fn index_quote(&self, symbol: &str) -> Result<IndexQuote> {
+        self.quote(symbol).map(IndexQuote::from)
+    }
+
+    /// 获取行业 / 概念板块列表（默认不支持）
+    fn boards(&self, kind: BoardKind) -> Result<Vec<Sector>> {
+        anyhow::bail!("当前数据源不提供{}板块", kind.label())
+    }
+
+    /// 获取行情中心分页列表：板块成分股或 hs_a 等市场节点（默认不支持）
+    fn node_quotes(&self, node: &str, _page: u32, _per_page: u32) -> Result<Vec<StockQuote>> {
+        anyhow::bail!("当前数据源不提供 {} 的行情列表", node)
+    }
+
+    /// 获取A股后复权因子（默认不支持）
+    fn adjust_factors(&self, symbol: &str) -> Result<AdjustFactors> {
+        anyhow::bail!("当前数据源不提供 {} 的复权因子", symbol)
+    }
+
+    /// 获取公司基本面与简介（默认不支持）
+    fn profile(&self, symbol: &str) -> Result<CompanyProfile> {
+        anyhow::bail!("当前数据源不提供 {} 的公司资料", symbol)
+    }
+
+    /// 各接口的请求统计（诊断用，不发起网络请求的数据源返回空）
+    fn stats(&self) -> Vec<EndpointStats> {
+        Vec::new()
+    }
+}
+
+/// 新浪财经数据源
+pub struct SinaProvider {
+    sina: SinaClient,
+}
+
+impl SinaProvider {
+    /// 使用指定的接口设置（代理地址无效时返回错误）
+    pub fn new(sina: SinaConfig) -> Result<Self> {
+        Ok(Self {
+            sina: SinaClient::new(sina)?,
+        })
+    }
+}
+
+impl Provider for SinaProvider {
+    fn quote(&self, symbol: &str) -> Result<StockQuote> {
+        self.sina.fetch_realtime_quote(symbol)
+    }
+
+    fn klines(&self, symbol: &str, tf: TimeFrame, len: u32) -> Result<Vec<KLineData>> {
+        self.sina.fetch_kline_data(symbol, tf.scale(), len)
+    }
+
+    fn index_quote(&self, symbol: &str) -> Result<IndexQuote> {
+        self.sina.fetch_index_quote(symbol)
+    }
+
+    fn boards(&self, kind: BoardKind) -> Result<Vec<Sector>> {
+        self.sina.fetch_boards(kind)
+    }
+
+    fn node_quotes(&self, node: &str, page: u32, per_page: u32) -> Result<Vec<StockQuote>> {
+        self.sina.fetch_node_quotes(node, page, per_page)
+    }
+
+    fn adjust_factors(&self, symbol: &str) -> Result<AdjustFactors> {
+        self.sina.fetch_adjust_factors(symbol)
+    }
+
+    fn profile(&self, symbol: &str) -> Result<CompanyProfile> {
+        if !adjust::supports(symbol) {
+            anyhow::bail!("暂不支持 {} 的公司资料（仅 A股）", symbol);
+        }
+        self.sina.fetch_profile(symbol)
+    }
+
+    fn stats(&self) -> Vec<EndpointStats> {
+        self.sina.stats()
+    }
+}
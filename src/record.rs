@@ -0,0 +1,380 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::adjust::AdjustFactors;
+use crate::api::{self, Page, ProfilePages, SinaClient, SinaConfig};
+use crate::board::{self, BoardKind, Sector};
+use crate::http::EndpointStats;
+use crate::models::{IndexQuote, KLineData, StockQuote, TimeFrame};
+use crate::profile::CompanyProfile;
+use crate::provider::Provider;
+
+/// 会话目录中的录制文件名
+const SESSION_FILE: &str = "session.jsonl";
+
+/// 一条录制的原始响应
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// 录制时间 (%Y-%m-%d %H:%M:%S%.3f)
+    pub time: String,
+    /// 请求的股票代码（板块列表为分类代码，行情中心为节点代码）
+    pub symbol: String,
+    /// K线周期代码（见 [`TimeFrame::code`]），实时行情为 None
+    pub tf: Option<String>,
+    /// 其他请求的类别：`boards` 板块列表、`node:{页}:{每页}` 行情中心、`hfq` 复权因子、
+    /// `jsvar` / `corp` / `bonus` 公司资料；实时行情和K线为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// 请求地址
+    pub url: String,
+    /// 响应正文（已完成 GBK 解码）
+    pub body: String,
+}
+
+/// 读取会话目录中的全部录制
+pub fn load_session(dir: &Path) -> Result<Vec<Recording>> {
+    let path = dir.join(SESSION_FILE);
+    let file =
+        File::open(&path).with_context(|| format!("打开录制文件失败: {}", path.display()))?;
+    let mut recordings = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let rec = serde_json::from_str(&line)
+            .with_context(|| format!("{} 第 {} 行格式错误", path.display(), i + 1))?;
+        recordings.push(rec);
+    }
+    Ok(recordings)
+}
+
+/// 录制新浪原始响应的数据源，每条响应追加到 `{会话目录}/session.jsonl`
+pub struct RecordingProvider {
+    sina: SinaClient,
+    dir: PathBuf,
+    out: Mutex<BufWriter<File>>,
+}
+
+impl RecordingProvider {
+    /// 在 dir 下开始录制（追加到已有会话）
+    pub fn new(sina: SinaConfig, dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir).with_context(|| format!("创建录制目录失败: {}", dir.display()))?;
+        let path = dir.join(SESSION_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("创建录制文件失败: {}", path.display()))?;
+        Ok(Self {
+            sina: SinaClient::new(sina)?,
+            dir: dir.to_path_buf(),
+            out: Mutex::new(BufWriter::new(file)),
+        })
+    }
+
+    /// 会话目录
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn record(&self, symbol: &str, tf: Option<TimeFrame>, url: String, body: &str) -> Result<()> {
+        self.write(Recording {
+            time: String::new(),
+            symbol: symbol.to_string(),
+            tf: tf.map(|tf| tf.code().to_string()),
+            kind: None,
+            url,
+            body: body.to_string(),
+        })
+    }
+
+    fn record_page(&self, symbol: &str, kind: &str, page: &Page) -> Result<()> {
+        self.write(Recording {
+            time: String::new(),
+            symbol: symbol.to_string(),
+            tf: None,
+            kind: Some(kind.to_string()),
+            url: page.url.clone(),
+            body: page.body.clone(),
+        })
+    }
+
+    fn write(&self, rec: Recording) -> Result<()> {
+        let rec = Recording {
+            time: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            ..rec
+        };
+        let mut out = self.out.lock().unwrap_or_else(|e| e.into_inner());
+        serde_json::to_writer(&mut *out, &rec)?;
+        writeln!(out)?;
+        out.flush().context("写入录制文件失败")
+    }
+}
+
+/// 行情中心请求的类别
+fn node_kind(page: u32, per_page: u32) -> String {
+    format!("node:{}:{}", page, per_page)
+}
+
+impl Provider for RecordingProvider {
+    fn quote(&self, symbol: &str) -> Result<StockQuote> {
+        let body = self.sina.fetch_quote_body(symbol)?;
+        self.record(symbol, None, self.sina.config().quote_url(symbol), &body)?;
+        api::parse_realtime_quote(symbol, &body)
+    }
+
+    fn klines(&self, symbol: &str, tf: TimeFrame, len: u32) -> Result<Vec<KLineData>> {
+        let Some(body) = self.sina.fetch_kline_body(symbol, tf.scale(), len)? else {
+            return Ok(Vec::new());
+        };
+        if let Some(url) = self.sina.config().kline_url(symbol, tf.scale(), len) {
+            self.record(symbol, Some(tf), url, &body)?;
+        }
+        api::parse_kline_data(symbol, &body)
+    }
+
+    fn index_quote(&self, symbol: &str) -> Result<IndexQuote> {
+        let body = self.sina.fetch_quote_body(symbol)?;
+        self.record(symbol, None, self.sina.config().quote_url(symbol), &body)?;
+        api::parse_index_quote(symbol, &body)
+    }
+
+    fn boards(&self, kind: BoardKind) -> Result<Vec<Sector>> {
+        let page = self.sina.fetch_boards_body(kind)?;
+        self.record_page(kind.code(), "boards", &page)?;
+        board::parse_sina_boards(&page.body)
+    }
+
+    fn node_quotes(&self, node: &str, page: u32, per_page: u32) -> Result<Vec<StockQuote>> {
+        let body = self.sina.fetch_node_body(node, page, per_page)?;
+        self.record_page(node, &node_kind(page, per_page), &body)?;
+        board::parse_node_quotes(&body.body)
+    }
+
+    fn adjust_factors(&self, symbol: &str) -> Result<AdjustFactors> {
+        let page = self.sina.fetch_factors_body(symbol)?;
+        self.record_page(symbol, "hfq", &page)?;
+        AdjustFactors::parse_sina(&page.body)
+    }
+
+    fn profile(&self, symbol: &str) -> Result<CompanyProfile> {
+        let pages = self.sina.fetch_profile_pages(symbol)?;
+        self.record_page(symbol, "jsvar", &pages.jsvar)?;
+        if let Some(page) = &pages.corp_info {
+            self.record_page(symbol, "corp", page)?;
+        }
+        if let Some(page) = &pages.bonus {
+            self.record_page(symbol, "bonus", page)?;
+        }
+        pages.parse()
+    }
+
+    fn stats(&self) -> Vec<EndpointStats> {
+        self.sina.stats()
+    }
+}
+
+/// 回放队列的键: (代码, 周期代码, 请求类别)
+type ReplayKey = (String, Option<String>, Option<String>);
+
+/// 回放录制会话的数据源
+///
+/// 按 (代码, 周期) 依录制顺序返回响应，同一请求的录制用完后重复最后一条，
+/// 因此可以离线运行整个终端界面。
+pub struct ReplayProvider {
+    queues: Mutex<HashMap<ReplayKey, VecDeque<Recording>>>,
+}
+
+impl ReplayProvider {
+    /// 从会话目录加载
+    pub fn open(dir: &Path) -> Result<Self> {
+        Ok(Self::new(load_session(dir)?))
+    }
+
+    /// 从录制列表创建
+    pub fn new(recordings: Vec<Recording>) -> Self {
+        let mut queues: HashMap<_, VecDeque<Recording>> = HashMap::new();
+        for rec in recordings {
+            queues
+                .entry((rec.symbol.clone(), rec.tf.clone(), rec.kind.clone()))
+                .or_default()
+                .push_back(rec);
+        }
+        Self {
+            queues: Mutex::new(queues),
+        }
+    }
+
+    fn next_body(&self, symbol: &str, tf: Option<TimeFrame>) -> Result<String> {
+        let tf = tf.map(|tf| tf.code().to_string());
+        self.pop((symbol.to_string(), tf.clone(), None))
+            .with_context(|| match &tf {
+                Some(tf) => format!("录制中没有 {} 的 {} K线", symbol, tf),
+                None => format!("录制中没有 {} 的行情", symbol),
+            })
+    }
+
+    fn next_page(&self, symbol: &str, kind: &str) -> Result<String> {
+        self.pop((symbol.to_string(), None, Some(kind.to_string())))
+            .with_context(|| format!("录制中没有 {} 的 {} 响应", symbol, kind))
+    }
+
+    fn pop(&self, key: ReplayKey) -> Option<String> {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        let queue = queues.get_mut(&key)?;
+        let rec = if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        };
+        Some(rec.map(|r| r.body).unwrap_or_default())
+    }
+}
+
+impl Provider for ReplayProvider {
+    fn quote(&self, symbol: &str) -> Result<StockQuote> {
+        let body = self.next_body(symbol, None)?;
+        api::parse_realtime_quote(symbol, &body)
+    }
+
+    fn index_quote(&self, symbol: &str) -> Result<IndexQuote> {
+        let body = self.next_body(symbol, None)?;
+        api::parse_index_quote(symbol, &body)
+    }
+
+    fn klines(&self, symbol: &str, tf: TimeFrame, len: u32) -> Result<Vec<KLineData>> {
+        let body = self.next_body(symbol, Some(tf))?;
+        let mut data = api::parse_kline_data(symbol, &body)?;
+        let skip = data.len().saturating_sub(len as usize);
+        data.drain(..skip);
+        Ok(data)
+    }
+
+    fn boards(&self, kind: BoardKind) -> Result<Vec<Sector>> {
+        board::parse_sina_boards(&self.next_page(kind.code(), "boards")?)
+    }
+
+    fn node_quotes(&self, node: &str, page: u32, per_page: u32) -> Result<Vec<StockQuote>> {
+        board::parse_node_quotes(&self.next_page(node, &node_kind(page, per_page))?)
+    }
+
+    fn adjust_factors(&self, symbol: &str) -> Result<AdjustFactors> {
+        AdjustFactors::parse_sina(&self.next_page(symbol, "hfq")?)
+    }
+
+    fn profile(&self, symbol: &str) -> Result<CompanyProfile> {
+        let page = |kind: &str| {
+            self.next_page(symbol, kind).map(|body| Page {
+                url: String::new(),
+                body,
+            })
+        };
+        ProfilePages {
+            jsvar: page("jsvar")?,
+            corp_info: page("corp").ok(),
+            bonus: page("bonus").ok(),
+        }
+        .parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quote(symbol: &str, price: &str) -> Recording {
+        Recording {
+            time: "2025-02-11 15:00:00.000".to_string(),
+            symbol: symbol.to_string(),
+            tf: None,
+            kind: None,
+            url: SinaConfig::default().quote_url(symbol),
+            body: format!(
+                "var hq_str_{}=\"测试,10.00,10.00,{},{},9.00,0,0,100,1000,{},2025-02-11,15:00:00,00\";",
+                symbol,
+                price,
+                price,
+                ["0"; 20].join(",")
+            ),
+        }
+    }
+
+    #[test]
+    fn test_replay_in_order_then_repeat_last() {
+        let kline = Recording {
+            tf: Some("daily".to_string()),
+            url: SinaConfig::default()
+                .kline_url("sh600519", 240, 2)
+                .unwrap(),
+            body: r#"[{"day":"2025-02-10","open":"1","high":"2","low":"1","close":"2","volume":"10"},{"day":"2025-02-11","open":"2","high":"3","low":"2","close":"3","volume":"20"}]"#.to_string(),
+            ..quote("sh600519", "0")
+        };
+        let replay = ReplayProvider::new(vec![
+            quote("sh600519", "10.50"),
+            kline,
+            quote("sh600519", "11.00"),
+        ]);
+
+        assert_eq!(replay.quote("sh600519").unwrap().current, 10.5);
+        assert_eq!(replay.quote("sh600519").unwrap().current, 11.0);
+        assert_eq!(replay.quote("sh600519").unwrap().current, 11.0);
+        let data = replay.klines("sh600519", TimeFrame::Daily, 1).unwrap();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].day, "2025-02-11");
+        assert!(replay.quote("sz000001").is_err());
+        assert!(replay.klines("sh600519", TimeFrame::Weekly, 10).is_err());
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("stock-tui-record-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let recorder = RecordingProvider::new(SinaConfig::default(), &dir).unwrap();
+        let rec = quote("sh600519", "10.50");
+        recorder
+            .record(&rec.symbol, None, rec.url.clone(), &rec.body)
+            .unwrap();
+        drop(recorder);
+
+        let loaded = load_session(&dir).unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].body, rec.body);
+        assert_eq!(loaded[0].url, "http://hq.sinajs.cn/list=sh600519");
+        let replay = ReplayProvider::open(&dir).unwrap();
+        assert_eq!(replay.quote("sh600519").unwrap().name, "测试");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_and_replay_market_and_profile() {
+        let dir =
+            std::env::temp_dir().join(format!("stock-tui-record-pages-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let recorder = RecordingProvider::new(api::mock::start(api::mock::REFERER), &dir).unwrap();
+        let boards = recorder.boards(BoardKind::Industry).unwrap();
+        let stocks = recorder.node_quotes(&boards[0].code, 1, 40).unwrap();
+        let factors = recorder.adjust_factors("sh600519").unwrap();
+        let profile = recorder.profile("sh600519").unwrap();
+        drop(recorder);
+
+        let replay = ReplayProvider::open(&dir).unwrap();
+        assert_eq!(replay.boards(BoardKind::Industry).unwrap(), boards);
+        assert_eq!(replay.node_quotes(&boards[0].code, 1, 40).unwrap(), stocks);
+        assert_eq!(replay.adjust_factors("sh600519").unwrap(), factors);
+        assert_eq!(replay.profile("sh600519").unwrap(), profile);
+        assert_eq!(profile.industry.as_deref(), Some("酿酒行业"));
+        // 没有录制的请求报错
+        assert!(replay.boards(BoardKind::Concept).is_err());
+        assert!(replay.node_quotes(&boards[0].code, 2, 40).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
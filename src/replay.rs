@@ -0,0 +1,214 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::adjust::AdjustFactors;
+use crate::backtest::bar_quote;
+use crate::limit::LimitState;
+use crate::models::{KLineData, PriceLevel, StockQuote};
+
+/// 历史回放设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayConfig {
+    /// 自动播放时每根K线的间隔（毫秒）
+    pub speed_ms: u64,
+    /// 回放获取的K线数量（开始日期须在此范围内）
+    pub len: u32,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            speed_ms: 1000,
+            len: 1000,
+        }
+    }
+}
+
+/// 历史回放：从开始日期起逐根显示K线，之前的K线作为历史一次显示
+#[derive(Debug, Clone)]
+pub struct Replay {
+    data: Vec<KLineData>,
+    start: usize,
+    position: usize,
+    /// 前复权因子：已显示的K线以当前K线为基准前复权，不引入之后的除权
+    forward: Option<AdjustFactors>,
+}
+
+impl Replay {
+    /// 从第一根日期不早于 `start_date` 的K线开始回放（开始的这根立即显示）
+    pub fn new(data: Vec<KLineData>, start_date: &str) -> Result<Self> {
+        let Some(start) = data.iter().position(|k| k.day.as_str() >= start_date) else {
+            match data.first() {
+                Some(first) => bail!(
+                    "{} 之后没有K线（数据范围 {} ~ {}）",
+                    start_date,
+                    first.day,
+                    data[data.len() - 1].day
+                ),
+                None => bail!("没有K线数据"),
+            }
+        };
+        Ok(Self {
+            data,
+            start,
+            position: start + 1,
+            forward: None,
+        })
+    }
+
+    /// 按前复权回放（`data` 须为不复权K线）
+    pub fn with_forward(mut self, factors: AdjustFactors) -> Self {
+        self.forward = Some(factors);
+        self
+    }
+
+    /// 已显示的K线（指标只用这部分计算）
+    pub fn visible(&self) -> Vec<KLineData> {
+        let visible = &self.data[..self.position];
+        match &self.forward {
+            Some(factors) => factors.forward_as_of(visible, &self.current().day),
+            None => visible.to_vec(),
+        }
+    }
+
+    /// 当前（最后显示的）K线
+    pub fn current(&self) -> &KLineData {
+        &self.data[self.position - 1]
+    }
+
+    /// 前进 n 根，返回实际前进的数量
+    pub fn step(&mut self, n: usize) -> usize {
+        let next = (self.position + n).min(self.data.len());
+        let moved = next - self.position;
+        self.position = next;
+        moved
+    }
+
+    /// 是否已回放到最后一根
+    pub fn finished(&self) -> bool {
+        self.position == self.data.len()
+    }
+
+    /// 进度: (已回放的根数, 回放区间的总根数)
+    pub fn progress(&self) -> (usize, usize) {
+        (self.position - self.start, self.data.len() - self.start)
+    }
+
+    /// 当前K线作为模拟交易的行情：以收盘价为买一、卖一，收于涨停时卖一为空，收于跌停时买一为空
+    ///
+    /// 前复权时当前K线的价格与实际成交价相同，前一根按复权价作昨收，除权日也能正确判断涨跌停
+    pub fn quote(&self, symbol: &str, name: &str) -> StockQuote {
+        let k = self.current();
+        let from = self.position.saturating_sub(2);
+        let bars = match &self.forward {
+            Some(factors) => factors.forward_as_of(&self.data[from..self.position], &k.day),
+            None => self.data[from..self.position].to_vec(),
+        };
+        let mut quote = StockQuote {
+            symbol: symbol.to_string(),
+            name: name.to_string(),
+            ..bar_quote(&bars, bars.len() - 1)
+        };
+        let (date, time) = k.day.split_once(' ').unwrap_or((&k.day, "15:00:00"));
+        quote.date = date.to_string();
+        quote.time = time.to_string();
+        let level = vec![PriceLevel {
+            price: quote.current,
+            volume: quote.volume,
+        }];
+        match quote.limit_state() {
+            Some(LimitState::LimitUp) => quote.bids = level,
+            Some(LimitState::LimitDown) => quote.asks = level,
+            _ => {
+                quote.bids = level.clone();
+                quote.asks = level;
+            }
+        }
+        quote
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(day: &str, close: f64) -> KLineData {
+        KLineData {
+            day: day.to_string(),
+            open: close.to_string(),
+            high: close.to_string(),
+            low: close.to_string(),
+            close: close.to_string(),
+            volume: "1000".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replay_reveals_bars_one_at_a_time() {
+        let data = vec![
+            bar("2025-02-05", 10.0),
+            bar("2025-02-06", 10.2),
+            bar("2025-02-07", 10.1),
+            bar("2025-02-10", 10.5),
+        ];
+        assert!(Replay::new(data.clone(), "2025-03-01").is_err());
+        // 非交易日从下一根开始
+        let mut replay = Replay::new(data, "2025-02-06").unwrap();
+        assert_eq!(replay.visible().len(), 2);
+        assert_eq!(replay.progress(), (1, 3));
+        assert_eq!(replay.step(1), 1);
+        assert_eq!(replay.current().day, "2025-02-07");
+        assert_eq!(replay.step(5), 1);
+        assert!(replay.finished());
+        assert_eq!(replay.visible().len(), 4);
+        assert_eq!(replay.step(1), 0);
+    }
+
+    #[test]
+    fn test_replay_quote_blocks_sealed_limit() {
+        let data = vec![
+            bar("2025-02-10 14:55:00", 10.0),
+            bar("2025-02-10 15:00:00", 11.0),
+        ];
+        let mut replay = Replay::new(data, "2025-02-10 14:55").unwrap();
+        let q = replay.quote("sh600000", "浦发银行");
+        assert_eq!(
+            (q.date.as_str(), q.time.as_str()),
+            ("2025-02-10", "14:55:00")
+        );
+        assert_eq!((q.bids.len(), q.asks.len()), (1, 1));
+        replay.step(1);
+        let q = replay.quote("sh600000", "浦发银行");
+        assert_eq!(q.limit_state(), Some(LimitState::LimitUp));
+        assert!(q.asks.is_empty());
+        assert_eq!(q.bids[0].price, 11.0);
+    }
+
+    #[test]
+    fn test_replay_forward_adjusts_as_of_current_bar() {
+        // 6-19 每 10 股送 10 股，价格从 20 跌到 10
+        let factors = AdjustFactors::new(vec![
+            ("1990-01-01".to_string(), 1.0),
+            ("2024-06-19".to_string(), 2.0),
+        ]);
+        let data = vec![bar("2024-06-18", 20.0), bar("2024-06-19", 10.0)];
+        let mut replay = Replay::new(data, "2024-06-18")
+            .unwrap()
+            .with_forward(factors);
+        // 回放到除权日之前，价格不受之后的除权影响
+        assert_eq!(replay.visible()[0].close_f64(), 20.0);
+        assert_eq!(replay.quote("sh600000", "浦发银行").current, 20.0);
+        replay.step(1);
+        let visible = replay.visible();
+        assert_eq!(
+            (visible[0].close_f64(), visible[1].close_f64()),
+            (10.0, 10.0)
+        );
+        // 除权日以复权后的昨收判断，不误判为跌停
+        let q = replay.quote("sh600000", "浦发银行");
+        assert_eq!(q.current, 10.0);
+        assert_eq!(q.limit_state(), None);
+        assert_eq!((q.bids.len(), q.asks.len()), (1, 1));
+    }
+}
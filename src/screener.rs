@@ -0,0 +1,717 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::breadth;
+use crate::indicators::{calculate_ma, calculate_rsi};
+use crate::models::{KLineData, StockQuote, TimeFrame};
+use crate::provider::Provider;
+
+/// 技术条件使用的日K根数（足够计算 MA60 和 RSI）
+pub const KLINE_LEN: u32 = 120;
+
+/// 需要K线才能判断的候选最多获取的数量（按成交额从大到小）
+const MAX_KLINE_FETCHES: usize = 200;
+
+/// 默认 RSI 周期
+const DEFAULT_RSI_PERIOD: usize = 14;
+
+/// 结果表可排序的字段
+pub const SORT_FIELDS: [Field; 7] = [
+    Field::ChangePercent,
+    Field::Price,
+    Field::Turnover,
+    Field::TurnoverRate,
+    Field::VolumeRatio,
+    Field::Pe,
+    Field::MarketCap,
+];
+
+/// 保存的选股方案（config.json 的 screens 项）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Screen {
+    /// 名称
+    pub name: String,
+    /// 条件表达式，如 `pct > 5 and vr > 2`
+    pub filter: String,
+    /// 选股范围：新浪行情中心节点，如 hs_a（沪深A股）、cyb（创业板）或板块代码 new_blhy
+    #[serde(default = "default_universe")]
+    pub universe: String,
+}
+
+fn default_universe() -> String {
+    breadth::HS_A_NODE.to_string()
+}
+
+impl Screen {
+    /// 创建选股方案（范围为沪深A股）
+    pub fn new(name: &str, filter: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            filter: filter.to_string(),
+            universe: default_universe(),
+        }
+    }
+}
+
+/// 条件中可使用的字段
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    /// 当前价 (price / close)
+    Price,
+    /// 涨跌幅 % (pct)
+    ChangePercent,
+    /// 成交额 (amount)
+    Turnover,
+    /// 换手率 % (turnover_rate / hs)
+    TurnoverRate,
+    /// 量比 (volume_ratio / vr)，行情中没有时由日K估算
+    VolumeRatio,
+    /// 振幅 % (amplitude)
+    Amplitude,
+    /// 市盈率 (pe)
+    Pe,
+    /// 总市值 (cap)
+    MarketCap,
+    /// 流通市值 (float_cap)
+    FloatMarketCap,
+    /// N 日均线 (ma5、ma20 ...)
+    Ma(usize),
+    /// N 日 RSI (rsi 为 14 日，rsi6 ...)
+    Rsi(usize),
+}
+
+impl Field {
+    /// 按名称解析（不区分大小写）
+    pub fn parse(name: &str) -> Option<Field> {
+        let name = name.to_ascii_lowercase();
+        let field = match name.as_str() {
+            "price" | "close" => Field::Price,
+            "pct" | "change_percent" => Field::ChangePercent,
+            "amount" | "turnover" => Field::Turnover,
+            "turnover_rate" | "hs" => Field::TurnoverRate,
+            "volume_ratio" | "vr" => Field::VolumeRatio,
+            "amplitude" => Field::Amplitude,
+            "pe" => Field::Pe,
+            "cap" | "market_cap" => Field::MarketCap,
+            "float_cap" | "float_market_cap" => Field::FloatMarketCap,
+            "rsi" => Field::Rsi(DEFAULT_RSI_PERIOD),
+            _ => {
+                let period = |prefix: &str| -> Option<usize> {
+                    name.strip_prefix(prefix)?.parse().ok().filter(|n| *n > 0)
+                };
+                if let Some(n) = period("ma") {
+                    Field::Ma(n)
+                } else {
+                    Field::Rsi(period("rsi")?)
+                }
+            }
+        };
+        Some(field)
+    }
+
+    /// 显示名称
+    pub fn label(&self) -> String {
+        match self {
+            Field::Price => "当前价".to_string(),
+            Field::ChangePercent => "涨跌幅".to_string(),
+            Field::Turnover => "成交额".to_string(),
+            Field::TurnoverRate => "换手率".to_string(),
+            Field::VolumeRatio => "量比".to_string(),
+            Field::Amplitude => "振幅".to_string(),
+            Field::Pe => "市盈率".to_string(),
+            Field::MarketCap => "总市值".to_string(),
+            Field::FloatMarketCap => "流通市值".to_string(),
+            Field::Ma(n) => format!("MA{}", n),
+            Field::Rsi(n) => format!("RSI{}", n),
+        }
+    }
+
+    /// 取值：外层 None 表示需要K线才能确定，内层 None 表示数据缺失
+    fn value(&self, q: &StockQuote, klines: Option<&[KLineData]>) -> Option<Option<f64>> {
+        let value = match self {
+            Field::Price => Some(q.current),
+            Field::ChangePercent => Some(q.change_percent()),
+            Field::Turnover => Some(q.turnover),
+            Field::TurnoverRate => q.ext.turnover_rate,
+            Field::VolumeRatio => match q.ext.volume_ratio {
+                Some(v) => Some(v),
+                None => estimate_volume_ratio(klines?),
+            },
+            Field::Amplitude => Some(q.amplitude()),
+            Field::Pe => q.ext.pe_ttm,
+            Field::MarketCap => q.ext.market_cap,
+            Field::FloatMarketCap => q.ext.float_market_cap,
+            Field::Ma(n) => calculate_ma(klines?, *n).last().copied().flatten(),
+            Field::Rsi(n) => calculate_rsi(klines?, *n).last().copied().flatten(),
+        };
+        Some(value)
+    }
+
+    /// 计算该字段至少需要的K线根数
+    fn warmup(&self) -> usize {
+        match self {
+            Field::Ma(n) => *n,
+            Field::Rsi(n) => n + 1,
+            Field::VolumeRatio => 6,
+            // 需要前一根的收盘价
+            Field::ChangePercent | Field::Amplitude => 2,
+            _ => 1,
+        }
+    }
+
+    /// 只用行情数据的取值（排序用），缺失或需要K线时为 None
+    pub fn quote_value(&self, q: &StockQuote) -> Option<f64> {
+        self.value(q, None).flatten()
+    }
+}
+
+/// 由日K估算量比：最新一根的成交量 / 之前 5 根的平均成交量
+fn estimate_volume_ratio(klines: &[KLineData]) -> Option<f64> {
+    let (last, before) = klines.split_last()?;
+    let before = &before[before.len().saturating_sub(5)..];
+    let avg = before.iter().map(KLineData::volume_f64).sum::<f64>() / before.len() as f64;
+    (avg > 0.0).then(|| last.volume_f64() / avg)
+}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// >
+    Gt,
+    /// >=
+    Ge,
+    /// <
+    Lt,
+    /// <=
+    Le,
+}
+
+impl Op {
+    fn apply(&self, a: f64, b: f64) -> bool {
+        match self {
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+        }
+    }
+}
+
+/// 比较的一侧：字段或常数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    /// 字段
+    Field(Field),
+    /// 常数
+    Number(f64),
+}
+
+impl Operand {
+    fn value(&self, q: &StockQuote, klines: Option<&[KLineData]>) -> Option<Option<f64>> {
+        match self {
+            Operand::Field(field) => field.value(q, klines),
+            Operand::Number(n) => Some(Some(*n)),
+        }
+    }
+
+    fn warmup(&self) -> usize {
+        match self {
+            Operand::Field(field) => field.warmup(),
+            Operand::Number(_) => 1,
+        }
+    }
+}
+
+/// 选股条件
+///
+/// 文本语法: `pct > 5 and (vr > 2 or hs > 10) and price > ma20 and cap in 100亿..500亿`，
+/// and 优先于 or，数字可带 万 / 亿 后缀。
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// 全部满足 (and)
+    All(Vec<Filter>),
+    /// 任一满足 (or)
+    Any(Vec<Filter>),
+    /// 比较
+    Compare(Operand, Op, Operand),
+    /// 区间（含两端）
+    Between(Operand, f64, f64),
+}
+
+impl Filter {
+    /// 解析条件表达式
+    pub fn parse(text: &str) -> Result<Filter> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.or_expr()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            anyhow::bail!("条件表达式多余的内容: {:?}", token);
+        }
+        Ok(filter)
+    }
+
+    /// 各字段都能算出值所需的最少K线根数（回测时用于跳过指标未就绪的K线）
+    pub fn warmup(&self) -> usize {
+        match self {
+            Filter::All(items) | Filter::Any(items) => {
+                items.iter().map(Filter::warmup).max().unwrap_or(1)
+            }
+            Filter::Compare(a, _, b) => a.warmup().max(b.warmup()),
+            Filter::Between(a, _, _) => a.warmup(),
+        }
+    }
+
+    /// 条件中用到的全部字段
+    pub fn fields(&self) -> Vec<Field> {
+        let operand = |o: &Operand| match o {
+            Operand::Field(field) => Some(*field),
+            Operand::Number(_) => None,
+        };
+        match self {
+            Filter::All(items) | Filter::Any(items) => {
+                items.iter().flat_map(Filter::fields).collect()
+            }
+            Filter::Compare(a, _, b) => operand(a).into_iter().chain(operand(b)).collect(),
+            Filter::Between(a, _, _) => operand(a).into_iter().collect(),
+        }
+    }
+
+    /// 求值：满足为 Some(true)，不满足为 Some(false)，需要K线才能判断时为 None
+    ///
+    /// 传入K线后不会再返回 None；数据缺失（如没有市盈率）视为不满足。
+    pub fn eval(&self, q: &StockQuote, klines: Option<&[KLineData]>) -> Option<bool> {
+        match self {
+            Filter::All(items) => {
+                let mut result = Some(true);
+                for item in items {
+                    match item.eval(q, klines) {
+                        Some(false) => return Some(false),
+                        None => result = None,
+                        Some(true) => {}
+                    }
+                }
+                result
+            }
+            Filter::Any(items) => {
+                let mut result = Some(false);
+                for item in items {
+                    match item.eval(q, klines) {
+                        Some(true) => return Some(true),
+                        None => result = None,
+                        Some(false) => {}
+                    }
+                }
+                result
+            }
+            Filter::Compare(a, op, b) => {
+                let (a, b) = (a.value(q, klines), b.value(q, klines));
+                match (a?, b?) {
+                    (Some(a), Some(b)) => Some(op.apply(a, b)),
+                    _ => Some(false),
+                }
+            }
+            Filter::Between(a, low, high) => {
+                Some(a.value(q, klines)?.is_some_and(|v| *low <= v && v <= *high))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    And,
+    Or,
+    In,
+    Range,
+    Op(Op),
+    Number(f64),
+    Ident(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        let negative = c == '-' && next.is_some_and(|n| n.is_ascii_digit());
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || negative {
+            let start = i;
+            i += 1;
+            // 遇到 ".." 时数字结束（区间写法 100..500）
+            while i < chars.len()
+                && (chars[i].is_ascii_digit()
+                    || (chars[i] == '.' && chars.get(i + 1) != Some(&'.')))
+            {
+                i += 1;
+            }
+            let mut value: f64 = chars[start..i]
+                .iter()
+                .collect::<String>()
+                .parse()
+                .with_context(|| format!("无效的数字: {}", text))?;
+            match chars.get(i) {
+                Some('万') => value *= 1e4,
+                Some('亿') => value *= 1e8,
+                _ => i -= 1,
+            }
+            i += 1;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match word.to_ascii_lowercase().as_str() {
+                "and" => Token::And,
+                "or" => Token::Or,
+                "in" => Token::In,
+                _ => Token::Ident(word),
+            });
+        } else {
+            let (token, len) = match (c, next) {
+                ('(', _) => (Token::LParen, 1),
+                (')', _) => (Token::RParen, 1),
+                ('&', Some('&')) => (Token::And, 2),
+                ('|', Some('|')) => (Token::Or, 2),
+                ('.', Some('.')) => (Token::Range, 2),
+                ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+                ('<', Some('=')) => (Token::Op(Op::Le), 2),
+                ('>', _) => (Token::Op(Op::Gt), 1),
+                ('<', _) => (Token::Op(Op::Lt), 1),
+                _ => anyhow::bail!("条件表达式无法识别的字符: {}", c),
+            };
+            tokens.push(token);
+            i += len;
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let matched = self.tokens.get(self.pos) == Some(token);
+        if matched {
+            self.pos += 1;
+        }
+        matched
+    }
+
+    fn or_expr(&mut self) -> Result<Filter> {
+        let mut items = vec![self.and_expr()?];
+        while self.eat(&Token::Or) {
+            items.push(self.and_expr()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Filter::Any(items)
+        })
+    }
+
+    fn and_expr(&mut self) -> Result<Filter> {
+        let mut items = vec![self.atom()?];
+        while self.eat(&Token::And) {
+            items.push(self.atom()?);
+        }
+        Ok(if items.len() == 1 {
+            items.remove(0)
+        } else {
+            Filter::All(items)
+        })
+    }
+
+    fn atom(&mut self) -> Result<Filter> {
+        if self.eat(&Token::LParen) {
+            let filter = self.or_expr()?;
+            if !self.eat(&Token::RParen) {
+                anyhow::bail!("条件表达式缺少 )");
+            }
+            return Ok(filter);
+        }
+        let left = self.operand()?;
+        match self.next() {
+            Some(Token::Op(op)) => Ok(Filter::Compare(left, op, self.operand()?)),
+            Some(Token::In) => {
+                let low = self.number()?;
+                if !self.eat(&Token::Range) {
+                    anyhow::bail!("区间应写作 下限..上限");
+                }
+                Ok(Filter::Between(left, low, self.number()?))
+            }
+            _ => anyhow::bail!("条件表达式缺少比较运算符 (> >= < <= in)"),
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Operand::Number(n)),
+            Some(Token::Ident(name)) => Field::parse(&name)
+                .map(Operand::Field)
+                .with_context(|| format!("未知字段: {}", name)),
+            _ => anyhow::bail!("条件表达式不完整: 缺少字段或数字"),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(n),
+            _ => anyhow::bail!("区间的上下限应为数字"),
+        }
+    }
+}
+
+/// 选股结果
+#[derive(Debug, Clone, Default)]
+pub struct ScreenResult {
+    /// 满足条件的股票
+    pub matches: Vec<StockQuote>,
+    /// 扫描的股票数（不含停牌）
+    pub scanned: usize,
+    /// 因获取K线数量上限或失败而未判断的股票数
+    pub skipped: usize,
+}
+
+/// 在选股范围内执行筛选
+///
+/// 先用行情列表判断，只有需要技术指标的候选才逐只获取日K。行情列表不含量比，
+/// 获取过日K的股票写入估算的量比，其余结果在获取上限内按成交额补充。
+pub fn run(provider: &dyn Provider, universe: &str, filter: &Filter) -> Result<ScreenResult> {
+    let quotes = breadth::fetch_all(provider, universe)?;
+    let mut result = ScreenResult::default();
+    let mut pending = Vec::new();
+    for q in quotes.into_iter().filter(|q| q.current > 0.0) {
+        result.scanned += 1;
+        match filter.eval(&q, None) {
+            Some(true) => result.matches.push(q),
+            Some(false) => {}
+            None => pending.push(q),
+        }
+    }
+
+    pending.sort_by(|a, b| b.turnover.total_cmp(&a.turnover));
+    result.skipped = pending.len().saturating_sub(MAX_KLINE_FETCHES);
+    let mut fetches = 0;
+    for mut q in pending.into_iter().take(MAX_KLINE_FETCHES) {
+        fetches += 1;
+        match provider.klines(&q.symbol, TimeFrame::Daily, KLINE_LEN) {
+            Ok(klines) => {
+                fill_volume_ratio(&mut q, &klines);
+                if filter.eval(&q, Some(&klines)) == Some(true) {
+                    result.matches.push(q);
+                }
+            }
+            Err(_) => result.skipped += 1,
+        }
+    }
+
+    let mut missing: Vec<usize> = (0..result.matches.len())
+        .filter(|&i| result.matches[i].ext.volume_ratio.is_none())
+        .collect();
+    missing.sort_by(|&a, &b| {
+        let m = &result.matches;
+        m[b].turnover.total_cmp(&m[a].turnover)
+    });
+    for i in missing
+        .into_iter()
+        .take(MAX_KLINE_FETCHES.saturating_sub(fetches))
+    {
+        let q = &mut result.matches[i];
+        if let Ok(klines) = provider.klines(&q.symbol, TimeFrame::Daily, VOLUME_RATIO_LEN) {
+            fill_volume_ratio(q, &klines);
+        }
+    }
+    Ok(result)
+}
+
+/// 估算量比所需的日K根数
+const VOLUME_RATIO_LEN: u32 = 6;
+
+/// 行情未提供量比时用日K估算
+fn fill_volume_ratio(q: &mut StockQuote, klines: &[KLineData]) {
+    if q.ext.volume_ratio.is_none() {
+        q.ext.volume_ratio = estimate_volume_ratio(klines);
+    }
+}
+
+/// 按字段排序，缺失的值排在最后
+pub fn sort(quotes: &mut [StockQuote], field: Field, descending: bool) {
+    quotes.sort_by(|a, b| match (field.quote_value(a), field.quote_value(b)) {
+        (Some(x), Some(y)) if descending => y.total_cmp(&x),
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        (a, b) => b.is_some().cmp(&a.is_some()),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::QuoteExt;
+
+    fn quote(symbol: &str, pre_close: f64, current: f64, market_cap: Option<f64>) -> StockQuote {
+        StockQuote {
+            symbol: symbol.to_string(),
+            pre_close,
+            current,
+            high: current,
+            low: current,
+            turnover: current * 1e6,
+            ext: QuoteExt {
+                market_cap,
+                ..QuoteExt::default()
+            },
+            ..StockQuote::default()
+        }
+    }
+
+    fn klines(closes: &[f64]) -> Vec<KLineData> {
+        closes
+            .iter()
+            .map(|c| KLineData {
+                day: "2025-02-11".to_string(),
+                open: c.to_string(),
+                high: c.to_string(),
+                low: c.to_string(),
+                close: c.to_string(),
+                volume: "100".to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_and_eval() {
+        let f = Filter::parse("pct > 5 and cap in 100亿..500亿 or PCT<=-9.5").unwrap();
+        assert_eq!(
+            f,
+            Filter::Any(vec![
+                Filter::All(vec![
+                    Filter::Compare(
+                        Operand::Field(Field::ChangePercent),
+                        Op::Gt,
+                        Operand::Number(5.0)
+                    ),
+                    Filter::Between(Operand::Field(Field::MarketCap), 1e10, 5e10),
+                ]),
+                Filter::Compare(
+                    Operand::Field(Field::ChangePercent),
+                    Op::Le,
+                    Operand::Number(-9.5)
+                ),
+            ])
+        );
+        assert_eq!(
+            f.eval(&quote("a", 10.0, 10.6, Some(2e10)), None),
+            Some(true)
+        );
+        assert_eq!(
+            f.eval(&quote("b", 10.0, 10.6, Some(6e10)), None),
+            Some(false)
+        );
+        // 缺少市值视为不满足
+        assert_eq!(f.eval(&quote("c", 10.0, 10.6, None), None), Some(false));
+        assert_eq!(f.eval(&quote("d", 10.0, 9.0, None), None), Some(true));
+
+        assert!(Filter::parse("pct >").is_err());
+        assert!(Filter::parse("foo > 1").is_err());
+        assert!(Filter::parse("(pct > 1").is_err());
+        assert!(Filter::parse("pct > 1 pct").is_err());
+    }
+
+    #[test]
+    fn test_eval_with_klines() {
+        let f = Filter::parse("pct > 1 && (price > ma3 || rsi2 < 30)").unwrap();
+        let q = quote("a", 10.0, 10.5, None);
+        assert_eq!(f.eval(&q, None), None);
+        // 价格未涨够时无需K线
+        assert_eq!(f.eval(&quote("b", 10.0, 10.05, None), None), Some(false));
+        assert_eq!(f.eval(&q, Some(&klines(&[9.0, 10.0, 10.5]))), Some(true));
+        assert_eq!(f.eval(&q, Some(&klines(&[12.0, 11.0, 10.5]))), Some(true));
+        assert_eq!(f.eval(&q, Some(&klines(&[9.0, 12.0, 11.0]))), Some(false));
+    }
+
+    #[test]
+    fn test_run_and_sort() {
+        struct Fake;
+        impl Provider for Fake {
+            fn quote(&self, _: &str) -> Result<StockQuote> {
+                anyhow::bail!("unused")
+            }
+            fn klines(&self, symbol: &str, _: TimeFrame, _: u32) -> Result<Vec<KLineData>> {
+                match symbol {
+                    "sh600001" => Ok(klines(&[10.0, 10.0, 11.0])),
+                    "sh600002" => Ok(klines(&[11.0, 12.0, 12.0])),
+                    _ => anyhow::bail!("无数据"),
+                }
+            }
+            fn node_quotes(&self, _: &str, page: u32, _: u32) -> Result<Vec<StockQuote>> {
+                Ok(if page == 1 {
+                    vec![
+                        quote("sh600001", 10.0, 11.0, Some(1e10)),
+                        quote("sh600002", 10.0, 12.0, Some(3e10)),
+                        quote("sh600003", 10.0, 12.0, Some(2e10)),
+                        quote("sh600004", 10.0, 10.0, Some(2e10)),
+                        quote("sh600005", 10.0, 0.0, None),
+                    ]
+                } else {
+                    Vec::new()
+                })
+            }
+        }
+
+        let f = Filter::parse("pct > 5 and price >= ma3").unwrap();
+        let mut result = run(&Fake, "hs_a", &f).unwrap();
+        assert_eq!((result.scanned, result.skipped), (4, 1));
+        let symbols = |r: &ScreenResult| {
+            r.matches
+                .iter()
+                .map(|q| q.symbol.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(symbols(&result), vec!["sh600002", "sh600001"]);
+
+        // 获取过日K的结果带有估算的量比（成交量相同 -> 1）
+        assert!(result
+            .matches
+            .iter()
+            .all(|q| q.ext.volume_ratio == Some(1.0)));
+
+        sort(&mut result.matches, Field::MarketCap, false);
+        assert_eq!(symbols(&result), vec!["sh600001", "sh600002"]);
+        result.matches[0].ext.market_cap = None;
+        sort(&mut result.matches, Field::MarketCap, true);
+        assert_eq!(symbols(&result), vec!["sh600002", "sh600001"]);
+
+        // 只用行情判断的结果也补充量比，获取失败的排在最后
+        let mut result = run(&Fake, "hs_a", &Filter::parse("pct > 5").unwrap()).unwrap();
+        sort(&mut result.matches, Field::VolumeRatio, true);
+        assert_eq!(
+            result
+                .matches
+                .iter()
+                .map(|q| (q.symbol.as_str(), q.ext.volume_ratio))
+                .collect::<Vec<_>>(),
+            vec![
+                ("sh600001", Some(1.0)),
+                ("sh600002", Some(1.0)),
+                ("sh600003", None)
+            ]
+        );
+    }
+}
@@ -0,0 +1,1001 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{IpAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::export::QuoteRow;
+use crate::feed::{self, KlineCache, QuoteEnricher};
+use crate::indicators::calculate_ma;
+use crate::market::{Scheduler, TradingCalendar};
+use crate::models::{normalize_symbol, KLineData, PriceLevel, StockQuote, TimeFrame};
+use crate::provider::Provider;
+
+/// 服务器模式参数
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// 监听地址，如 127.0.0.1:8080
+    pub addr: String,
+    /// 自选股行情轮询间隔
+    pub poll_interval: Duration,
+    /// K线缓存有效期
+    pub kline_ttl: Duration,
+    /// 每个客户端 IP 每秒允许的请求数
+    pub rate_limit: f64,
+    /// 每个客户端 IP 允许的突发请求数
+    pub burst: f64,
+    /// 推送流在行情无变化时发送心跳的间隔
+    pub heartbeat: Duration,
+    /// 交易日历，设置后只轮询开市中的股票；None 表示始终轮询
+    pub calendar: Option<TradingCalendar>,
+    /// 单个推送流最多订阅的股票数
+    pub max_stream_symbols: usize,
+    /// 所有推送流合计最多订阅的自选股以外的股票数
+    pub max_subscriptions: usize,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            addr: "127.0.0.1:8080".to_string(),
+            poll_interval: Duration::from_secs(5),
+            kline_ttl: Duration::from_secs(60),
+            rate_limit: 5.0,
+            burst: 20.0,
+            heartbeat: Duration::from_secs(15),
+            calendar: Some(TradingCalendar::load(None)),
+            max_stream_symbols: 20,
+            max_subscriptions: 200,
+        }
+    }
+}
+
+/// 清理空闲令牌桶的间隔
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 按客户端 IP 的令牌桶限流器
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: HashMap<IpAddr, (f64, Instant)>,
+    last_prune: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// rate 为每秒补充的令牌数，burst 为桶容量
+    pub fn new(rate: f64, burst: f64) -> Self {
+        Self {
+            rate,
+            burst,
+            buckets: HashMap::new(),
+            last_prune: None,
+        }
+    }
+
+    /// 消耗一个令牌，令牌不足时返回 false
+    pub fn allow(&mut self, ip: IpAddr, now: Instant) -> bool {
+        if self
+            .last_prune
+            .is_none_or(|t| now.saturating_duration_since(t) >= PRUNE_INTERVAL)
+        {
+            self.prune(now);
+        }
+        let (tokens, last) = self.buckets.entry(ip).or_insert((self.burst, now));
+        let elapsed = now.saturating_duration_since(*last).as_secs_f64();
+        *tokens = (*tokens + elapsed * self.rate).min(self.burst);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// 移除已补满的令牌桶（与新建的桶等价），避免记录所有访问过的 IP
+    pub fn prune(&mut self, now: Instant) {
+        let (rate, burst) = (self.rate, self.burst);
+        self.buckets.retain(|_, (tokens, last)| {
+            *tokens + now.saturating_duration_since(*last).as_secs_f64() * rate < burst
+        });
+        self.last_prune = Some(now);
+    }
+
+    /// 当前记录的令牌桶数量
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// 是否没有记录任何令牌桶
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+}
+
+/// 最近一次轮询的行情
+#[derive(Default)]
+struct QuoteState {
+    /// 与自选股一一对应的行情
+    quotes: Vec<Option<StockQuote>>,
+    /// 推送流订阅的自选股以外的股票行情
+    streamed: HashMap<String, StockQuote>,
+    errors: Vec<(String, String)>,
+    updated_at: Option<String>,
+}
+
+struct State {
+    provider: Arc<dyn Provider>,
+    /// 配置的自选股，`/api/quotes` 只返回这些股票
+    watchlist: Vec<String>,
+    /// 推送流订阅的股票及订阅数，客户端断开后减少，为 0 时停止轮询
+    subscriptions: Mutex<HashMap<String, usize>>,
+    max_stream_symbols: usize,
+    max_subscriptions: usize,
+    quotes: RwLock<QuoteState>,
+    klines: Mutex<KlineCache>,
+    /// A股行情的换手率、量比、市值等补充数据
+    enricher: Mutex<QuoteEnricher>,
+    limiter: Mutex<RateLimiter>,
+    /// 行情轮询次数，每轮结束后通知推送流
+    version: Mutex<u64>,
+    updated: Condvar,
+    heartbeat: Duration,
+    scheduler: Option<Mutex<Scheduler>>,
+}
+
+impl State {
+    fn refresh_quotes(&self) {
+        let streamed: Vec<String> = self
+            .subscriptions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .keys()
+            .filter(|s| !self.watchlist.contains(s))
+            .cloned()
+            .collect();
+        let symbols: Vec<String> = self.watchlist.iter().chain(&streamed).cloned().collect();
+        let mut due = match &self.scheduler {
+            Some(scheduler) => scheduler
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .due(&symbols, chrono::Utc::now()),
+            None => symbols.clone(),
+        };
+        {
+            // 丢弃已无人订阅的行情；重新订阅的股票即使休市也补充获取一次
+            let mut state = self.quotes.write().unwrap_or_else(|e| e.into_inner());
+            state.streamed.retain(|s, _| streamed.contains(s));
+            for symbol in &streamed {
+                if !state.streamed.contains_key(symbol) && !due.contains(symbol) {
+                    due.push(symbol.clone());
+                }
+            }
+        }
+        if due.is_empty() {
+            return;
+        }
+
+        let mut poll = feed::poll_quotes(self.provider.as_ref(), &due);
+        self.enricher
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .enrich(self.provider.as_ref(), &mut poll.quotes);
+        {
+            let mut state = self.quotes.write().unwrap_or_else(|e| e.into_inner());
+            poll.merge_into(&due, &self.watchlist, &mut state.quotes);
+            for (symbol, quote) in due.iter().zip(&poll.quotes) {
+                if let Some(q) = quote.as_ref().filter(|_| streamed.contains(symbol)) {
+                    state.streamed.insert(symbol.clone(), q.clone());
+                }
+            }
+            state.errors = poll
+                .errors
+                .into_iter()
+                .filter(|(s, _)| self.watchlist.contains(s))
+                .collect();
+            state.updated_at = Some(now_string());
+        }
+        *self.version.lock().unwrap_or_else(|e| e.into_inner()) += 1;
+        self.updated.notify_all();
+    }
+
+    /// 订阅推送流的股票，超出合计上限时返回错误；返回的订阅在销毁时自动退订
+    fn subscribe<'a>(&'a self, symbols: &'a [String]) -> Result<Subscription<'a>, Response> {
+        let mut subs = self.subscriptions.lock().unwrap_or_else(|e| e.into_inner());
+        let added = symbols
+            .iter()
+            .filter(|s| !self.watchlist.contains(s) && !subs.contains_key(*s))
+            .count();
+        let current = subs.keys().filter(|s| !self.watchlist.contains(s)).count();
+        if current + added > self.max_subscriptions {
+            return Err(Response::error(
+                503,
+                format!("推送流订阅的股票已达上限 {}", self.max_subscriptions),
+            ));
+        }
+        for symbol in symbols {
+            *subs.entry(symbol.clone()).or_default() += 1;
+        }
+        Ok(Subscription {
+            state: self,
+            symbols,
+        })
+    }
+
+    fn unsubscribe(&self, symbols: &[String]) {
+        let mut subs = self.subscriptions.lock().unwrap_or_else(|e| e.into_inner());
+        for symbol in symbols {
+            if let Some(count) = subs.get_mut(symbol) {
+                *count -= 1;
+                if *count == 0 {
+                    subs.remove(symbol);
+                }
+            }
+        }
+    }
+
+    /// 等待下一轮行情（最多 timeout），返回最新的轮询次数
+    fn wait_for_update(&self, seen: u64, timeout: Duration) -> u64 {
+        let version = self.version.lock().unwrap_or_else(|e| e.into_inner());
+        let (version, _) = self
+            .updated
+            .wait_timeout_while(version, timeout, |v| *v == seen)
+            .unwrap_or_else(|e| e.into_inner());
+        *version
+    }
+
+    /// 获取K线：只在查询和写入缓存时加锁，网络请求期间不阻塞其他请求
+    fn klines(&self, symbol: &str, tf: TimeFrame, len: u32) -> Result<Vec<KLineData>> {
+        let cached = self
+            .klines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .cached(symbol, tf, len);
+        if let Some(data) = cached {
+            return Ok(data);
+        }
+        let data = self.provider.klines(symbol, tf, len)?;
+        self.klines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(symbol, tf, len, data.clone());
+        Ok(data)
+    }
+
+    fn quote(&self, symbol: &str) -> Option<StockQuote> {
+        let state = self.quotes.read().unwrap_or_else(|e| e.into_inner());
+        state
+            .quotes
+            .iter()
+            .flatten()
+            .find(|q| q.symbol == symbol)
+            .or_else(|| state.streamed.get(symbol))
+            .cloned()
+    }
+}
+
+/// 推送流的订阅，连接结束（包括客户端断开导致写入失败）时退订
+struct Subscription<'a> {
+    state: &'a State,
+    symbols: &'a [String],
+}
+
+impl Drop for Subscription<'_> {
+    fn drop(&mut self) {
+        self.state.unsubscribe(self.symbols);
+    }
+}
+
+fn now_string() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// HTTP/JSON 服务器：后台轮询自选股行情，对外提供行情、K线和指标接口
+///
+/// - `GET /api/quotes` 全部自选股行情
+/// - `GET /api/quotes/{代码}` 单只自选股行情
+/// - `GET /api/klines/{代码}?tf=daily&len=120` K线
+/// - `GET /api/indicators/{代码}?tf=daily&len=120&ma=5,10,20` 均线
+/// - `GET /api/stream?symbols=sh600519,hk00700` Server-Sent Events 推送行情变化
+pub struct Server {
+    state: Arc<State>,
+    poll_interval: Duration,
+}
+
+impl Server {
+    /// 创建服务器，symbols 为需要轮询的股票
+    pub fn new(provider: Arc<dyn Provider>, symbols: Vec<String>, options: &ServerOptions) -> Self {
+        let state = State {
+            provider,
+            watchlist: symbols,
+            subscriptions: Mutex::new(HashMap::new()),
+            max_stream_symbols: options.max_stream_symbols,
+            max_subscriptions: options.max_subscriptions,
+            quotes: RwLock::new(QuoteState::default()),
+            klines: Mutex::new(KlineCache::new(options.kline_ttl)),
+            enricher: Mutex::new(QuoteEnricher::default()),
+            limiter: Mutex::new(RateLimiter::new(options.rate_limit, options.burst)),
+            version: Mutex::new(0),
+            updated: Condvar::new(),
+            heartbeat: options.heartbeat,
+            scheduler: options
+                .calendar
+                .clone()
+                .map(|c| Mutex::new(Scheduler::new(c))),
+        };
+        Self {
+            state: Arc::new(state),
+            poll_interval: options.poll_interval,
+        }
+    }
+
+    /// 启动行情轮询线程并在 listener 上处理请求（阻塞）
+    pub fn run(&self, listener: TcpListener) -> Result<()> {
+        let state = Arc::clone(&self.state);
+        let interval = self.poll_interval;
+        thread::spawn(move || loop {
+            state.refresh_quotes();
+            thread::sleep(interval);
+        });
+
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let state = Arc::clone(&self.state);
+            thread::spawn(move || {
+                let _ = handle_connection(&state, stream);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// 绑定地址并运行服务器（阻塞）
+pub fn serve(
+    provider: Arc<dyn Provider>,
+    symbols: Vec<String>,
+    options: &ServerOptions,
+) -> Result<()> {
+    let listener = TcpListener::bind(&options.addr)
+        .with_context(|| format!("监听地址失败: {}", options.addr))?;
+    Server::new(provider, symbols, options).run(listener)
+}
+
+/// HTTP 响应
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+fn handle_connection(state: &State, stream: TcpStream) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let peer = stream.peer_addr()?.ip();
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 读取并丢弃请求头
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+            break;
+        }
+    }
+
+    let allowed = state
+        .limiter
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .allow(peer, Instant::now());
+    if !allowed {
+        return write_response(stream, &Response::error(429, "请求过于频繁，请稍后再试"));
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let target = parts.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if method == "GET" && path.trim_end_matches('/') == "/api/stream" {
+        return match stream_symbols(&parse_query(query), state.max_stream_symbols) {
+            Ok(symbols) => stream_quotes(state, stream, &symbols),
+            Err(resp) => write_response(stream, &resp),
+        };
+    }
+
+    write_response(stream, &route(state, method, target))
+}
+
+/// 推送流中的行情消息
+#[derive(Serialize)]
+struct QuoteEvent<'a> {
+    symbol: &'a str,
+    name: &'a str,
+    current: f64,
+    change: f64,
+    change_percent: f64,
+    volume: f64,
+    turnover: f64,
+    bids: &'a [PriceLevel],
+    asks: &'a [PriceLevel],
+    date: &'a str,
+    time: &'a str,
+}
+
+impl<'a> From<&'a StockQuote> for QuoteEvent<'a> {
+    fn from(q: &'a StockQuote) -> Self {
+        QuoteEvent {
+            symbol: &q.symbol,
+            name: &q.name,
+            current: q.current,
+            change: q.change(),
+            change_percent: q.change_percent(),
+            volume: q.volume,
+            turnover: q.turnover,
+            bids: &q.bids,
+            asks: &q.asks,
+            date: &q.date,
+            time: &q.time,
+        }
+    }
+}
+
+/// 规范化请求中的股票代码，前缀后只能是字母、数字或 `.`，否则返回 400
+fn parse_symbol(input: &str) -> Result<String, Response> {
+    let symbol =
+        normalize_symbol(input).map_err(|e| Response::error(400, format!("{}: {}", input, e)))?;
+    let code = symbol.strip_prefix("gb_").unwrap_or(&symbol[2..]);
+    if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+        return Err(Response::error(400, format!("{}: 无效的股票代码", input)));
+    }
+    Ok(symbol)
+}
+
+/// 解析 symbols 参数（逗号分隔，去重后最多 max 只）
+fn stream_symbols(query: &HashMap<&str, &str>, max: usize) -> Result<Vec<String>, Response> {
+    let list = query
+        .get("symbols")
+        .filter(|s| !s.is_empty())
+        .ok_or_else(|| Response::error(400, "缺少 symbols 参数"))?;
+    let mut symbols: Vec<String> = Vec::new();
+    for s in list.split(',') {
+        let symbol = parse_symbol(s)?;
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    if symbols.len() > max {
+        return Err(Response::error(
+            400,
+            format!("每个推送流最多订阅 {} 只股票", max),
+        ));
+    }
+    Ok(symbols)
+}
+
+/// Server-Sent Events 推送：行情变化时发送 quote 事件，无变化时按间隔发送 heartbeat 事件
+fn stream_quotes(state: &State, mut stream: TcpStream, symbols: &[String]) -> std::io::Result<()> {
+    let _subscription = match state.subscribe(symbols) {
+        Ok(subscription) => subscription,
+        Err(resp) => return write_response(stream, &resp),
+    };
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream; charset=utf-8\r\nCache-Control: no-cache\r\nConnection: keep-alive\r\n\r\n",
+    )?;
+    stream.flush()?;
+
+    let mut last_sent: HashMap<&str, StockQuote> = HashMap::new();
+    let mut last_write = Instant::now();
+    let mut seen = 0;
+    loop {
+        let mut sent = false;
+        for symbol in symbols {
+            let Some(quote) = state.quote(symbol) else {
+                continue;
+            };
+            if last_sent.get(symbol.as_str()) != Some(&quote) {
+                let data = serde_json::to_string(&QuoteEvent::from(&quote))?;
+                write!(stream, "event: quote\ndata: {}\n\n", data)?;
+                last_sent.insert(symbol, quote);
+                sent = true;
+            }
+        }
+        if sent {
+            stream.flush()?;
+            last_write = Instant::now();
+        }
+
+        // 距上次写出超过心跳间隔时发送心跳，轮询比心跳频繁时也能保持连接活跃
+        if last_write.elapsed() >= state.heartbeat {
+            write!(
+                stream,
+                "event: heartbeat\ndata: {}\n\n",
+                json!({ "time": now_string() })
+            )?;
+            stream.flush()?;
+            last_write = Instant::now();
+        }
+        seen = state.wait_for_update(seen, state.heartbeat.saturating_sub(last_write.elapsed()));
+    }
+}
+
+fn write_response(mut stream: TcpStream, response: &Response) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    let reason = match response.status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        429 => "Too Many Requests",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        reason,
+        body.len()
+    );
+    if response.status == 429 {
+        head.push_str("Retry-After: 1\r\n");
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
+
+fn route(state: &State, method: &str, target: &str) -> Response {
+    if method != "GET" {
+        return Response::error(405, "仅支持 GET 请求");
+    }
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = parse_query(query);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+
+    match segments.as_slice() {
+        [""] | ["health"] => Response::ok(json!({
+            "status": "ok",
+            "http": state.provider.stats(),
+        })),
+        ["api", "quotes"] => quotes_response(state, None),
+        ["api", "quotes", symbol] => quotes_response(state, Some(symbol)),
+        ["api", "klines", symbol] => klines_response(state, symbol, &query),
+        ["api", "indicators", symbol] => indicators_response(state, symbol, &query),
+        _ => Response::error(404, format!("未知路径: {}", path)),
+    }
+}
+
+fn parse_query(query: &str) -> HashMap<&str, &str> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect()
+}
+
+fn quotes_response(state: &State, symbol: Option<&str>) -> Response {
+    let snapshot = state.quotes.read().unwrap_or_else(|e| e.into_inner());
+    let rows: Vec<QuoteRow> = snapshot
+        .quotes
+        .iter()
+        .flatten()
+        .map(QuoteRow::from)
+        .collect();
+
+    match symbol {
+        Some(symbol) => match snapshot
+            .quotes
+            .iter()
+            .flatten()
+            .find(|q| q.symbol == symbol)
+        {
+            Some(q) => Response::ok(json!(QuoteRow::from(q))),
+            None => Response::error(404, format!("{} 不在轮询列表中或暂无行情", symbol)),
+        },
+        None => {
+            let errors: Vec<Value> = snapshot
+                .errors
+                .iter()
+                .map(|(s, e)| json!({ "symbol": s, "error": e }))
+                .collect();
+            Response::ok(json!({
+                "updated_at": snapshot.updated_at,
+                "quotes": rows,
+                "errors": errors,
+            }))
+        }
+    }
+}
+
+/// 解析 tf / len 参数，默认日K、120 根
+fn kline_params(query: &HashMap<&str, &str>) -> Result<(TimeFrame, u32), Response> {
+    let tf = match query.get("tf") {
+        Some(code) => TimeFrame::from_code(code)
+            .ok_or_else(|| Response::error(400, format!("未知周期: {}", code)))?,
+        None => TimeFrame::Daily,
+    };
+    let len = match query.get("len") {
+        Some(len) => len
+            .parse::<u32>()
+            .ok()
+            .filter(|n| (1..=1000).contains(n))
+            .ok_or_else(|| Response::error(400, format!("len 应为 1-1000: {}", len)))?,
+        None => 120,
+    };
+    Ok((tf, len))
+}
+
+fn klines_response(state: &State, symbol: &str, query: &HashMap<&str, &str>) -> Response {
+    let symbol = match parse_symbol(symbol) {
+        Ok(symbol) => symbol,
+        Err(resp) => return resp,
+    };
+    let (tf, len) = match kline_params(query) {
+        Ok(params) => params,
+        Err(resp) => return resp,
+    };
+    match state.klines(&symbol, tf, len) {
+        Ok(data) => Response::ok(json!({ "symbol": symbol, "tf": tf.code(), "klines": data })),
+        Err(e) => Response::error(502, format!("获取K线失败: {}", e)),
+    }
+}
+
+fn indicators_response(state: &State, symbol: &str, query: &HashMap<&str, &str>) -> Response {
+    let symbol = match parse_symbol(symbol) {
+        Ok(symbol) => symbol,
+        Err(resp) => return resp,
+    };
+    let (tf, len) = match kline_params(query) {
+        Ok(params) => params,
+        Err(resp) => return resp,
+    };
+    let windows: Vec<usize> = match query.get("ma") {
+        Some(list) => match list
+            .split(',')
+            .map(|w| w.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(ws) if ws.iter().all(|&w| w > 0) => ws,
+            _ => return Response::error(400, format!("无效的均线参数: {}", list)),
+        },
+        None => vec![5, 10, 20],
+    };
+
+    let data = match state.klines(&symbol, tf, len) {
+        Ok(data) => data,
+        Err(e) => return Response::error(502, format!("获取K线失败: {}", e)),
+    };
+
+    let days: Vec<&str> = data.iter().map(|k| k.day.as_str()).collect();
+    let mut ma = serde_json::Map::new();
+    for w in windows {
+        ma.insert(format!("ma{}", w), json!(calculate_ma(&data, w)));
+    }
+    Response::ok(json!({ "symbol": symbol, "tf": tf.code(), "day": days, "ma": ma }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::Ipv4Addr;
+
+    struct FakeProvider;
+
+    impl Provider for FakeProvider {
+        fn quote(&self, symbol: &str) -> Result<StockQuote> {
+            if symbol == "sh000000" {
+                anyhow::bail!("无效代码");
+            }
+            Ok(StockQuote {
+                name: "测试".to_string(),
+                symbol: symbol.to_string(),
+                open: 10.0,
+                pre_close: 10.0,
+                current: 11.0,
+                high: 11.0,
+                low: 10.0,
+                volume: 1000.0,
+                turnover: 10500.0,
+                date: "2024-01-02".to_string(),
+                time: "15:00:00".to_string(),
+                ..Default::default()
+            })
+        }
+
+        fn klines(&self, _symbol: &str, _tf: TimeFrame, len: u32) -> Result<Vec<KLineData>> {
+            Ok((1..=len)
+                .map(|i| KLineData {
+                    day: format!("2024-01-{:02}", i),
+                    open: "1".to_string(),
+                    high: "1".to_string(),
+                    low: "1".to_string(),
+                    close: i.to_string(),
+                    volume: "100".to_string(),
+                })
+                .collect())
+        }
+    }
+
+    fn get(addr: &str, path: &str) -> (u16, Value) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut raw = String::new();
+        stream.read_to_string(&mut raw).unwrap();
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    fn start(options: ServerOptions) -> String {
+        start_with(Arc::new(FakeProvider), options)
+    }
+
+    fn start_with(provider: Arc<dyn Provider>, options: ServerOptions) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let symbols = vec!["sh600519".to_string(), "sh000000".to_string()];
+        let server = Server::new(provider, symbols, &options);
+        server.state.refresh_quotes();
+        thread::spawn(move || server.run(listener));
+        addr
+    }
+
+    /// 价格由测试控制的假数据源
+    struct TickingProvider {
+        price: Mutex<f64>,
+    }
+
+    impl Provider for TickingProvider {
+        fn quote(&self, symbol: &str) -> Result<StockQuote> {
+            let price = *self.price.lock().unwrap();
+            Ok(StockQuote {
+                name: "测试".to_string(),
+                symbol: symbol.to_string(),
+                pre_close: 10.0,
+                current: price,
+                volume: 1000.0,
+                bids: vec![PriceLevel {
+                    price: price - 0.01,
+                    volume: 100.0,
+                }],
+                asks: vec![PriceLevel {
+                    price: price + 0.01,
+                    volume: 200.0,
+                }],
+                ..Default::default()
+            })
+        }
+
+        fn klines(&self, _symbol: &str, _tf: TimeFrame, _len: u32) -> Result<Vec<KLineData>> {
+            Ok(Vec::new())
+        }
+    }
+
+    /// 读取下一条 SSE 事件，返回 (事件名, 数据)
+    fn next_event(reader: &mut BufReader<TcpStream>) -> (String, Value) {
+        let (mut event, mut data) = (String::new(), Value::Null);
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let line = line.trim_end();
+            if let Some(name) = line.strip_prefix("event: ") {
+                event = name.to_string();
+            } else if let Some(json) = line.strip_prefix("data: ") {
+                data = serde_json::from_str(json).unwrap();
+            } else if line.is_empty() && !event.is_empty() {
+                return (event, data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_pushes_changes_and_heartbeats() {
+        let provider = Arc::new(TickingProvider {
+            price: Mutex::new(11.0),
+        });
+        let addr = start_with(
+            provider.clone(),
+            ServerOptions {
+                poll_interval: Duration::from_millis(20),
+                heartbeat: Duration::from_millis(50),
+                calendar: None,
+                ..ServerOptions::default()
+            },
+        );
+
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        write!(stream, "GET /api/stream?symbols=sh600519 HTTP/1.1\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        assert!(status.starts_with("HTTP/1.1 200"));
+
+        let (event, data) = next_event(&mut reader);
+        assert_eq!(event, "quote");
+        assert_eq!(data["current"], 11.0);
+        assert_eq!(data["change"], 1.0);
+        assert_eq!(data["bids"][0]["volume"], 100.0);
+
+        // 行情不变时只收到心跳
+        let (event, _) = next_event(&mut reader);
+        assert_eq!(event, "heartbeat");
+
+        *provider.price.lock().unwrap() = 12.5;
+        let data = loop {
+            match next_event(&mut reader) {
+                (event, data) if event == "quote" => break data,
+                _ => continue,
+            }
+        };
+        assert_eq!(data["current"], 12.5);
+        assert_eq!(data["asks"][0]["price"], 12.51);
+    }
+
+    #[test]
+    fn test_stream_requires_symbols() {
+        let addr = start(ServerOptions {
+            max_stream_symbols: 1,
+            ..ServerOptions::default()
+        });
+        assert_eq!(get(&addr, "/api/stream").0, 400);
+        assert_eq!(get(&addr, "/api/stream?symbols=600519").0, 400);
+        assert_eq!(get(&addr, "/api/stream?symbols=sh600519,hk00700").0, 400);
+    }
+
+    #[test]
+    fn test_stream_subscriptions_are_counted_and_capped() {
+        let options = ServerOptions {
+            calendar: None,
+            max_subscriptions: 2,
+            ..ServerOptions::default()
+        };
+        let server = Server::new(
+            Arc::new(FakeProvider),
+            vec!["sh600519".to_string()],
+            &options,
+        );
+        let state = &server.state;
+        let first = ["sh600519", "sz000858"].map(String::from);
+        let second = ["sz000858", "hk00700"].map(String::from);
+        let third = ["gb_aapl".to_string()];
+        let a = state.subscribe(&first).unwrap();
+        let b = state.subscribe(&second).unwrap();
+        // 自选股不计入上限
+        assert!(state.subscribe(&third).is_err());
+
+        state.refresh_quotes();
+        assert!(state.quote("hk00700").is_some());
+        // 订阅的股票不出现在自选股行情中
+        assert_eq!(state.quotes.read().unwrap().quotes.len(), 1);
+
+        drop(b);
+        state.refresh_quotes();
+        assert!(state.quote("hk00700").is_none());
+        assert!(state.quote("sz000858").is_some());
+        drop(a);
+        assert!(state.subscriptions.lock().unwrap().is_empty());
+        assert!(state.subscribe(&third).is_ok());
+    }
+
+    #[test]
+    fn test_stream_unsubscribes_on_disconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let options = ServerOptions {
+            poll_interval: Duration::from_millis(20),
+            heartbeat: Duration::from_millis(20),
+            calendar: None,
+            ..ServerOptions::default()
+        };
+        let server = Server::new(Arc::new(FakeProvider), Vec::new(), &options);
+        let state = Arc::clone(&server.state);
+        thread::spawn(move || server.run(listener));
+
+        let mut stream = TcpStream::connect(&addr).unwrap();
+        write!(stream, "GET /api/stream?symbols=hk00700 HTTP/1.1\r\n\r\n").unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut status = String::new();
+        reader.read_line(&mut status).unwrap();
+        while next_event(&mut reader).0 != "quote" {}
+        assert!(state.subscriptions.lock().unwrap().contains_key("hk00700"));
+
+        drop(reader);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !state.subscriptions.lock().unwrap().is_empty() {
+            assert!(Instant::now() < deadline, "断开后未退订");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_endpoints() {
+        let addr = start(ServerOptions::default());
+
+        let (status, body) = get(&addr, "/api/quotes");
+        assert_eq!(status, 200);
+        assert_eq!(body["quotes"][0]["symbol"], "sh600519");
+        assert_eq!(body["quotes"][0]["change"], 1.0);
+        assert_eq!(body["errors"][0]["symbol"], "sh000000");
+
+        let (status, _) = get(&addr, "/api/quotes/sh000000");
+        assert_eq!(status, 404);
+
+        let (status, body) = get(&addr, "/api/klines/sh600519?tf=weekly&len=30");
+        assert_eq!(status, 200);
+        assert_eq!(body["tf"], "weekly");
+        assert_eq!(body["klines"].as_array().unwrap().len(), 30);
+
+        let (status, body) = get(&addr, "/api/indicators/sh600519?len=5&ma=3");
+        assert_eq!(status, 200);
+        assert_eq!(body["ma"]["ma3"][4], 4.0);
+        assert!(body["ma"]["ma3"][1].is_null());
+
+        let (status, _) = get(&addr, "/api/klines/sh600519?tf=2h");
+        assert_eq!(status, 400);
+        let (status, body) = get(&addr, "/api/klines/SH600519?len=5");
+        assert_eq!(status, 200);
+        assert_eq!(body["symbol"], "sh600519");
+        for path in [
+            "/api/klines/600519",
+            "/api/klines/sh",
+            "/api/indicators/sh6005*9",
+        ] {
+            assert_eq!(get(&addr, path).0, 400, "{}", path);
+        }
+        let (status, _) = get(&addr, "/nope");
+        assert_eq!(status, 404);
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let addr = start(ServerOptions {
+            rate_limit: 0.001,
+            burst: 2.0,
+            ..ServerOptions::default()
+        });
+        assert_eq!(get(&addr, "/health").0, 200);
+        assert_eq!(get(&addr, "/health").0, 200);
+        assert_eq!(get(&addr, "/health").0, 429);
+    }
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let mut limiter = RateLimiter::new(1.0, 2.0);
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let t0 = Instant::now();
+        assert!(limiter.allow(ip, t0));
+        assert!(limiter.allow(ip, t0));
+        assert!(!limiter.allow(ip, t0));
+        assert!(limiter.allow(ip, t0 + Duration::from_secs(1)));
+        assert!(!limiter.allow(ip, t0 + Duration::from_secs(1)));
+
+        // 补满后的令牌桶被清理
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        assert!(limiter.allow(other, t0 + Duration::from_secs(2)));
+        assert!(limiter.allow(other, t0 + Duration::from_secs(2)));
+        assert_eq!(limiter.len(), 2);
+        limiter.prune(t0 + Duration::from_secs(3));
+        assert_eq!(limiter.len(), 1);
+        limiter.prune(t0 + Duration::from_secs(10));
+        assert!(limiter.is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::models::{KLineData, TimeFrame};
+
+/// 通达信二进制记录长度（.day / .lc5 均为 32 字节）
+const RECORD_LEN: usize = 32;
+
+/// 通达信数据文件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TdxKind {
+    /// 日线 vipdoc/{sh,sz}/lday/*.day
+    Day,
+    /// 5分钟线 vipdoc/{sh,sz}/fivemin/*.lc5
+    Lc5,
+}
+
+impl TdxKind {
+    /// 根据文件扩展名判断类型
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "day" => Some(TdxKind::Day),
+            "lc5" => Some(TdxKind::Lc5),
+            _ => None,
+        }
+    }
+}
+
+/// 读取通达信数据文件（按扩展名自动识别 .day / .lc5，按文件名中的代码确定价格精度）
+pub fn read_file(path: &Path) -> Result<Vec<KLineData>> {
+    let kind = TdxKind::from_path(path)
+        .with_context(|| format!("不支持的通达信文件类型: {}", path.display()))?;
+    let bytes = fs::read(path).with_context(|| format!("读取文件失败: {}", path.display()))?;
+    let symbol = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let decimals = price_decimals(&symbol);
+    match kind {
+        TdxKind::Day => parse_day(&bytes, decimals),
+        TdxKind::Lc5 => parse_lc5(&bytes, decimals),
+    }
+}
+
+/// 价格小数位数：基金、债券、回购为 3 位（.day 中按 ×1000 存储），股票和指数为 2 位（×100）
+pub fn price_decimals(symbol: &str) -> usize {
+    let (market, code) = (symbol.get(..2), symbol.get(2..).unwrap_or_default());
+    match market {
+        // 上证: 5 基金, 0 国债(000 指数除外), 1 债券, 2 回购
+        Some("sh") if code.starts_with(['1', '2', '5']) => 3,
+        Some("sh") if code.starts_with('0') && !code.starts_with("000") => 3,
+        // 深证: 10-13 债券, 15/16/18 基金
+        Some("sz") if code.starts_with('1') => 3,
+        _ => 2,
+    }
+}
+
+/// 解析 .day 日线文件，decimals 为价格小数位数（见 [`price_decimals`]）
+/// 记录格式 (小端): 日期u32(YYYYMMDD), 开高低收u32(×10^decimals), 成交额f32, 成交量u32(股), 保留u32
+pub fn parse_day(bytes: &[u8], decimals: usize) -> Result<Vec<KLineData>> {
+    check_len(bytes)?;
+    let mut klines = Vec::with_capacity(bytes.len() / RECORD_LEN);
+    for rec in bytes.chunks_exact(RECORD_LEN) {
+        let date = read_u32(rec, 0);
+        let (year, month, day) = (date / 10000, date / 100 % 100, date % 100);
+        if month == 0 || day == 0 {
+            continue;
+        }
+        let scale = 10f64.powi(decimals as i32);
+        let price = |offset| format!("{:.*}", decimals, read_u32(rec, offset) as f64 / scale);
+        klines.push(KLineData {
+            day: format!("{:04}-{:02}-{:02}", year, month, day),
+            open: price(4),
+            high: price(8),
+            low: price(12),
+            close: price(16),
+            volume: read_u32(rec, 24).to_string(),
+        });
+    }
+    Ok(klines)
+}
+
+/// 解析 .lc5 五分钟线文件，decimals 为价格小数位数
+/// 记录格式 (小端): 日期u16, 分钟u16(距0点), 开高低收f32, 成交额f32, 成交量u32(股), 保留u32
+/// 日期编码: year = n / 2048 + 2004, month = n % 2048 / 100, day = n % 2048 % 100
+pub fn parse_lc5(bytes: &[u8], decimals: usize) -> Result<Vec<KLineData>> {
+    check_len(bytes)?;
+    let mut klines = Vec::with_capacity(bytes.len() / RECORD_LEN);
+    for rec in bytes.chunks_exact(RECORD_LEN) {
+        let date = read_u16(rec, 0) as u32;
+        let minutes = read_u16(rec, 2) as u32;
+        let (year, month, day) = (date / 2048 + 2004, date % 2048 / 100, date % 2048 % 100);
+        if month == 0 || day == 0 {
+            continue;
+        }
+        let price = |offset| format!("{:.*}", decimals, read_f32(rec, offset));
+        klines.push(KLineData {
+            day: format!(
+                "{:04}-{:02}-{:02} {:02}:{:02}:00",
+                year,
+                month,
+                day,
+                minutes / 60,
+                minutes % 60
+            ),
+            open: price(4),
+            high: price(8),
+            low: price(12),
+            close: price(16),
+            volume: read_u32(rec, 24).to_string(),
+        });
+    }
+    Ok(klines)
+}
+
+/// 根据股票代码和周期定位通达信本地文件
+/// vipdoc 为通达信安装目录下的 vipdoc 目录，仅支持沪深A股的日线和5分钟线
+pub fn local_path(vipdoc: &Path, symbol: &str, tf: TimeFrame) -> Option<PathBuf> {
+    let market = symbol.get(..2)?;
+    if market != "sh" && market != "sz" {
+        return None;
+    }
+    let (dir, ext) = match tf {
+        TimeFrame::Daily => ("lday", "day"),
+        TimeFrame::Min5 => ("fivemin", "lc5"),
+        _ => return None,
+    };
+    Some(
+        vipdoc
+            .join(market)
+            .join(dir)
+            .join(format!("{}.{}", symbol, ext)),
+    )
+}
+
+/// 从通达信本地目录加载K线（找不到对应文件时返回空列表）
+pub fn load_local(vipdoc: &Path, symbol: &str, tf: TimeFrame) -> Result<Vec<KLineData>> {
+    match local_path(vipdoc, symbol, tf) {
+        Some(path) if path.exists() => read_file(&path),
+        _ => Ok(Vec::new()),
+    }
+}
+
+fn check_len(bytes: &[u8]) -> Result<()> {
+    if !bytes.len().is_multiple_of(RECORD_LEN) {
+        anyhow::bail!(
+            "通达信文件长度异常: {} 字节，不是 {} 的整数倍",
+            bytes.len(),
+            RECORD_LEN
+        );
+    }
+    Ok(())
+}
+
+fn read_u16(rec: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([rec[offset], rec[offset + 1]])
+}
+
+fn read_u32(rec: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        rec[offset],
+        rec[offset + 1],
+        rec[offset + 2],
+        rec[offset + 3],
+    ])
+}
+
+fn read_f32(rec: &[u8], offset: usize) -> f32 {
+    f32::from_bits(read_u32(rec, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day_record(date: u32, ohlc: [u32; 4], amount: f32, volume: u32) -> Vec<u8> {
+        let mut rec = Vec::with_capacity(RECORD_LEN);
+        rec.extend_from_slice(&date.to_le_bytes());
+        for p in ohlc {
+            rec.extend_from_slice(&p.to_le_bytes());
+        }
+        rec.extend_from_slice(&amount.to_le_bytes());
+        rec.extend_from_slice(&volume.to_le_bytes());
+        rec.extend_from_slice(&0u32.to_le_bytes());
+        rec
+    }
+
+    #[test]
+    fn test_parse_day() {
+        let mut bytes = day_record(20240102, [172000, 175000, 171050, 174800], 1.0e9, 2543210);
+        bytes.extend(day_record(
+            20240103,
+            [174800, 176000, 173000, 173500],
+            2.0e9,
+            1000,
+        ));
+        let klines = parse_day(&bytes, 2).unwrap();
+        assert_eq!(klines.len(), 2);
+        assert_eq!(klines[0].day, "2024-01-02");
+        assert_eq!(klines[0].open, "1720.00");
+        assert_eq!(klines[0].low, "1710.50");
+        assert_eq!(klines[0].close_f64(), 1748.0);
+        assert_eq!(klines[0].volume, "2543210");
+        assert_eq!(klines[1].day, "2024-01-03");
+
+        // 基金、债券按 ×1000 存储
+        let fund = parse_day(
+            &day_record(20240102, [3512, 3530, 3498, 3521], 1.0e8, 100),
+            3,
+        )
+        .unwrap();
+        assert_eq!(fund[0].open, "3.512");
+        assert_eq!(fund[0].close_f64(), 3.521);
+    }
+
+    #[test]
+    fn test_price_decimals() {
+        for symbol in [
+            "sh510300", "sh588000", "sh113050", "sh019547", "sz159915", "sz128136",
+        ] {
+            assert_eq!(price_decimals(symbol), 3, "{}", symbol);
+        }
+        for symbol in ["sh600519", "sh000001", "sz000858", "sz300750", "sz399001"] {
+            assert_eq!(price_decimals(symbol), 2, "{}", symbol);
+        }
+    }
+
+    #[test]
+    fn test_parse_lc5() {
+        // 2024-01-02 => (2024 - 2004) * 2048 + 1 * 100 + 2
+        let date: u16 = 20 * 2048 + 102;
+        let minutes: u16 = 9 * 60 + 35;
+        let mut rec = Vec::with_capacity(RECORD_LEN);
+        rec.extend_from_slice(&date.to_le_bytes());
+        rec.extend_from_slice(&minutes.to_le_bytes());
+        for p in [10.5f32, 10.8, 10.4, 10.75, 1.0e6] {
+            rec.extend_from_slice(&p.to_le_bytes());
+        }
+        rec.extend_from_slice(&12300u32.to_le_bytes());
+        rec.extend_from_slice(&0u32.to_le_bytes());
+
+        let klines = parse_lc5(&rec, 2).unwrap();
+        assert_eq!(klines.len(), 1);
+        assert_eq!(klines[0].day, "2024-01-02 09:35:00");
+        assert_eq!(klines[0].high, "10.80");
+        assert_eq!(klines[0].close, "10.75");
+        assert_eq!(klines[0].volume, "12300");
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        assert!(parse_day(&[0u8; 33], 2).is_err());
+    }
+
+    #[test]
+    fn test_local_path() {
+        let vipdoc = Path::new("/tdx/vipdoc");
+        assert_eq!(
+            local_path(vipdoc, "sh600519", TimeFrame::Daily),
+            Some(PathBuf::from("/tdx/vipdoc/sh/lday/sh600519.day"))
+        );
+        assert_eq!(
+            local_path(vipdoc, "sz000858", TimeFrame::Min5),
+            Some(PathBuf::from("/tdx/vipdoc/sz/fivemin/sz000858.lc5"))
+        );
+        assert_eq!(local_path(vipdoc, "hk00700", TimeFrame::Daily), None);
+        assert_eq!(local_path(vipdoc, "sh600519", TimeFrame::Weekly), None);
+    }
+}
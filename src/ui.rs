@@ -5,13 +5,25 @@ use ratatui::{
     text::{Line, Span},
     widgets::{
         canvas::{Canvas, Context as CanvasContext, Line as CanvasLine},
-        Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table,
+        Axis, Bar, BarChart, BarGroup, Block, Borders, Cell, Chart, Clear, Dataset, GraphType,
+        Paragraph, Row, Table,
     },
     Frame,
 };
 
-use crate::app::App;
-use crate::models::*;
+use std::collections::HashMap;
+
+use crate::app::{App, BacktestRun, ChartZoom, GridCell, InputMode, ViewMode};
+use stock_tui::adjust::{Adjust, AdjustFactors};
+use stock_tui::breadth;
+use stock_tui::calculate_ma;
+use stock_tui::compare;
+use stock_tui::limit::{self, LimitState};
+use stock_tui::market::MarketPhase;
+use stock_tui::models::*;
+use stock_tui::paper::{OrderStatus, Side};
+use stock_tui::screener;
+use stock_tui::viewport;
 
 /// 涨的颜色（红色）
 const COLOR_UP: Color = Color::Red;
@@ -32,10 +44,24 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     match app.view_mode {
         ViewMode::Normal => draw_normal_layout(f, app),
         ViewMode::FullscreenChart => draw_fullscreen_chart(f, app),
+        ViewMode::Heatmap => draw_heatmap(f, app),
+        ViewMode::Breadth => draw_breadth(f, app),
+        ViewMode::Screener => draw_screener(f, app),
+        ViewMode::Paper => draw_paper(f, app),
+        ViewMode::Compare => draw_compare(f, app),
+        ViewMode::Grid => draw_grid(f, app),
     }
 
     // 如果在输入模式，绘制输入弹窗（两种视图下都可用）
-    if app.input_mode == InputMode::AddStock {
+    if matches!(
+        app.input_mode,
+        InputMode::AddStock
+            | InputMode::ScreenFilter
+            | InputMode::ScreenName
+            | InputMode::PaperOrder
+            | InputMode::ReplayDate
+            | InputMode::GridSymbol
+    ) {
         draw_input_popup(f, app);
     }
 
@@ -43,26 +69,1094 @@ pub fn draw(f: &mut Frame, app: &mut App) {
     if app.input_mode == InputMode::HelpScreen {
         draw_help_popup(f, app);
     }
+
+    // 网络诊断弹窗
+    if app.input_mode == InputMode::Diagnostics {
+        draw_diagnostics_popup(f, app);
+    }
 }
 
+/// 终端宽度不小于该值时，详情面板放在K线图右侧，否则以弹窗显示
+const DETAIL_SIDE_MIN_WIDTH: u16 = 120;
+/// 详情面板宽度
+const DETAIL_WIDTH: u16 = 38;
+
 /// 正常布局
 fn draw_normal_layout(f: &mut Frame, app: &mut App) {
+    let strip_height = if app.config.indices.is_empty() { 0 } else { 1 };
+    let outer = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(strip_height), Constraint::Min(0)])
+        .split(f.area());
+    if strip_height > 0 {
+        draw_index_strip(f, app, outer[0]);
+    }
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(10),   // K线图
+            Constraint::Min(12),   // 自选股列表（含行情信息）
+            Constraint::Length(1), // 状态栏
+        ])
+        .split(outer[1]);
+
+    if app.show_detail && f.area().width >= DETAIL_SIDE_MIN_WIDTH {
+        let top = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(40), Constraint::Length(DETAIL_WIDTH)])
+            .split(chunks[0]);
+        draw_chart_area(f, app, top[0]);
+        draw_detail_panel(f, app, top[1]);
+    } else {
+        draw_chart_area(f, app, chunks[0]);
+    }
+    draw_watchlist(f, app, chunks[1]);
+    draw_status_bar(f, app, chunks[2]);
+
+    // 终端较窄时以弹窗显示详情
+    if app.show_detail && f.area().width < DETAIL_SIDE_MIN_WIDTH {
+        let area = centered_rect(60, 24, f.area());
+        f.render_widget(Clear, area);
+        draw_detail_panel(f, app, area);
+    }
+}
+
+/// 绘制详情面板：完整行情、基本面、行业板块与近期分红
+fn draw_detail_panel(f: &mut Frame, app: &App, area: Rect) {
+    let kv = |label: &str, value: String, color: Color| {
+        Line::from(vec![
+            Span::styled(
+                format!(" {:<8}", label),
+                Style::default().fg(Color::DarkGray),
+            ),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    };
+    let section = |title: &str| {
+        Line::from(Span::styled(
+            format!(" ── {} ──", title),
+            Style::default()
+                .fg(Color::Cyan)
+                .add_modifier(Modifier::BOLD),
+        ))
+    };
+
+    let mut lines = Vec::new();
+    let title = match app.current_quote() {
+        Some(q) => {
+            let change = q.change();
+            let color = if change > 0.0 {
+                COLOR_UP
+            } else if change < 0.0 {
+                COLOR_DOWN
+            } else {
+                COLOR_FLAT
+            };
+            lines.push(kv(
+                "现价",
+                format!(
+                    "{:.2} {:+.2} ({:+.2}%)",
+                    q.current,
+                    change,
+                    q.change_percent()
+                ),
+                color,
+            ));
+            lines.push(kv("今开", format!("{:.2}", q.open), Color::White));
+            lines.push(kv("最高", format!("{:.2}", q.high), COLOR_UP));
+            lines.push(kv("最低", format!("{:.2}", q.low), COLOR_DOWN));
+            lines.push(kv("昨收", format!("{:.2}", q.pre_close), Color::White));
+            lines.push(kv("成交量", q.volume_display(), Color::White));
+            lines.push(kv("成交额", q.turnover_display(), Color::White));
+            for field in QuoteField::all() {
+                let value = field.display(q);
+                if field != QuoteField::Turnover && value != "--" {
+                    lines.push(kv(field.label(), value, Color::White));
+                }
+            }
+            if let Some(limits) = q.limit_prices() {
+                lines.push(kv("涨停价", format!("{:.2}", limits.up), COLOR_UP));
+                lines.push(kv("跌停价", format!("{:.2}", limits.down), COLOR_DOWN));
+            }
+            format!(" 详情 - {} {} ", q.name, q.symbol)
+        }
+        None => " 详情 ".to_string(),
+    };
+
+    lines.push(section("基本面"));
+    let active = app.watchlist.get(app.active_index);
+    match &app.detail {
+        Some((symbol, Ok(p))) if Some(symbol) == active => {
+            let num = |v: Option<f64>, suffix: &str| {
+                v.map_or("--".to_string(), |v| format!("{:.2}{}", v, suffix))
+            };
+            lines.push(kv("每股收益", num(p.eps, ""), Color::White));
+            lines.push(kv("每股净资产", num(p.bvps, ""), Color::White));
+            lines.push(kv("ROE", num(p.roe, "%"), Color::White));
+            lines.push(kv("总股本", shares_display(p.total_shares), Color::White));
+            lines.push(kv("流通股本", shares_display(p.float_shares), Color::White));
+            lines.push(kv(
+                "行业",
+                p.industry.clone().unwrap_or_else(|| "--".to_string()),
+                Color::Yellow,
+            ));
+            if !p.sectors.is_empty() {
+                lines.push(kv("板块", p.sectors.join(" "), Color::Yellow));
+            }
+            if !p.dividends.is_empty() {
+                lines.push(section("近期分红"));
+                for d in p.dividends.iter().take(3) {
+                    lines.push(kv(&d.date, d.plan.clone(), Color::White));
+                }
+            }
+        }
+        Some((symbol, Err(e))) if Some(symbol) == active => {
+            lines.push(Line::from(Span::styled(
+                format!(" {}", e),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        _ => lines.push(Line::from(Span::styled(
+            " 加载中...",
+            Style::default().fg(Color::DarkGray),
+        ))),
+    }
+
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    f.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(ratatui::widgets::Wrap { trim: false }),
+        area,
+    );
+}
+
+/// 板块热力图：按成交额分配面积、按涨跌幅着色；下钻后显示成分股列表
+fn draw_heatmap(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(f.area());
+
+    if app.board_stocks.is_some() {
+        draw_board_stocks(f, app, chunks[0]);
+    } else {
+        let block = Block::default()
+            .title(format!(
+                " {}板块热力图 (Tab 切换行业/概念, Enter 成分股, Esc 返回) ",
+                app.board_kind.label()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        let inner = block.inner(chunks[0]);
+        f.render_widget(block, chunks[0]);
+
+        // 面积太小的板块无法显示名称，只取成交额靠前的部分
+        let max_tiles = (inner.width as usize * inner.height as usize / 24).max(1);
+        let boards = &app.boards[..app.boards.len().min(max_tiles)];
+        let weights: Vec<f64> = boards.iter().map(|b| b.turnover.max(1.0)).collect();
+        let mut tiles = Vec::new();
+        treemap(&weights, inner, &mut tiles);
+
+        for (i, (board, tile)) in boards.iter().zip(tiles).enumerate() {
+            let mut style = Style::default()
+                .fg(Color::White)
+                .bg(heat_color(board.change_percent));
+            if i == app.board_index {
+                style = style
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            }
+            let text = vec![
+                Line::from(board.name.clone()),
+                Line::from(format!("{:+.2}%", board.change_percent)),
+            ];
+            f.render_widget(Paragraph::new(text).style(style), tile);
+        }
+    }
+
+    // 状态栏显示选中板块的概况
+    let status = match app.boards.get(app.board_index) {
+        Some(b) if app.board_stocks.is_none() => format!(
+            " {} {:+.2}%  成交额 {}  {} 只  领涨 {} {:+.2}%",
+            b.name,
+            b.change_percent,
+            format_amount(b.turnover),
+            b.count,
+            b.leader_name,
+            b.leader_change_percent
+        ),
+        _ => format!(" {}", app.status_message),
+    };
+    f.render_widget(
+        Paragraph::new(status).style(Style::default().fg(Color::DarkGray)),
+        chunks[1],
+    );
+}
+
+/// 市场宽度：涨跌家数概况、涨跌幅分布柱状图和分时涨跌家数曲线
+fn draw_breadth(f: &mut Frame, app: &App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(10),    // K线图
-            Constraint::Min(12),    // 自选股列表（含行情信息）
-            Constraint::Length(1),  // 状态栏
+            Constraint::Length(4),
+            Constraint::Percentage(45),
+            Constraint::Min(0),
+            Constraint::Length(1),
         ])
         .split(f.area());
+    let title_block = |title: &str| {
+        Block::default()
+            .title(title.to_string())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+    };
+
+    let Some(b) = &app.breadth else {
+        f.render_widget(
+            Paragraph::new(" 正在获取沪深A股行情...")
+                .block(title_block(" 市场宽度 (r 刷新, Esc 返回) ")),
+            chunks[0],
+        );
+        draw_status_bar(f, app, chunks[3]);
+        return;
+    };
+
+    // 概况：涨跌平家数、涨跌停、成交额与上一交易日对比
+    let previous = app.breadth_log.previous_turnover(&app.breadth_date);
+    let compare = match previous {
+        Some(prev) if prev > 0.0 => {
+            let diff = b.turnover - prev;
+            let color = if diff >= 0.0 { COLOR_UP } else { COLOR_DOWN };
+            Span::styled(
+                format!(
+                    "  较上一交易日 {}{} ({:+.1}%)",
+                    if diff >= 0.0 { "+" } else { "-" },
+                    format_amount(diff.abs()),
+                    diff / prev * 100.0
+                ),
+                Style::default().fg(color),
+            )
+        }
+        _ => Span::styled("  较上一交易日 --", Style::default().fg(Color::DarkGray)),
+    };
+    let summary = vec![
+        Line::from(vec![
+            Span::styled(
+                format!(" 上涨 {}", b.advancers),
+                Style::default().fg(COLOR_UP),
+            ),
+            Span::styled(
+                format!("  下跌 {}", b.decliners),
+                Style::default().fg(COLOR_DOWN),
+            ),
+            Span::styled(
+                format!("  平盘 {}", b.unchanged),
+                Style::default().fg(COLOR_FLAT),
+            ),
+            Span::styled(
+                format!("    涨停 {}", b.limit_up),
+                Style::default().fg(COLOR_UP).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!("  跌停 {}", b.limit_down),
+                Style::default().fg(COLOR_DOWN).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(vec![
+            Span::raw(format!(" 两市成交额 {}", format_amount(b.turnover))),
+            compare,
+        ]),
+    ];
+    f.render_widget(
+        Paragraph::new(summary).block(title_block(&format!(
+            " 市场宽度 {} (r 刷新, Esc 返回) ",
+            app.breadth_date
+        ))),
+        chunks[0],
+    );
+
+    // 涨跌幅分布（中间为平盘）
+    let middle = b.histogram.len() / 2;
+    let bars: Vec<Bar> = b
+        .histogram
+        .iter()
+        .enumerate()
+        .map(|(i, &(label, count))| {
+            let color = match i.cmp(&middle) {
+                std::cmp::Ordering::Less => COLOR_DOWN,
+                std::cmp::Ordering::Equal => COLOR_FLAT,
+                std::cmp::Ordering::Greater => COLOR_UP,
+            };
+            Bar::default()
+                .value(count as u64)
+                .label(Line::from(label))
+                .style(Style::default().fg(color))
+                .value_style(Style::default().fg(Color::Black).bg(color))
+        })
+        .collect();
+    let area = chunks[1];
+    let n = bars.len() as u16;
+    let bar_width = (area.width.saturating_sub(2 + n) / n).max(3);
+    f.render_widget(
+        BarChart::default()
+            .block(title_block(" 涨跌幅分布 (%) "))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(bar_width)
+            .bar_gap(1),
+        area,
+    );
+
+    // 分时涨跌家数：横轴为交易分钟（9:30 - 15:00，共 240 分钟）
+    let samples = app.breadth_log.samples(&app.breadth_date);
+    let points = |pick: fn(&breadth::BreadthSample) -> u32| -> Vec<(f64, f64)> {
+        samples
+            .iter()
+            .filter_map(|s| Some((breadth::session_minute(&s.time)? as f64, pick(s) as f64)))
+            .collect()
+    };
+    let advancers = points(|s| s.advancers);
+    let decliners = points(|s| s.decliners);
+    let max = advancers
+        .iter()
+        .chain(&decliners)
+        .map(|p| p.1)
+        .fold(0.0, f64::max)
+        .max(10.0)
+        * 1.1;
+    let datasets = vec![
+        Dataset::default()
+            .name("上涨")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(COLOR_UP))
+            .data(&advancers),
+        Dataset::default()
+            .name("下跌")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(COLOR_DOWN))
+            .data(&decliners),
+    ];
+    let axis_style = Style::default().fg(Color::DarkGray);
+    let chart = Chart::new(datasets)
+        .block(title_block(" 分时涨跌家数 "))
+        .x_axis(
+            Axis::default()
+                .style(axis_style)
+                .bounds([0.0, 240.0])
+                .labels(["9:30", "11:30/13:00", "15:00"]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(axis_style)
+                .bounds([0.0, max])
+                .labels([
+                    "0".to_string(),
+                    format!("{:.0}", max / 2.0),
+                    format!("{:.0}", max),
+                ]),
+        );
+    f.render_widget(chart, chunks[2]);
+
+    draw_status_bar(f, app, chunks[3]);
+}
+
+/// 选股结果未配置附加列时显示的列
+const SCREENER_COLUMNS: [QuoteField; 5] = [
+    QuoteField::Turnover,
+    QuoteField::TurnoverRate,
+    QuoteField::VolumeRatio,
+    QuoteField::Pe,
+    QuoteField::MarketCap,
+];
+
+/// 条件选股：方案与条件、结果表（与自选股相同的样式）
+fn draw_screener(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+
+    let name = if app.screen.name.is_empty() {
+        "未保存"
+    } else {
+        &app.screen.name
+    };
+    let sort = screener::SORT_FIELDS[app.screen_sort].label();
+    let (count, skipped) = app
+        .screen_result
+        .as_ref()
+        .map_or((0, 0), |r| (r.matches.len(), r.skipped));
+    let mut info = vec![
+        Span::styled(
+            format!(" 范围 {}", app.screen.universe),
+            Style::default().fg(Color::White),
+        ),
+        Span::styled(
+            format!("  符合 {} 只", count),
+            Style::default().fg(Color::Yellow),
+        ),
+        Span::styled(
+            format!(
+                "  排序 {} {}",
+                sort,
+                if app.screen_ascending { "↑" } else { "↓" }
+            ),
+            Style::default().fg(Color::DarkGray),
+        ),
+    ];
+    if app.screen_pending {
+        info.push(Span::styled(
+            "  扫描中...",
+            Style::default().fg(Color::Cyan),
+        ));
+    }
+    if skipped > 0 {
+        info.push(Span::styled(
+            format!("  {} 只未获取K线", skipped),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    let header = Paragraph::new(vec![
+        Line::from(vec![
+            Span::styled(" 条件 ", Style::default().fg(Color::Cyan)),
+            Span::styled(
+                app.screen.filter.clone(),
+                Style::default()
+                    .fg(Color::White)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ]),
+        Line::from(info),
+    ])
+    .block(
+        Block::default()
+            .title(format!(
+                " 选股: {} (Tab 下一方案, / 编辑条件, w 保存, o/O 排序, a 加入自选, Esc 返回) ",
+                name
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    );
+    f.render_widget(header, chunks[0]);
+
+    let columns = if app.config.columns.is_empty() {
+        SCREENER_COLUMNS.to_vec()
+    } else {
+        app.config.columns.clone()
+    };
+    let rows: Vec<Row> = app
+        .screen_result
+        .iter()
+        .flat_map(|r| &r.matches)
+        .map(|q| quote_row(&q.symbol, Some(q), &columns))
+        .collect();
+    let table = Table::new(rows, quote_widths(&columns))
+        .header(quote_header(&columns))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_symbol("▶ ");
+    f.render_stateful_widget(table, chunks[1], &mut app.screen_state);
 
-    draw_kline_chart(f, app, chunks[0]);
-    draw_watchlist(f, app, chunks[1]);
     draw_status_bar(f, app, chunks[2]);
 }
 
+/// 盈亏的颜色（红涨绿跌）
+fn profit_color(v: f64) -> Color {
+    if v > 0.0 {
+        COLOR_UP
+    } else if v < 0.0 {
+        COLOR_DOWN
+    } else {
+        COLOR_FLAT
+    }
+}
+
+/// 绘制模拟交易：账户摘要、持仓、每日盈亏、委托和成交
+fn draw_paper(f: &mut Frame, app: &mut App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Percentage(45),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    let account = app.active_paper();
+    let block = |title: String| {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan))
+    };
+    let header_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    let equity = account.equity();
+    let total = equity - account.initial_cash;
+    let (date, today) = account
+        .journal
+        .last()
+        .map_or(("", 0.0), |d| (d.date.as_str(), d.pnl));
+    let floating: f64 = account.positions.values().map(|p| p.profit()).sum();
+    let summary = Paragraph::new(vec![
+        Line::from(vec![
+            Span::raw(format!(" 总资产 {:.2}", equity)),
+            Span::raw(format!("  可用资金 {:.2}", account.cash)),
+            Span::raw(format!("  持仓市值 {:.2}", equity - account.cash)),
+        ]),
+        Line::from(vec![
+            Span::styled(
+                format!(
+                    " 累计盈亏 {:+.2} ({:+.2}%)",
+                    total,
+                    total / account.initial_cash * 100.0
+                ),
+                Style::default().fg(profit_color(total)),
+            ),
+            Span::styled(
+                format!("  浮动盈亏 {:+.2}", floating),
+                Style::default().fg(profit_color(floating)),
+            ),
+            Span::styled(
+                format!("  {} 盈亏 {:+.2}", date, today),
+                Style::default().fg(profit_color(today)),
+            ),
+        ]),
+    ])
+    .block(block(format!(
+        " {} (b 买入, s 卖出, c 撤单, ↑↓ 选择委托, Esc 返回) ",
+        if app.replay.is_some() {
+            "回放模拟账户"
+        } else {
+            "模拟交易"
+        }
+    )));
+    f.render_widget(summary, chunks[0]);
+
+    let middle = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .split(chunks[1]);
+    let rows: Vec<Row> = account
+        .positions
+        .iter()
+        .map(|(symbol, p)| {
+            let profit = p.profit();
+            Row::new(vec![
+                Cell::from(symbol.clone()),
+                Cell::from(p.name.clone()),
+                Cell::from(format!("{}", p.shares)),
+                Cell::from(format!("{}", p.sellable(date))),
+                Cell::from(format!("{:.3}", p.cost / p.shares)),
+                Cell::from(format!("{:.2}", p.last_price)),
+                Cell::from(format!("{:.2}", p.value())),
+                Cell::from(format!("{:+.2}", profit))
+                    .style(Style::default().fg(profit_color(profit))),
+            ])
+        })
+        .collect();
+    let positions = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(12),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "代码",
+            "名称",
+            "持仓",
+            "可卖",
+            "成本价",
+            "最新价",
+            "市值",
+            "浮动盈亏",
+        ])
+        .style(header_style),
+    )
+    .block(block(format!(" 持仓 {} 只 ", account.positions.len())));
+    f.render_widget(positions, middle[0]);
+
+    let rows: Vec<Row> = account
+        .journal
+        .iter()
+        .rev()
+        .map(|d| {
+            Row::new(vec![
+                Cell::from(d.date.clone()),
+                Cell::from(format!("{:.2}", d.equity)),
+                Cell::from(format!("{:+.2}", d.pnl))
+                    .style(Style::default().fg(profit_color(d.pnl))),
+            ])
+        })
+        .collect();
+    let journal = Table::new(
+        rows,
+        [
+            Constraint::Length(11),
+            Constraint::Length(13),
+            Constraint::Min(10),
+        ],
+    )
+    .header(Row::new(vec!["日期", "总资产", "当日盈亏"]).style(header_style))
+    .block(block(" 每日盈亏 ".to_string()));
+    f.render_widget(journal, middle[1]);
+
+    let bottom = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+        .split(chunks[2]);
+    let side_color = |side: Side| match side {
+        Side::Buy => COLOR_UP,
+        Side::Sell => COLOR_DOWN,
+    };
+    let rows: Vec<Row> = account
+        .orders
+        .iter()
+        .rev()
+        .map(|o| {
+            let status_color = match o.status {
+                OrderStatus::Open => Color::Yellow,
+                OrderStatus::Filled => Color::White,
+                _ => Color::DarkGray,
+            };
+            Row::new(vec![
+                Cell::from(format!("#{}", o.id)),
+                Cell::from(o.time.get(5..16).unwrap_or(&o.time).to_string()),
+                Cell::from(o.side.label()).style(Style::default().fg(side_color(o.side))),
+                Cell::from(format!("{} {}", o.symbol, o.name)),
+                Cell::from(format!("{}", o.shares)),
+                Cell::from(o.limit.map_or("市价".to_string(), |p| format!("{:.2}", p))),
+                Cell::from(o.status.label().to_string()).style(Style::default().fg(status_color)),
+            ])
+        })
+        .collect();
+    let orders = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Length(12),
+            Constraint::Length(5),
+            Constraint::Min(16),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "编号",
+            "时间",
+            "方向",
+            "股票",
+            "数量",
+            "委托价",
+            "状态",
+        ])
+        .style(header_style),
+    )
+    .block(block(format!(
+        " 委托 ({} 笔未成交) ",
+        account.open_orders().count()
+    )))
+    .highlight_symbol("▶ ");
+
+    let rows: Vec<Row> = account
+        .fills
+        .iter()
+        .rev()
+        .map(|fill| {
+            Row::new(vec![
+                Cell::from(fill.time.get(5..16).unwrap_or(&fill.time).to_string()),
+                Cell::from(fill.side.label()).style(Style::default().fg(side_color(fill.side))),
+                Cell::from(format!("{} {}", fill.symbol, fill.name)),
+                Cell::from(format!("{}", fill.shares)),
+                Cell::from(format!("{:.2}", fill.price)),
+                Cell::from(format!("{:.2}", fill.fee)),
+            ])
+        })
+        .collect();
+    let fills = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(5),
+            Constraint::Min(16),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(7),
+        ],
+    )
+    .header(Row::new(vec!["时间", "方向", "股票", "数量", "成交价", "费用"]).style(header_style))
+    .block(block(format!(" 成交 {} 笔 ", account.fills.len())));
+
+    f.render_widget(fills, bottom[1]);
+    f.render_stateful_widget(orders, bottom[0], &mut app.paper_state);
+    draw_status_bar(f, app, chunks[3]);
+}
+
+/// 走势对比各股票的线条颜色
+const COMPARE_COLORS: [Color; 6] = [
+    Color::Yellow,
+    Color::Cyan,
+    Color::Magenta,
+    Color::LightBlue,
+    Color::White,
+    Color::LightYellow,
+];
+
+/// 绘制走势对比：各股票收盘价相对第一天的涨跌幅
+fn draw_compare(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(4),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .split(f.area());
+    let title = format!(
+        " 走势对比 - {} (列表中按 x 加入/移出, ←→ 游标, 1-7 周期, r 刷新, Esc 返回) ",
+        app.timeframe.label()
+    );
+    let block = Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan));
+    let Some(cmp) = app
+        .comparison
+        .as_ref()
+        .filter(|c| !c.aligned.dates.is_empty())
+    else {
+        f.render_widget(Paragraph::new(" 无K线数据").block(block), chunks[0]);
+        draw_status_bar(f, app, chunks[2]);
+        return;
+    };
+
+    let dates = &cmp.aligned.dates;
+    let last = dates.len() - 1;
+    let at = app.compare_cursor.unwrap_or(last).min(last);
+    // 以第一个共同交易日为基准，没有共同交易日时无法比较
+    let base = cmp.aligned.base_index();
+    let changes: Vec<Vec<Option<f64>>> = cmp
+        .aligned
+        .closes
+        .iter()
+        .map(|c| match base {
+            Some(base) => compare::percent_change(c, base),
+            None => vec![None; c.len()],
+        })
+        .collect();
+
+    // 图例：游标所在日期（默认最后一天）各股票的涨跌幅，当天休市的显示休市
+    let mut legend = vec![Span::styled(
+        format!(" {} ", dates[at]),
+        Style::default().fg(Color::White),
+    )];
+    for (i, ((symbol, name), pct)) in cmp.series.iter().zip(&changes).enumerate() {
+        let color = COMPARE_COLORS[i % COMPARE_COLORS.len()];
+        legend.push(Span::styled(
+            format!("  ■ {} {} ", name, symbol),
+            Style::default().fg(color),
+        ));
+        legend.push(match pct[at] {
+            Some(v) => Span::styled(format!("{:+.2}%", v), Style::default().fg(profit_color(v))),
+            None => Span::styled("休市", Style::default().fg(Color::DarkGray)),
+        });
+    }
+    let header = Paragraph::new(vec![
+        Line::from(legend),
+        Line::from(Span::styled(
+            match base {
+                Some(base) => format!(" 以 {} 为基准", dates[base]),
+                None => " 无共同交易日".to_string(),
+            },
+            Style::default().fg(Color::DarkGray),
+        )),
+    ])
+    .block(block);
+    f.render_widget(header, chunks[0]);
+
+    let points: Vec<Vec<(f64, f64)>> = changes
+        .iter()
+        .map(|pct| {
+            pct.iter()
+                .enumerate()
+                .filter_map(|(i, v)| Some((i as f64, (*v)?)))
+                .collect()
+        })
+        .collect();
+    let (min, max) = points
+        .iter()
+        .flatten()
+        .fold((0.0f64, 0.0f64), |(lo, hi), &(_, v)| (lo.min(v), hi.max(v)));
+    let margin = ((max - min) * 0.05).max(0.5);
+    let (min, max) = (min - margin, max + margin);
+    let cursor = [(at as f64, min), (at as f64, max)];
+
+    let mut datasets: Vec<Dataset> = points
+        .iter()
+        .enumerate()
+        .map(|(i, data)| {
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(COMPARE_COLORS[i % COMPARE_COLORS.len()]))
+                .data(data)
+        })
+        .collect();
+    if app.compare_cursor.is_some() {
+        datasets.push(
+            Dataset::default()
+                .marker(symbols::Marker::Braille)
+                .graph_type(GraphType::Line)
+                .style(Style::default().fg(COLOR_CURSOR))
+                .data(&cursor),
+        );
+    }
+
+    let day = |i: usize| dates[i].get(..10).unwrap_or(&dates[i]).to_string();
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .x_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([0.0, last.max(1) as f64])
+                .labels([day(0), day(last / 2), day(last)]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(Style::default().fg(Color::DarkGray))
+                .bounds([min, max])
+                .labels([
+                    format!("{:+.1}%", min),
+                    format!("{:+.1}%", (min + max) / 2.0),
+                    format!("{:+.1}%", max),
+                ]),
+        );
+    f.render_widget(chart, chunks[1]);
+    draw_status_bar(f, app, chunks[2]);
+}
+
+/// 板块成分股列表
+fn draw_board_stocks(f: &mut Frame, app: &mut App, area: Rect) {
+    let Some((name, stocks)) = &app.board_stocks else {
+        return;
+    };
+    let header = Row::new(
+        [
+            "  代码",
+            "名称",
+            "当前价",
+            "涨跌幅",
+            "成交额",
+            "换手率",
+            "市盈率",
+        ]
+        .map(|h| Cell::from(h).style(Style::default().fg(Color::Yellow))),
+    )
+    .style(Style::default().add_modifier(Modifier::BOLD));
+
+    let rows: Vec<Row> = stocks
+        .iter()
+        .map(|q| {
+            let pct = q.change_percent();
+            let color = if pct > 0.0 {
+                COLOR_UP
+            } else if pct < 0.0 {
+                COLOR_DOWN
+            } else {
+                COLOR_FLAT
+            };
+            Row::new(vec![
+                Cell::from(format!("  {}", q.symbol)).style(Style::default().fg(Color::Cyan)),
+                Cell::from(q.name.clone()),
+                Cell::from(format!("{:>8.2}", q.current)).style(Style::default().fg(color)),
+                Cell::from(format!("{:>+7.2}%", pct)).style(Style::default().fg(color)),
+                Cell::from(format!("{:>10}", q.turnover_display())),
+                Cell::from(format!("{:>8}", QuoteField::TurnoverRate.display(q))),
+                Cell::from(format!("{:>8}", QuoteField::Pe.display(q))),
+            ])
+        })
+        .collect();
+
+    let widths = [
+        Constraint::Length(12),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(10),
+        Constraint::Length(12),
+        Constraint::Length(10),
+        Constraint::Length(10),
+    ];
+    let table = Table::new(rows, widths)
+        .header(header)
+        .block(
+            Block::default()
+                .title(format!(" {} 成分股 (a 加入自选股, Esc 返回) ", name))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_symbol("▶ ");
+    f.render_stateful_widget(table, area, &mut app.board_stock_state);
+}
+
+/// 二分切割的树图布局：按权重把区域分成两半，沿较长的一边切开并递归
+fn treemap(weights: &[f64], area: Rect, out: &mut Vec<Rect>) {
+    if weights.is_empty() {
+        return;
+    }
+    if weights.len() == 1 || area.width < 2 || area.height < 2 {
+        out.extend(std::iter::repeat_n(area, weights.len()));
+        return;
+    }
+    let total: f64 = weights.iter().sum();
+    let mut split = 1;
+    let mut acc = weights[0];
+    while split < weights.len() - 1 && acc < total / 2.0 {
+        acc += weights[split];
+        split += 1;
+    }
+    let ratio = acc / total;
+    // 终端字符高约为宽的两倍
+    let (first, second) = if area.width >= area.height * 2 {
+        let w = ((area.width as f64 * ratio).round() as u16).clamp(1, area.width - 1);
+        (
+            Rect { width: w, ..area },
+            Rect {
+                x: area.x + w,
+                width: area.width - w,
+                ..area
+            },
+        )
+    } else {
+        let h = ((area.height as f64 * ratio).round() as u16).clamp(1, area.height - 1);
+        (
+            Rect { height: h, ..area },
+            Rect {
+                y: area.y + h,
+                height: area.height - h,
+                ..area
+            },
+        )
+    };
+    treemap(&weights[..split], first, out);
+    treemap(&weights[split..], second, out);
+}
+
+/// 热力图颜色：涨跌幅越大颜色越深，±5% 及以上为 COLOR_UP / COLOR_DOWN
+fn heat_color(change_percent: f64) -> Color {
+    const UP: [u8; 4] = [52, 88, 124, 160];
+    const DOWN: [u8; 4] = [22, 28, 34, 40];
+    let level = change_percent.abs().ceil() as usize;
+    if change_percent.abs() < 0.05 {
+        Color::Indexed(238)
+    } else if level > UP.len() {
+        if change_percent > 0.0 {
+            COLOR_UP
+        } else {
+            COLOR_DOWN
+        }
+    } else if change_percent > 0.0 {
+        Color::Indexed(UP[level - 1])
+    } else {
+        Color::Indexed(DOWN[level - 1])
+    }
+}
+
+/// 绘制顶部指数条：名称、点位、涨跌幅和迷你走势图
+fn draw_index_strip(f: &mut Frame, app: &App, area: Rect) {
+    let mut spans = Vec::new();
+    for (symbol, quote) in app.config.indices.iter().zip(&app.indices) {
+        let Some(q) = quote else {
+            spans.push(Span::styled(
+                format!(" {} -- ", symbol),
+                Style::default().fg(Color::DarkGray),
+            ));
+            continue;
+        };
+        let color = if q.change > 0.0 {
+            COLOR_UP
+        } else if q.change < 0.0 {
+            COLOR_DOWN
+        } else {
+            COLOR_FLAT
+        };
+        spans.push(Span::styled(
+            format!(" {} ", q.name),
+            Style::default().fg(Color::White),
+        ));
+        spans.push(Span::styled(
+            format!("{:.2} {:+.2}% ", q.current, q.change_percent),
+            Style::default().fg(color),
+        ));
+        if let Some(history) = app.index_history.get(symbol) {
+            spans.push(Span::styled(
+                sparkline(history.iter().copied(), 8),
+                Style::default().fg(color),
+            ));
+        }
+        spans.push(Span::styled(" │", Style::default().fg(Color::DarkGray)));
+    }
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
+
+/// 用块字符绘制最近 width 个点的迷你走势图
+fn sparkline(values: impl DoubleEndedIterator<Item = f64>, width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let mut recent: Vec<f64> = values.rev().take(width).collect();
+    recent.reverse();
+    let min = recent.iter().copied().fold(f64::MAX, f64::min);
+    let max = recent.iter().copied().fold(f64::MIN, f64::max);
+    recent
+        .iter()
+        .map(|v| {
+            if max > min {
+                BARS[((v - min) / (max - min) * 7.0).round() as usize]
+            } else {
+                BARS[3]
+            }
+        })
+        .collect()
+}
+
+/// 格式化股本（亿股 / 万股）
+fn shares_display(shares: Option<f64>) -> String {
+    match shares {
+        Some(v) if v >= 1_0000_0000.0 => format!("{:.2}亿股", v / 1_0000_0000.0),
+        Some(v) => format!("{:.1}万股", v / 10000.0),
+        None => "--".to_string(),
+    }
+}
+
 /// 全屏K线图布局
-fn draw_fullscreen_chart(f: &mut Frame, app: &App) {
+fn draw_fullscreen_chart(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -71,7 +1165,7 @@ fn draw_fullscreen_chart(f: &mut Frame, app: &App) {
         ])
         .split(f.area());
 
-    draw_kline_chart(f, app, chunks[0]);
+    draw_chart_area(f, app, chunks[0]);
     draw_fullscreen_status(f, app, chunks[1]);
 }
 
@@ -108,33 +1202,267 @@ fn draw_fullscreen_status(f: &mut Frame, app: &App, area: Rect) {
         ));
         spans.push(Span::styled(
             format!(
-                " 高:{:.2} 低:{:.2} 量:{}",
-                quote.high, quote.low, quote.volume_display()
+                " 高:{:.2} 低:{:.2} 量:{} 额:{}",
+                quote.high,
+                quote.low,
+                quote.volume_display(),
+                quote.turnover_display()
             ),
             Style::default().fg(Color::DarkGray),
         ));
+        // 数据源提供的扩展指标
+        let ext: Vec<String> = QuoteField::all()
+            .into_iter()
+            .filter(|f| *f != QuoteField::Turnover)
+            .map(|f| (f, f.display(quote)))
+            .filter(|(_, v)| v != "--")
+            .map(|(f, v)| format!("{}:{}", f.label(), v))
+            .collect();
+        if !ext.is_empty() {
+            spans.push(Span::styled(
+                format!(" {}", ext.join(" ")),
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
     } else {
-        spans.push(Span::styled(" 加载中...", Style::default().fg(Color::DarkGray)));
+        spans.push(Span::styled(
+            " 加载中...",
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+
+    let p = Paragraph::new(Line::from(spans));
+    f.render_widget(p, area);
+}
+
+/// 一张K线图的绘制状态（主图和网格中的每个小图各自一份）
+struct ChartView<'a> {
+    /// 标题（周期、复权、游标、回放等）
+    title: String,
+    /// 股票代码（判断涨跌停板块）
+    symbol: &'a str,
+    /// 股票名称（判断 ST）
+    name: &'a str,
+    /// K线周期
+    timeframe: TimeFrame,
+    /// K线数据
+    data: &'a [KLineData],
+    /// 水平滚动偏移（从右往左）
+    offset: usize,
+    /// 游标位置（可见K线中的索引）
+    cursor: Option<usize>,
+    /// 要标注买卖点的回测
+    backtest: Option<&'a BacktestRun>,
+    /// 不复权数据的复权因子（计算除权日的涨跌停价）
+    ex_rights: Option<&'a AdjustFactors>,
+    /// 缩放级别
+    zoom: ChartZoom,
+    /// 边框颜色
+    border: Color,
+}
+
+impl<'a> ChartView<'a> {
+    /// 当前股票的主K线图
+    fn main(app: &'a App) -> Self {
+        let mut title = format!(" K线图 - {} ", app.timeframe.label());
+        if app.kline_adjust != Adjust::None {
+            title.push_str(&format!("[{}] ", app.kline_adjust.label()));
+        }
+        if app.kline_cursor.is_some() {
+            title.push_str("[游标模式] ");
+        }
+        if let Some(r) = &app.replay {
+            title.push_str(&format!("[回放 {}] ", r.replay.current().day));
+        }
+        if app.chart_zoom != ChartZoom::Normal {
+            title.push_str(&format!("[{}] ", app.chart_zoom.label()));
+        }
+        let symbol = app
+            .watchlist
+            .get(app.active_index)
+            .map_or("", String::as_str);
+        Self {
+            title,
+            symbol,
+            name: app.current_quote().map_or("", |q| q.name.as_str()),
+            timeframe: app.timeframe,
+            data: &app.kline_data,
+            offset: app.kline_offset,
+            cursor: app.kline_cursor,
+            backtest: app.active_backtest(),
+            ex_rights: app.ex_rights(symbol, app.kline_adjust),
+            zoom: app.chart_zoom,
+            border: Color::Cyan,
+        }
+    }
+
+    /// 网格中的一个小图
+    fn cell(app: &'a App, cell: &'a GridCell, focused: bool) -> Self {
+        let quote = app.quote_of(&cell.symbol).or(cell.quote.as_ref());
+        let mut title = format!(" {} ", cell.symbol);
+        if let Some(q) = quote {
+            title.push_str(&format!(
+                "{} {:.2} {:+.2}% ",
+                q.name,
+                q.current,
+                q.change_percent()
+            ));
+        }
+        title.push_str(&format!("{} ", cell.timeframe.short_label()));
+        if cell.applied != Adjust::None {
+            title.push_str(&format!("[{}] ", cell.applied.label()));
+        }
+        Self {
+            title,
+            symbol: &cell.symbol,
+            name: quote.map_or("", |q| q.name.as_str()),
+            timeframe: cell.timeframe,
+            data: &cell.data,
+            offset: cell.offset,
+            cursor: None,
+            backtest: None,
+            ex_rights: app.ex_rights(&cell.symbol, cell.applied),
+            zoom: app.chart_zoom,
+            border: if focused { COLOR_CURSOR } else { Color::Cyan },
+        }
+    }
+}
+
+/// 绘制多图网格，放大时只显示选中的小图
+fn draw_grid(f: &mut Frame, app: &App) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(f.area());
+    draw_status_bar(f, app, chunks[1]);
+
+    if app.grid.is_empty() {
+        let block = Block::default()
+            .title(" 网格 ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan));
+        f.render_widget(
+            Paragraph::new(" 自选股为空，按 e 输入股票代码").block(block),
+            chunks[0],
+        );
+        return;
+    }
+    if app.grid_expanded {
+        let cell = &app.grid[app.grid_focus];
+        draw_kline_chart(f, &ChartView::cell(app, cell, true), chunks[0]);
+        return;
+    }
+
+    let size = app.grid_size as u32;
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, size); app.grid_size])
+        .split(chunks[0]);
+    let cells: Vec<Rect> = rows
+        .iter()
+        .flat_map(|row| {
+            Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, size); app.grid_size])
+                .split(*row)
+                .to_vec()
+        })
+        .collect();
+    for (i, (cell, area)) in app.grid.iter().zip(cells).enumerate() {
+        draw_kline_chart(f, &ChartView::cell(app, cell, i == app.grid_focus), area);
     }
+}
 
-    let p = Paragraph::new(Line::from(spans));
-    f.render_widget(p, area);
+/// 绘制K线图区域，有回测时在下方显示权益曲线
+fn draw_chart_area(f: &mut Frame, app: &mut App, area: Rect) {
+    // 记录实际宽度（详情面板占去右侧时更窄），游标和缩放按它计算可见K线数量
+    app.chart_width = area.width as usize;
+    let chart = ChartView::main(app);
+    let Some(run) = chart.backtest else {
+        draw_kline_chart(f, &chart, area);
+        return;
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(70), Constraint::Percentage(30)])
+        .split(area);
+    draw_kline_chart(f, &chart, chunks[0]);
+    draw_equity_curve(f, run, chunks[1]);
 }
 
-/// 绘制K线蜡烛图（带游标支持 + 坐标轴 + 均线）
-fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
-    let title = if app.kline_cursor.is_some() {
-        format!(" K线图 - {} [游标模式] ", app.timeframe.label())
+/// 绘制回测权益曲线，标题显示统计
+fn draw_equity_curve(f: &mut Frame, run: &BacktestRun, area: Rect) {
+    let stats = &run.result.stats;
+    let pct = |v: Option<f64>| v.map_or("--".to_string(), |v| format!("{:+.2}%", v));
+    let mut title = format!(
+        " 回测 收益 {} 年化 {} 最大回撤 {:.2}% 胜率 {} 夏普 {} 交易 {} 笔 ",
+        pct(Some(stats.total_return)),
+        pct(stats.cagr),
+        stats.max_drawdown,
+        stats
+            .win_rate
+            .map_or("--".to_string(), |v| format!("{:.0}%", v)),
+        stats
+            .sharpe
+            .map_or("--".to_string(), |v| format!("{:.2}", v)),
+        stats.trades
+    );
+    if stats.blocked_buys > 0 {
+        title.push_str(&format!("涨停未买入 {} 次 ", stats.blocked_buys));
+    }
+    let color = if stats.total_return >= 0.0 {
+        COLOR_UP
     } else {
-        format!(" K线图 - {} ", app.timeframe.label())
+        COLOR_DOWN
     };
 
+    let equity = &run.result.equity;
+    let points: Vec<(f64, f64)> = equity
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| (i as f64, v))
+        .collect();
+    let (min, max) = equity
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), &v| (lo.min(v), hi.max(v)));
+    let margin = ((max - min) * 0.05).max(1.0);
+    let day = |i: usize| run.days.get(i).map_or("", |d| d.get(..10).unwrap_or(d));
+    let last = equity.len().saturating_sub(1);
+
+    let chart = Chart::new(vec![Dataset::default()
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(color))
+        .data(&points)])
+    .block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Cyan)),
+    )
+    .x_axis(
+        Axis::default()
+            .style(Style::default().fg(Color::DarkGray))
+            .bounds([0.0, last.max(1) as f64])
+            .labels([day(0), day(last / 2), day(last)]),
+    )
+    .y_axis(
+        Axis::default()
+            .style(Style::default().fg(Color::DarkGray))
+            .bounds([min - margin, max + margin])
+            .labels([format_amount(min), format_amount(max)]),
+    );
+    f.render_widget(chart, area);
+}
+
+/// 绘制K线蜡烛图（带游标支持 + 坐标轴 + 均线）
+fn draw_kline_chart(f: &mut Frame, chart: &ChartView, area: Rect) {
     let outer_block = Block::default()
-        .title(title)
+        .title(chart.title.as_str())
         .borders(Borders::ALL)
-        .border_style(Style::default().fg(Color::Cyan));
+        .border_style(Style::default().fg(chart.border));
 
-    if app.kline_data.is_empty() {
+    if chart.data.is_empty() {
         let paragraph = Paragraph::new(" 无K线数据")
             .block(outer_block)
             .style(Style::default().fg(Color::DarkGray));
@@ -184,22 +1512,22 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
     };
 
     // 计算均线数据 (全局计算)
-    let ma5 = calculate_ma(&app.kline_data, 5);
-    let ma10 = calculate_ma(&app.kline_data, 10);
-    let ma20 = calculate_ma(&app.kline_data, 20);
-
-    // 计算可显示的K线数量（每根蜡烛占3列宽度）
-    let candle_width = 3usize;
-    let visible_count = (chart_width as usize / candle_width).min(app.kline_data.len());
+    let ma5 = calculate_ma(chart.data, 5);
+    let ma10 = calculate_ma(chart.data, 10);
+    let ma20 = calculate_ma(chart.data, 20);
+
+    // 计算可显示的K线数量（每根K线占的列数由缩放级别决定）
+    let candle_width = chart.zoom.candle_width();
+    let zoom = chart.zoom;
+    // 第 i 根K线中心的横坐标
+    let center = move |i: usize| (i * candle_width + candle_width / 2) as f64;
+    let visible_count =
+        viewport::visible_count(chart_width as usize, candle_width, chart.data.len());
 
     // 根据偏移量截取可见的K线数据
-    let start_idx = if app.kline_data.len() > visible_count + app.kline_offset {
-        app.kline_data.len() - visible_count - app.kline_offset
-    } else {
-        0
-    };
-    let end_idx = (start_idx + visible_count).min(app.kline_data.len());
-    let visible_data = &app.kline_data[start_idx..end_idx];
+    let start_idx = viewport::start_index(chart.data.len(), visible_count, chart.offset);
+    let end_idx = (start_idx + visible_count).min(chart.data.len());
+    let visible_data = &chart.data[start_idx..end_idx];
 
     if visible_data.is_empty() {
         return;
@@ -235,11 +1563,11 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
     let final_range = max_price - min_price;
 
     // 计算网格线的价格级别
-    let num_grid_lines = (chart_height as usize).min(6).max(2);
+    let num_grid_lines = (chart_height as usize).clamp(2, 6);
     let grid_step = (chart_height as usize) / num_grid_lines.max(1);
     let mut grid_prices: Vec<f64> = Vec::new();
     for i in 0..chart_height {
-        if grid_step == 0 || (i as usize) % grid_step == 0 || i == chart_height - 1 {
+        if grid_step == 0 || (i as usize).is_multiple_of(grid_step) || i == chart_height - 1 {
             let ratio = 1.0 - (i as f64 / (chart_height.saturating_sub(1).max(1)) as f64);
             grid_prices.push(min_price + final_range * ratio);
         }
@@ -247,7 +1575,7 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
 
     // ── 绘制K线蜡烛图 + 网格线 + 均线 ──
     let canvas_w = (visible_data.len() * candle_width) as f64;
-    let cursor_pos = app.kline_cursor;
+    let cursor_pos = chart.cursor;
     let grid_prices_clone = grid_prices.clone();
 
     // Clone MA data for closure (efficient enough for TUI)
@@ -259,6 +1587,23 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
     let ma10_clone = ma10.clone();
     let ma20_clone = ma20.clone();
 
+    // 日K线标记收于涨跌停的K线
+    let limit_marks = match chart.timeframe {
+        TimeFrame::Daily => {
+            limit::closed_at_limit(chart.symbol, chart.name, chart.data, chart.ex_rights)
+        }
+        _ => vec![None; chart.data.len()],
+    };
+
+    // 回测的买卖点（按日期对应到当前K线），同一根K线可能既有卖出又有买入: (买, 卖)
+    let mut trade_marks: HashMap<String, (bool, bool)> = HashMap::new();
+    for t in chart.backtest.iter().flat_map(|b| &b.result.trades) {
+        trade_marks.entry(t.buy_day.clone()).or_default().0 = true;
+        if let Some(day) = &t.sell_day {
+            trade_marks.entry(day.clone()).or_default().1 = true;
+        }
+    }
+
     let canvas = Canvas::default()
         .x_bounds([0.0, canvas_w])
         .y_bounds([min_price, max_price])
@@ -283,8 +1628,8 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
             // 绘制均线 (Line chart)
             // Draw lines between adjacent points
             for i in 1..visible_data.len() {
-                let x_prev = ((i - 1) * candle_width) as f64 + 1.0;
-                let x_curr = (i * candle_width) as f64 + 1.0;
+                let x_prev = center(i - 1);
+                let x_curr = center(i);
                 let global_prev = start_idx + i - 1;
                 let global_curr = start_idx + i;
 
@@ -316,8 +1661,34 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
                 1.0
             };
 
+            // 折线模式：收盘价连线，游标处画圆点
+            if zoom == ChartZoom::Line {
+                for i in 1..visible_data.len() {
+                    let (prev, curr) =
+                        (visible_data[i - 1].close_f64(), visible_data[i].close_f64());
+                    let color = if curr >= prev { COLOR_UP } else { COLOR_DOWN };
+                    ctx.draw(&CanvasLine::new(
+                        center(i - 1),
+                        prev,
+                        center(i),
+                        curr,
+                        color,
+                    ));
+                }
+                if let Some(kline) = cursor_pos.and_then(|c| visible_data.get(c)) {
+                    ctx.print(
+                        center(cursor_pos.unwrap_or_default()),
+                        kline.close_f64(),
+                        ratatui::text::Line::from(Span::styled(
+                            "●",
+                            Style::default().fg(COLOR_CURSOR),
+                        )),
+                    );
+                }
+            }
+
             for (i, kline) in visible_data.iter().enumerate() {
-                let x = (i * candle_width) as f64 + 1.0;
+                let x = center(i);
                 let open = kline.open_f64();
                 let close = kline.close_f64();
                 let high = kline.high_f64();
@@ -327,9 +1698,66 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
                 let base_color = if close >= open { COLOR_UP } else { COLOR_DOWN };
                 let color = if is_cursor { COLOR_CURSOR } else { base_color };
 
+                // 涨停在最高价上方标 ▲，跌停在最低价下方标 ▼
+                match limit_marks.get(start_idx + i).copied().flatten() {
+                    Some(true) => ctx.print(
+                        x,
+                        (high + row_step).min(max_price),
+                        ratatui::text::Line::from(Span::styled("▲", Style::default().fg(COLOR_UP))),
+                    ),
+                    Some(false) => ctx.print(
+                        x,
+                        (low - row_step).max(min_price),
+                        ratatui::text::Line::from(Span::styled(
+                            "▼",
+                            Style::default().fg(COLOR_DOWN),
+                        )),
+                    ),
+                    None => {}
+                }
+
+                // 回测买点在最低价下方标 B，卖点在最高价上方标 S
+                let (bought, sold) = trade_marks.get(&kline.day).copied().unwrap_or_default();
+                if bought {
+                    ctx.print(
+                        x,
+                        (low - row_step * 2.0).max(min_price),
+                        ratatui::text::Line::from(Span::styled(
+                            "B",
+                            Style::default()
+                                .fg(Color::Yellow)
+                                .add_modifier(Modifier::BOLD),
+                        )),
+                    );
+                }
+                if sold {
+                    ctx.print(
+                        x,
+                        (high + row_step * 2.0).min(max_price),
+                        ratatui::text::Line::from(Span::styled(
+                            "S",
+                            Style::default()
+                                .fg(Color::Magenta)
+                                .add_modifier(Modifier::BOLD),
+                        )),
+                    );
+                }
+
+                if zoom == ChartZoom::Line {
+                    continue;
+                }
+
                 let body_top = open.max(close);
                 let body_bottom = open.min(close);
-                let body_char = if is_cursor { "▓" } else { "█" };
+                let body_char = match (zoom, is_cursor) {
+                    (ChartZoom::Thin, _) => "┃",
+                    (ChartZoom::Wide, true) => "▓▓▓",
+                    (ChartZoom::Wide, false) => "███",
+                    (_, true) => "▓",
+                    _ => "█",
+                };
+                // 宽K线的实体占三列，从中心左侧一列开始输出
+                let body_x = if zoom == ChartZoom::Wide { x - 1.0 } else { x };
 
                 if row_step <= 0.0 || final_range <= 0.0 {
                     // 无法计算步长，画一个点
@@ -344,14 +1772,14 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
                 // 从 low 到 high 逐行绘制
                 let mut y = low;
                 while y <= high + row_step * 0.5 {
-                    let ch = if y >= body_bottom - row_step * 0.5 && y <= body_top + row_step * 0.5
-                    {
-                        body_char
-                    } else {
-                        "│"
-                    };
+                    let (cx, ch) =
+                        if y >= body_bottom - row_step * 0.5 && y <= body_top + row_step * 0.5 {
+                            (body_x, body_char)
+                        } else {
+                            (x, "│")
+                        };
                     ctx.print(
-                        x,
+                        cx,
                         y,
                         ratatui::text::Line::from(Span::styled(ch, Style::default().fg(color))),
                     );
@@ -359,30 +1787,21 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
                 }
 
                 // 补充端点
-                ctx.print(
-                    x,
-                    low,
-                    ratatui::text::Line::from(Span::styled(
-                        if low >= body_bottom - row_step * 0.5 {
-                            body_char
-                        } else {
-                            "│"
-                        },
-                        Style::default().fg(color),
-                    )),
-                );
-                ctx.print(
-                    x,
-                    high,
-                    ratatui::text::Line::from(Span::styled(
-                        if high <= body_top + row_step * 0.5 {
-                            body_char
-                        } else {
-                            "│"
-                        },
-                        Style::default().fg(color),
-                    )),
-                );
+                for (y, in_body) in [
+                    (low, low >= body_bottom - row_step * 0.5),
+                    (high, high <= body_top + row_step * 0.5),
+                ] {
+                    let (cx, ch) = if in_body {
+                        (body_x, body_char)
+                    } else {
+                        (x, "│")
+                    };
+                    ctx.print(
+                        cx,
+                        y,
+                        ratatui::text::Line::from(Span::styled(ch, Style::default().fg(color))),
+                    );
+                }
             }
         });
 
@@ -393,7 +1812,7 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
     for i in 0..chart_height {
         let ratio = 1.0 - (i as f64 / (chart_height.saturating_sub(1).max(1)) as f64);
         let price_val = min_price + final_range * ratio;
-        if grid_step == 0 || (i as usize) % grid_step == 0 || i == chart_height - 1 {
+        if grid_step == 0 || (i as usize).is_multiple_of(grid_step) || i == chart_height - 1 {
             price_lines.push(Line::from(Span::styled(
                 format!("{:>9.2}", price_val),
                 Style::default().fg(Color::DarkGray),
@@ -406,28 +1825,29 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(price_axis, price_axis_area);
 
     // ── 绘制日期X轴（底部） ──
-    let mut date_str = String::new();
-    let date_interval = visible_data.len() / 5_usize.max(1);
-    let date_interval = date_interval.max(1);
+    // 按列写入标签，K线较窄时加大间隔避免标签重叠
+    let mut date_cols = vec![' '; chart_width as usize];
+    let date_interval = (visible_data.len() / 5)
+        .max(6usize.div_ceil(candle_width))
+        .max(1);
+    let mut next_free = 0;
     for (i, kline) in visible_data.iter().enumerate() {
-        if i % date_interval == 0 || i == visible_data.len() - 1 {
-            // 截取日期的月-日部分
-            let label = if kline.day.len() >= 10 {
-                &kline.day[5..10] // MM-DD
-            } else {
-                &kline.day
-            };
-            // 填充到蜡烛宽度
-            let padded = format!("{:<width$}", label, width = candle_width);
-            date_str.push_str(&padded);
+        let col = i * candle_width;
+        if col < next_free || (i % date_interval != 0 && i != visible_data.len() - 1) {
+            continue;
+        }
+        // 截取日期的月-日部分
+        let label = if kline.day.len() >= 10 {
+            &kline.day[5..10] // MM-DD
         } else {
-            for _ in 0..candle_width {
-                date_str.push(' ');
-            }
+            &kline.day
+        };
+        for (slot, ch) in date_cols.iter_mut().skip(col).zip(label.chars()) {
+            *slot = ch;
         }
+        next_free = col + label.chars().count() + 1;
     }
-    // 截断到区域宽度
-    let display_date: String = date_str.chars().take(chart_width as usize).collect();
+    let display_date: String = date_cols.into_iter().collect();
     let date_line = Paragraph::new(Line::from(Span::styled(
         display_date,
         Style::default().fg(Color::DarkGray),
@@ -435,9 +1855,9 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
     f.render_widget(date_line, date_axis_area);
 
     // ── 绘制游标信息覆盖层 ──
-    if let Some(cursor_idx) = app.kline_cursor {
+    if let Some(cursor_idx) = chart.cursor {
         if let Some(kline) = visible_data.get(cursor_idx) {
-            let color = if kline.close_f64() >= kline.open_f64() {
+            let color = if kline.is_bullish() {
                 COLOR_UP
             } else {
                 COLOR_DOWN
@@ -477,6 +1897,11 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
                     format!("{:.2} ", kline.close_f64()),
                     Style::default().fg(color),
                 ),
+                Span::styled("量:", Style::default().fg(Color::DarkGray)),
+                Span::styled(
+                    format!("{:.0}手 ", kline.volume_f64() / 100.0),
+                    Style::default().fg(Color::White),
+                ),
             ];
 
             // 添加均线信息
@@ -518,90 +1943,61 @@ fn draw_kline_chart(f: &mut Frame, app: &App, area: Rect) {
 
 /// 绘制自选股列表（含行情概览信息）
 fn draw_watchlist(f: &mut Frame, app: &mut App, area: Rect) {
-    let header = Row::new(vec![
-        Cell::from("  代码").style(Style::default().fg(Color::Cyan)),
-        Cell::from("名称").style(Style::default().fg(Color::White)),
-        Cell::from("当前价").style(Style::default().fg(Color::Yellow)),
-        Cell::from("涨跌额").style(Style::default().fg(Color::Yellow)),
-        Cell::from("涨跌幅").style(Style::default().fg(Color::Yellow)),
-        Cell::from("今开").style(Style::default().fg(Color::DarkGray)),
-        Cell::from("最高").style(Style::default().fg(COLOR_UP)),
-        Cell::from("最低").style(Style::default().fg(COLOR_DOWN)),
-        Cell::from("昨收").style(Style::default().fg(Color::DarkGray)),
-        Cell::from("成交量").style(Style::default().fg(Color::DarkGray)),
-    ])
-    .style(
-        Style::default()
-            .add_modifier(Modifier::BOLD),
-    )
-    .bottom_margin(0);
-
+    let columns = app.config.columns.clone();
     let rows: Vec<Row> = app
         .watchlist
         .iter()
         .enumerate()
         .map(|(i, symbol)| {
             let quote = app.quotes.get(i).and_then(|q| q.as_ref());
-
-            if let Some(q) = quote {
-                let change = q.change();
-                let change_pct = q.change_percent();
-                let sign = if change > 0.0 { "+" } else { "" };
-                let change_color = if change > 0.0 {
-                    COLOR_UP
-                } else if change < 0.0 {
-                    COLOR_DOWN
-                } else {
-                    COLOR_FLAT
-                };
-
-                // 今开 vs 昨收 的颜色
-                let open_color = if q.open > q.pre_close {
-                    COLOR_UP
-                } else if q.open < q.pre_close {
-                    COLOR_DOWN
-                } else {
-                    COLOR_FLAT
-                };
-
-                let is_active = i == app.active_index;
-                let mut style = Style::default();
-                if is_active {
-                    style = style.add_modifier(Modifier::UNDERLINED);
-                }
-
-                Row::new(vec![
-                    Cell::from(format!("  {}", symbol)).style(Style::default().fg(Color::Cyan)),
-                    Cell::from(q.name.clone()).style(Style::default().fg(Color::White)),
-                    Cell::from(format!("{:>8.2}", q.current)).style(Style::default().fg(change_color)),
-                    Cell::from(format!("{:>8}", format!("{}{:.2}", sign, change))).style(Style::default().fg(change_color)),
-                    Cell::from(format!("{:>8}", format!("{}{:.2}%", sign, change_pct))).style(Style::default().fg(change_color)),
-                    Cell::from(format!("{:>8.2}", q.open)).style(Style::default().fg(open_color)),
-                    Cell::from(format!("{:>8.2}", q.high)).style(Style::default().fg(COLOR_UP)),
-                    Cell::from(format!("{:>8.2}", q.low)).style(Style::default().fg(COLOR_DOWN)),
-                    Cell::from(format!("{:>8.2}", q.pre_close)).style(Style::default().fg(Color::White)),
-                    Cell::from(format!("{:>10}", q.volume_display())).style(Style::default().fg(Color::DarkGray)),
-                ])
-                .style(style)
+            let row = quote_row(symbol, quote, &columns);
+            if quote.is_some() && i == app.active_index {
+                row.style(Style::default().add_modifier(Modifier::UNDERLINED))
             } else {
-                Row::new(vec![
-                    Cell::from(format!("  {}", symbol)).style(Style::default().fg(Color::Cyan)),
-                    Cell::from("加载中...").style(Style::default().fg(Color::DarkGray)),
-                    Cell::from("      --"),
-                    Cell::from("      --"),
-                    Cell::from("      --"),
-                    Cell::from("      --"),
-                    Cell::from("      --"),
-                    Cell::from("      --"),
-                    Cell::from("      --"),
-                    Cell::from("        --"),
-                ])
-                .style(Style::default().fg(Color::DarkGray))
+                row
             }
         })
         .collect();
 
-    let widths = [
+    let table = Table::new(rows, quote_widths(&columns))
+        .header(quote_header(&columns))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        )
+        .highlight_symbol("▶ ");
+
+    f.render_stateful_widget(table, area, &mut app.watchlist_state);
+}
+
+/// 行情表头（自选股、选股结果共用），`columns` 为附加列
+fn quote_header(columns: &[QuoteField]) -> Row<'static> {
+    let mut header = vec![
+        Cell::from("  代码").style(Style::default().fg(Color::Cyan)),
+        Cell::from("名称").style(Style::default().fg(Color::White)),
+        Cell::from("当前价").style(Style::default().fg(Color::Yellow)),
+        Cell::from("涨跌额").style(Style::default().fg(Color::Yellow)),
+        Cell::from("涨跌幅").style(Style::default().fg(Color::Yellow)),
+        Cell::from("今开").style(Style::default().fg(Color::DarkGray)),
+        Cell::from("最高").style(Style::default().fg(COLOR_UP)),
+        Cell::from("最低").style(Style::default().fg(COLOR_DOWN)),
+        Cell::from("昨收").style(Style::default().fg(Color::DarkGray)),
+        Cell::from("成交量").style(Style::default().fg(Color::DarkGray)),
+        Cell::from("涨跌停").style(Style::default().fg(Color::DarkGray)),
+    ];
+    // 配置的附加列
+    header.extend(columns.iter().map(|c| {
+        Cell::from(format!("{:>8}", c.label())).style(Style::default().fg(Color::DarkGray))
+    }));
+    Row::new(header)
+        .style(Style::default().add_modifier(Modifier::BOLD))
+        .bottom_margin(0)
+}
+
+/// 行情表的列宽
+fn quote_widths(columns: &[QuoteField]) -> Vec<Constraint> {
+    let mut widths = vec![
         Constraint::Length(12),
         Constraint::Length(10),
         Constraint::Length(10),
@@ -612,32 +2008,145 @@ fn draw_watchlist(f: &mut Frame, app: &mut App, area: Rect) {
         Constraint::Length(10),
         Constraint::Length(10),
         Constraint::Length(10),
+        Constraint::Length(6),
     ];
+    widths.extend(columns.iter().map(|_| Constraint::Length(10)));
+    widths
+}
 
-    let table = Table::new(rows, widths)
-        .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(Color::Cyan)),
-        )
-        .highlight_symbol("▶ ");
+/// 行情表的一行，行情未加载时显示占位
+fn quote_row(symbol: &str, quote: Option<&StockQuote>, columns: &[QuoteField]) -> Row<'static> {
+    let Some(q) = quote else {
+        let mut cells = vec![
+            Cell::from(format!("  {}", symbol)).style(Style::default().fg(Color::Cyan)),
+            Cell::from("加载中...").style(Style::default().fg(Color::DarkGray)),
+            Cell::from("      --"),
+            Cell::from("      --"),
+            Cell::from("      --"),
+            Cell::from("      --"),
+            Cell::from("      --"),
+            Cell::from("      --"),
+            Cell::from("      --"),
+            Cell::from("        --"),
+            Cell::from(""),
+        ];
+        cells.extend(columns.iter().map(|_| Cell::from("        --")));
+        return Row::new(cells).style(Style::default().fg(Color::DarkGray));
+    };
 
-    f.render_stateful_widget(table, area, &mut app.watchlist_state);
+    let change = q.change();
+    let change_pct = q.change_percent();
+    let sign = if change > 0.0 { "+" } else { "" };
+    let change_color = if change > 0.0 {
+        COLOR_UP
+    } else if change < 0.0 {
+        COLOR_DOWN
+    } else {
+        COLOR_FLAT
+    };
+
+    // 今开 vs 昨收 的颜色
+    let open_color = if q.open > q.pre_close {
+        COLOR_UP
+    } else if q.open < q.pre_close {
+        COLOR_DOWN
+    } else {
+        COLOR_FLAT
+    };
+
+    let mut cells = vec![
+        Cell::from(format!("  {}", symbol)).style(Style::default().fg(Color::Cyan)),
+        Cell::from(q.name.clone()).style(Style::default().fg(Color::White)),
+        Cell::from(format!("{:>8.2}", q.current)).style(Style::default().fg(change_color)),
+        Cell::from(format!("{:>8}", format!("{}{:.2}", sign, change)))
+            .style(Style::default().fg(change_color)),
+        Cell::from(format!("{:>8}", format!("{}{:.2}%", sign, change_pct)))
+            .style(Style::default().fg(change_color)),
+        Cell::from(format!("{:>8.2}", q.open)).style(Style::default().fg(open_color)),
+        Cell::from(format!("{:>8.2}", q.high)).style(Style::default().fg(COLOR_UP)),
+        Cell::from(format!("{:>8.2}", q.low)).style(Style::default().fg(COLOR_DOWN)),
+        Cell::from(format!("{:>8.2}", q.pre_close)).style(Style::default().fg(Color::White)),
+        Cell::from(format!("{:>10}", q.volume_display()))
+            .style(Style::default().fg(Color::DarkGray)),
+        limit_badge(q.limit_state()),
+    ];
+    cells.extend(columns.iter().map(|c| {
+        Cell::from(format!("{:>10}", c.display(q))).style(Style::default().fg(Color::DarkGray))
+    }));
+    Row::new(cells)
+}
+
+/// 涨跌停徽标：封板为实底，炸板 / 翘板为彩色文字
+fn limit_badge(state: Option<LimitState>) -> Cell<'static> {
+    let Some(state) = state else {
+        return Cell::from("");
+    };
+    let color = if state.is_up() { COLOR_UP } else { COLOR_DOWN };
+    let style = match state {
+        LimitState::LimitUp | LimitState::LimitDown => Style::default().fg(Color::Black).bg(color),
+        LimitState::OpenedUp | LimitState::OpenedDown => Style::default().fg(color),
+    };
+    Cell::from(format!(" {} ", state.label())).style(style)
 }
 
 /// 绘制底部状态栏
 fn draw_status_bar(f: &mut Frame, app: &App, area: Rect) {
-    let status = Paragraph::new(Line::from(vec![
-        Span::styled(" ", Style::default()),
-        Span::styled(&app.status_message, Style::default().fg(Color::DarkGray)),
-    ]));
-    f.render_widget(status, area);
+    // 暂停标记和各市场状态徽标
+    let mut spans = Vec::new();
+    if app.timer.paused() {
+        spans.push(Span::styled(
+            " ⏸ 暂停 ",
+            Style::default().fg(Color::Black).bg(Color::Magenta),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    for (market, phase) in app.market_phases() {
+        let color = match phase {
+            MarketPhase::Open => Color::Cyan,
+            MarketPhase::PreMarket | MarketPhase::Break => Color::Yellow,
+            MarketPhase::Closed => Color::DarkGray,
+        };
+        spans.push(Span::styled(
+            format!(" {} {} ", market.label(), phase.label()),
+            Style::default().fg(Color::Black).bg(color),
+        ));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::styled(" ", Style::default()));
+    spans.push(Span::styled(
+        &app.status_message,
+        Style::default().fg(Color::DarkGray),
+    ));
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
 }
 
 /// 绘制添加股票的输入弹窗
 fn draw_input_popup(f: &mut Frame, app: &App) {
-    let area = centered_rect(50, 5, f.area());
+    let (title, width) = match app.input_mode {
+        InputMode::ScreenFilter => (
+            " 选股条件 (and / or / 括号, 如 pct > 5 and vr > 2) ".to_string(),
+            80,
+        ),
+        InputMode::ScreenName => (" 保存选股方案: 名称 ".to_string(), 50),
+        InputMode::PaperOrder => (
+            format!(
+                " 模拟{} {}: 数量 [限价] ",
+                app.paper_side.label(),
+                app.order_quote().map_or(String::new(), |q| q.symbol)
+            ),
+            50,
+        ),
+        InputMode::GridSymbol => (" 网格小图: 股票代码 (sh/sz/hk/gb_...) ".to_string(), 50),
+        InputMode::ReplayDate => (
+            format!(
+                " 历史回放 {}: 开始日期 (YYYY-MM-DD) ",
+                app.timeframe.label()
+            ),
+            50,
+        ),
+        _ => (" 添加股票 (sh/sz/hk/gb_...) ".to_string(), 50),
+    };
+    let area = centered_rect(width, 5, f.area());
     f.render_widget(Clear, area);
 
     let input = Paragraph::new(Line::from(vec![
@@ -652,7 +2161,7 @@ fn draw_input_popup(f: &mut Frame, app: &App) {
     ]))
     .block(
         Block::default()
-            .title(" 添加股票 (sh/sz/hk/gb_...) ")
+            .title(title)
             .borders(Borders::ALL)
             .border_style(Style::default().fg(Color::Yellow)),
     );
@@ -662,25 +2171,12 @@ fn draw_input_popup(f: &mut Frame, app: &App) {
 
 /// 绘制快捷键帮助弹窗
 fn draw_help_popup(f: &mut Frame, app: &App) {
-    let area = centered_rect(60, 20, f.area());
-    f.render_widget(Clear, area);
-
-    let timeframes = [
-        ("1", "5分钟", TimeFrame::Min5),
-        ("2", "15分钟", TimeFrame::Min15),
-        ("3", "30分钟", TimeFrame::Min30),
-        ("4", "60分钟", TimeFrame::Min60),
-        ("5", "日K", TimeFrame::Daily),
-        ("6", "周K", TimeFrame::Weekly),
-        ("7", "月K", TimeFrame::Monthly),
-    ];
-
-    // 构建周期行
+    // 构建周期行（按键 1-7 依次对应 TimeFrame::all()）
     let mut tf_spans: Vec<Span> = vec![Span::styled("  ", Style::default())];
-    for (key, label, tf) in &timeframes {
+    for (i, tf) in TimeFrame::all().iter().enumerate() {
         let is_active = app.timeframe == *tf;
         tf_spans.push(Span::styled(
-            format!(" {} ", key),
+            format!(" {} ", i + 1),
             Style::default()
                 .fg(Color::Yellow)
                 .add_modifier(Modifier::BOLD),
@@ -692,7 +2188,7 @@ fn draw_help_popup(f: &mut Frame, app: &App) {
         } else {
             Style::default().fg(Color::White)
         };
-        tf_spans.push(Span::styled(format!("{} ", label), style));
+        tf_spans.push(Span::styled(format!("{} ", tf.label()), style));
         if is_active {
             tf_spans.push(Span::styled("◀ ", Style::default().fg(Color::Cyan)));
         }
@@ -714,10 +2210,25 @@ fn draw_help_popup(f: &mut Frame, app: &App) {
             Span::styled("  r       ", Style::default().fg(Color::Yellow)),
             Span::styled("刷新数据", Style::default().fg(Color::White)),
         ]),
+        Line::from(vec![
+            Span::styled("  p       ", Style::default().fg(Color::Yellow)),
+            Span::styled("暂停 / 恢复自动刷新", Style::default().fg(Color::White)),
+        ]),
+        Line::from(vec![
+            Span::styled("  e / E   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "导出K线 / 导出自选股行情",
+                Style::default().fg(Color::White),
+            ),
+        ]),
         Line::from(vec![
             Span::styled("  f/Enter ", Style::default().fg(Color::Yellow)),
             Span::styled("切换全屏K线", Style::default().fg(Color::White)),
         ]),
+        Line::from(vec![
+            Span::styled("  D       ", Style::default().fg(Color::Yellow)),
+            Span::styled("网络诊断", Style::default().fg(Color::White)),
+        ]),
         Line::from(""),
         Line::from(vec![Span::styled(
             "  ── 自选股 ──",
@@ -752,6 +2263,83 @@ fn draw_help_popup(f: &mut Frame, app: &App) {
             Span::styled("  PgUp/Dn ", Style::default().fg(Color::Yellow)),
             Span::styled("滚动K线", Style::default().fg(Color::White)),
         ]),
+        Line::from(vec![
+            Span::styled("  H       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "板块热力图（Tab 行业/概念, Enter 成分股, a 加入自选）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  s       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "条件选股（Tab 方案, / 条件, w 保存, o 排序, a 加入自选）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  B       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "市场宽度（涨跌家数、涨跌停、涨跌幅分布）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  i       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "详情面板（行情、基本面、分红）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  P       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "模拟交易（b 买入 / s 卖出 / c 撤单，账户保存在 paper.json）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  R       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "历史回放（空格 播放/暂停，n 下一根，+/- 速度，b/s 模拟买卖，Esc 退出）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  x / C   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "将高亮股票加入/移出对比 / 走势对比图（涨跌幅叠加）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  + / -   ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "K线缩放（折线 / 细K线 / 标准 / 宽K线）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  g       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "多图网格（方向键选择，f 放大，1-7 周期，[ ] 切换股票，e 输入代码，F 复权，+ - 缩放）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  T       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "回测当前股票（K线标注买卖点，下方权益曲线）",
+                Style::default().fg(Color::White),
+            ),
+        ]),
+        Line::from(vec![
+            Span::styled("  F       ", Style::default().fg(Color::Yellow)),
+            Span::styled(
+                "切换复权: 不复权 / 前复权 / 后复权",
+                Style::default().fg(Color::White),
+            ),
+        ]),
         Line::from(vec![
             Span::styled("  Esc     ", Style::default().fg(Color::Yellow)),
             Span::styled("取消游标 / 退出全屏", Style::default().fg(Color::White)),
@@ -771,6 +2359,10 @@ fn draw_help_popup(f: &mut Frame, app: &App) {
         )]),
     ];
 
+    // 弹窗高度随内容行数变化（+2 为边框）
+    let area = centered_rect(60, help_lines.len() as u16 + 2, f.area());
+    f.render_widget(Clear, area);
+
     let help = Paragraph::new(help_lines)
         .block(
             Block::default()
@@ -783,6 +2375,98 @@ fn draw_help_popup(f: &mut Frame, app: &App) {
     f.render_widget(help, area);
 }
 
+/// 绘制网络诊断弹窗：各接口的请求数、错误、重试和延迟
+fn draw_diagnostics_popup(f: &mut Frame, app: &App) {
+    let stats = app.provider.stats();
+    let header = Row::new(["接口", "请求", "错误", "重试", "平均", "最近"]).style(
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows: Vec<Row> = stats
+        .iter()
+        .map(|s| {
+            let error_style = if s.errors > 0 {
+                Style::default().fg(COLOR_UP)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            Row::new([
+                Cell::from(s.endpoint.label()),
+                Cell::from(s.requests.to_string()),
+                Cell::from(s.errors.to_string()).style(error_style),
+                Cell::from(s.retries.to_string()),
+                Cell::from(s.avg_ms().map_or("-".to_string(), |ms| format!("{}ms", ms))),
+                Cell::from(if s.requests > 0 {
+                    format!("{}ms", s.last_ms)
+                } else {
+                    "-".to_string()
+                }),
+            ])
+        })
+        .collect();
+
+    // 最近错误单独列出（表格下方）
+    let errors: Vec<Line> = stats
+        .iter()
+        .filter_map(|s| {
+            s.last_error.as_ref().map(|e| {
+                Line::from(vec![
+                    Span::styled(
+                        format!(" {}: ", s.endpoint.label()),
+                        Style::default().fg(Color::Yellow),
+                    ),
+                    Span::styled(e.clone(), Style::default().fg(COLOR_UP)),
+                ])
+            })
+        })
+        .collect();
+    let empty = stats.is_empty();
+
+    let height = if empty {
+        3
+    } else {
+        rows.len() + errors.len() + 4
+    };
+    let area = centered_rect(70, height as u16, f.area());
+    f.render_widget(Clear, area);
+    let block = Block::default()
+        .title(" 网络诊断 (Esc 关闭) ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Yellow))
+        .style(Style::default().bg(Color::Black));
+
+    if empty {
+        let text = Paragraph::new(" 当前数据源不发起网络请求（回放模式）").block(block);
+        f.render_widget(text, area);
+        return;
+    }
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(rows.len() as u16 + 1),
+            Constraint::Min(0),
+        ])
+        .split(inner);
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(6),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(header);
+    f.render_widget(table, chunks[0]);
+    f.render_widget(Paragraph::new(errors), chunks[1]);
+}
+
 /// 创建居中矩形
 fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
     let popup_layout = Layout::default()
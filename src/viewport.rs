@@ -0,0 +1,70 @@
+/// 画布宽 `width` 列、每根K线占 `candle_width` 列时可显示的K线数量（不超过总数 `len`）
+pub fn visible_count(width: usize, candle_width: usize, len: usize) -> usize {
+    (width / candle_width.max(1)).min(len)
+}
+
+/// 第一根可见K线的下标：`offset` 为最右侧可见K线距最新一根的根数
+pub fn start_index(len: usize, count: usize, offset: usize) -> usize {
+    len.saturating_sub(count + offset)
+}
+
+/// 可见数量从 `old_count` 变为 `count`（缩放）后的 (偏移, 游标)
+///
+/// 有游标时游标所在的K线保持在窗口中的相对位置，否则保持最右侧的K线不变；
+/// 窗口不会越过最早的一根K线
+pub fn zoom(
+    len: usize,
+    offset: usize,
+    cursor: Option<usize>,
+    old_count: usize,
+    count: usize,
+) -> (usize, Option<usize>) {
+    let Some(cursor) = cursor else {
+        return (offset.min(len.saturating_sub(count)), None);
+    };
+    if count == 0 {
+        return (offset, Some(cursor));
+    }
+    let focus = start_index(len, old_count, offset) + cursor;
+    let pos = (cursor * count / old_count.max(1)).min(count - 1);
+    let start = focus.saturating_sub(pos).min(len.saturating_sub(count));
+    (len.saturating_sub(count + start), Some(focus - start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_count() {
+        assert_eq!(visible_count(90, 3, 120), 30);
+        assert_eq!(visible_count(90, 1, 50), 50);
+        assert_eq!(visible_count(4, 5, 50), 0);
+        assert_eq!(start_index(100, 30, 10), 60);
+        assert_eq!(start_index(20, 30, 10), 0);
+    }
+
+    #[test]
+    fn test_zoom_without_cursor_keeps_right_edge() {
+        // 放大：最右侧仍是距最新 10 根的那一根
+        assert_eq!(zoom(100, 10, None, 50, 20), (10, None));
+        // 缩小：窗口不能越过最早的K线
+        assert_eq!(zoom(100, 70, None, 20, 50), (50, None));
+        assert_eq!(zoom(30, 0, None, 30, 90), (0, None));
+    }
+
+    #[test]
+    fn test_zoom_with_cursor_keeps_focused_bar() {
+        // 可见 50..100，游标在第 75 根（窗口中间）；放大到 10 根后仍在窗口中间
+        let (offset, cursor) = zoom(100, 0, Some(25), 50, 10);
+        assert_eq!((offset, cursor), (20, Some(5)));
+        assert_eq!(start_index(100, 10, offset) + cursor.unwrap(), 75);
+        // 再缩小回 50 根，恢复原来的窗口
+        assert_eq!(zoom(100, offset, cursor, 10, 50), (0, Some(25)));
+
+        // 游标在最左侧时缩小，仍在最左侧
+        assert_eq!(zoom(100, 60, Some(0), 20, 60), (20, Some(0)));
+        // 缩小后数据不足一屏：窗口从第一根开始，游标指向同一根K线
+        assert_eq!(zoom(30, 0, Some(9), 10, 30), (0, Some(29)));
+    }
+}